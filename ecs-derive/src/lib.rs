@@ -1,28 +1,183 @@
-//! Provides a custom derive `Component` to allow to name components more easily.
+//! Provides a custom derive `Component` to allow to name components more easily. The derive
+//! accepts an optional `component` attribute:
+//!
+//! ```ignore
+//! #[derive(Component)]
+//! #[component(storage = "sparse", name = "bounding_volume", requires(Model))]
+//! pub struct BoundingVolume { ... }
+//! ```
+//!
+//! * `storage` selects the `StorageKind` (either `"dense"` or `"sparse"`).
+//! * `name` sets the stable name of the component, used by save files and the debug shell. It
+//!   defaults to the identifier of the type.
+//! * `requires` lists the component types that an entity must have before this component may
+//!   be added. The types are given as paths (e.g. `requires(Model, shapes::Sphere)`) that must
+//!   resolve where the derive is used.
 
 extern crate proc_macro;
 #[macro_use]
 extern crate quote;
+#[macro_use]
 extern crate syn;
 
 use proc_macro::TokenStream;
+use syn::{Attribute, Ident, Lit, Path};
+use syn::punctuated::Punctuated;
+use syn::synom::Synom;
 
 /// Implements the `Component` custom derive.
-#[proc_macro_derive(Component)]
+#[proc_macro_derive(Component, attributes(component))]
 pub fn derive_component(input: TokenStream) -> TokenStream {
     // Parse the token stream
     let ast: syn::DeriveInput = syn::parse(input).expect("Could not parse the input token stream");
 
     // Get the name of the type.
     let name = &ast.ident;
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
 
-    // Generate the impl.
-    let gen = quote! {
-        use ecs::ComponentTrait;
+    // Parse the component options.
+    let options = ComponentOptions::from_attributes(&ast.attrs);
+
+    // Unlike `std::any::type_name`, the bare identifier does not depend on the module path or
+    // the compiler version.
+    let component_name = options.name.unwrap_or_else(|| name.to_string());
+    let name_fn = quote! {
+        fn name() -> &'static str {
+            #component_name
+        }
+    };
+
+    let storage_fn = options.storage.map(|s| {
+        let kind = match s.as_str() {
+            "dense" => quote! { ::ecs::StorageKind::Dense },
+            "sparse" => quote! { ::ecs::StorageKind::Sparse },
+            s => panic!("Unknown storage kind '{}', expected 'dense' or 'sparse'", s),
+        };
+        quote! {
+            fn storage_kind() -> ::ecs::StorageKind {
+                #kind
+            }
+        }
+    });
+
+    let requires_fn = if options.requires.is_empty() {
+        None
+    } else {
+        let requirements = options
+            .requires
+            .iter()
+            .map(|r| {
+                quote! {
+                    (::std::any::TypeId::of::<#r>(), <#r as ::ecs::ComponentTrait>::name())
+                }
+            })
+            .collect::<Vec<_>>();
+        Some(quote! {
+            fn required_components() -> Vec<(::std::any::TypeId, &'static str)> {
+                vec![#(#requirements),*]
+            }
+        })
+    };
 
-        impl ComponentTrait for #name {}
+    // Generate the impl. Fully qualified paths are used, such that the derive may be used
+    // multiple times within one module.
+    let gen = quote! {
+        impl #impl_generics ::ecs::ComponentTrait for #name #ty_generics #where_clause {
+            #name_fn
+            #storage_fn
+            #requires_fn
+        }
     };
 
     // Return the generated impl
     gen.into()
 }
+
+/// Holds the options given by the `component` attribute.
+#[derive(Default)]
+struct ComponentOptions {
+    name: Option<String>,
+    storage: Option<String>,
+    requires: Vec<Path>,
+}
+
+impl ComponentOptions {
+    /// Collects the options from all `component` attributes.
+    fn from_attributes(attrs: &[Attribute]) -> Self {
+        let mut options = ComponentOptions::default();
+
+        for attr in attrs {
+            let is_component = attr.path.segments.len() == 1
+                && attr.path.segments.iter().all(|s| s.ident == "component");
+            if !is_component {
+                continue;
+            }
+
+            let list: OptionList = syn::parse2(attr.tts.clone())
+                .expect("The component attribute must be of the form #[component(...)]");
+
+            for option in list.0 {
+                match (option.ident.as_ref(), option.value) {
+                    ("name", OptionValue::Lit(ref lit)) => {
+                        options.name = Some(string_literal(lit, "name"));
+                    }
+                    ("storage", OptionValue::Lit(ref lit)) => {
+                        options.storage = Some(string_literal(lit, "storage"));
+                    }
+                    ("requires", OptionValue::Paths(paths)) => options.requires.extend(paths),
+                    ("requires", _) => panic!("The requires option only accepts type paths"),
+                    _ => panic!(
+                        "Unknown component option, expected one of 'name', 'storage' or 'requires'"
+                    ),
+                }
+            }
+        }
+
+        options
+    }
+}
+
+/// Holds the parenthesized, comma-separated options of a `component` attribute.
+struct OptionList(Vec<ComponentOption>);
+
+impl Synom for OptionList {
+    named!(parse -> Self, map!(
+        parens!(call!(Punctuated::<ComponentOption, Token![,]>::parse_terminated)),
+        |(_, options)| OptionList(options.into_iter().collect())
+    ));
+}
+
+/// Holds a single option, which is either of the form `ident = "literal"` or `ident(Path, ...)`.
+struct ComponentOption {
+    ident: Ident,
+    value: OptionValue,
+}
+
+/// Holds the value of a single option.
+enum OptionValue {
+    Lit(Lit),
+    Paths(Vec<Path>),
+}
+
+impl Synom for ComponentOption {
+    named!(parse -> Self, do_parse!(
+        ident: syn!(Ident) >>
+        value: alt!(
+            do_parse!(punct!(=) >> lit: syn!(Lit) >> (OptionValue::Lit(lit)))
+            |
+            map!(
+                parens!(call!(Punctuated::<Path, Token![,]>::parse_terminated)),
+                |(_, paths)| OptionValue::Paths(paths.into_iter().collect())
+            )
+        ) >>
+        (ComponentOption { ident, value })
+    ));
+}
+
+/// Extracts the value of a string literal given to the specified option.
+fn string_literal(lit: &Lit, option: &str) -> String {
+    match *lit {
+        Lit::Str(ref s) => s.value(),
+        _ => panic!("The option '{}' must be a string literal", option),
+    }
+}
@@ -0,0 +1,68 @@
+extern crate ecs;
+#[macro_use]
+extern crate ecs_derive;
+
+use ecs::{Assembly, ComponentTrait, EcsError, StorageKind};
+
+#[derive(Debug, Component)]
+struct Plain;
+
+#[derive(Debug, Component)]
+#[component(name = "model")]
+struct Model;
+
+#[derive(Debug, Component)]
+#[component(storage = "sparse", name = "bounding_volume", requires(Model))]
+struct BoundingVolume;
+
+#[derive(Debug, Component)]
+#[component(storage = "dense")]
+struct Generic<T: 'static>(T);
+
+mod shapes {
+    #[derive(Debug, Component)]
+    #[component(name = "sphere")]
+    pub struct Sphere;
+}
+
+#[derive(Debug, Component)]
+#[component(requires(Model, shapes::Sphere))]
+struct Collider;
+
+#[test]
+fn test_defaults() {
+    assert_eq!(Plain::storage_kind(), StorageKind::Dense);
+    assert_eq!(Plain::name(), "Plain");
+    assert_eq!(Generic::<u32>::name(), "Generic");
+    assert!(Plain::required_components().is_empty());
+    assert_eq!(Generic::<u32>::storage_kind(), StorageKind::Dense);
+}
+
+#[test]
+fn test_options() {
+    assert_eq!(Model::name(), "model");
+    assert_eq!(BoundingVolume::name(), "bounding_volume");
+    assert_eq!(BoundingVolume::storage_kind(), StorageKind::Sparse);
+
+    let requirements = BoundingVolume::required_components();
+    assert_eq!(requirements.len(), 1);
+    assert_eq!(requirements[0].1, "model");
+
+    let requirements = Collider::required_components();
+    assert_eq!(requirements.len(), 2);
+    assert_eq!(requirements[1].1, "sphere");
+}
+
+#[test]
+fn test_requirements() {
+    let mut a = Assembly::new();
+    let e = a.create_entity();
+
+    match a.add_component(&e, BoundingVolume) {
+        Err(EcsError::MissingRequiredComponent(_, "bounding_volume", "model")) => (),
+        r => panic!("Expected a missing required component error, got {:?} instead", r),
+    }
+    a.add_component(&e, Model).unwrap();
+    a.add_component(&e, BoundingVolume).unwrap();
+    assert_eq!(a.component_names(&e).unwrap(), vec!["bounding_volume", "model"]);
+}
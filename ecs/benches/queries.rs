@@ -0,0 +1,116 @@
+//! Measures the cost of `Assembly` queries. Joins should scale with the number of matching
+//! entities rather than with the total number of entities.
+
+#[macro_use]
+extern crate bencher;
+extern crate ecs;
+
+use bencher::Bencher;
+use ecs::{Assembly, ComponentTrait, StorageKind};
+
+const ENTITIES: usize = 10_000;
+
+struct Position(f32);
+
+impl ComponentTrait for Position {}
+
+struct Velocity(f32);
+
+impl ComponentTrait for Velocity {}
+
+struct Tag;
+
+impl ComponentTrait for Tag {
+    fn storage_kind() -> StorageKind {
+        StorageKind::Sparse
+    }
+}
+
+/// Creates `ENTITIES` entities with `Position` and `Velocity`, of which every `stride`-th entity
+/// additionally carries a `Tag`.
+fn populate(stride: usize) -> Assembly {
+    let mut a = Assembly::new();
+    for i in 0..ENTITIES {
+        let e = a.create_entity();
+        a.add_component(&e, Position(i as f32)).unwrap();
+        a.add_component(&e, Velocity(1.0)).unwrap();
+        if i % stride == 0 {
+            a.add_component(&e, Tag).unwrap();
+        }
+    }
+    a
+}
+
+fn r2_all(b: &mut Bencher) {
+    let a = populate(1);
+    b.iter(|| {
+        a.r2::<Position, Velocity>()
+            .into_iter()
+            .map(|(_, p, v)| p.0 + v.0)
+            .sum::<f32>()
+    })
+}
+
+fn r2_tagged_10(b: &mut Bencher) {
+    let a = populate(ENTITIES / 10);
+    b.iter(|| a.r2::<Position, Tag>().len())
+}
+
+fn r2_tagged_100(b: &mut Bencher) {
+    let a = populate(ENTITIES / 100);
+    b.iter(|| a.r2::<Position, Tag>().len())
+}
+
+fn r2_tagged_1000(b: &mut Bencher) {
+    let a = populate(ENTITIES / 1000);
+    b.iter(|| a.r2::<Position, Tag>().len())
+}
+
+fn r2_iter_all(b: &mut Bencher) {
+    let a = populate(1);
+    b.iter(|| {
+        a.r2_iter::<Position, Velocity>()
+            .map(|(_, p, v)| p.0 + v.0)
+            .sum::<f32>()
+    })
+}
+
+fn w1_all(b: &mut Bencher) {
+    let mut a = populate(1);
+    b.iter(|| {
+        for (_, p) in a.w1::<Position>() {
+            p.0 += 1.0;
+        }
+    })
+}
+
+fn w1_iter_all(b: &mut Bencher) {
+    let mut a = populate(1);
+    b.iter(|| {
+        for (_, p) in a.w1_iter::<Position>() {
+            p.0 += 1.0;
+        }
+    })
+}
+
+fn w1r1_iter_all(b: &mut Bencher) {
+    let mut a = populate(1);
+    b.iter(|| {
+        for (_, p, v) in a.w1r1_iter::<Position, Velocity>() {
+            p.0 += v.0;
+        }
+    })
+}
+
+benchmark_group!(
+    benches,
+    r2_all,
+    r2_tagged_10,
+    r2_tagged_100,
+    r2_tagged_1000,
+    r2_iter_all,
+    w1_all,
+    w1_iter_all,
+    w1r1_iter_all
+);
+benchmark_main!(benches);
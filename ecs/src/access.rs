@@ -0,0 +1,134 @@
+use std::any::{type_name, TypeId};
+
+use component_group::ComponentTrait;
+
+/// Declares which component types a system reads or writes, and which parts of the auxiliary
+/// object it accesses. The `World` uses these declarations to run systems without conflicting
+/// access concurrently, if the `ExecutionMode::Parallel` is selected.
+///
+/// Only component types that are `Send` and `Sync` may be declared, because their storages are
+/// handed to other threads. Likewise, auxiliary parts must be `Send`. A system that runs
+/// concurrently may only access the declared component types and auxiliary parts; any other
+/// access panics.
+#[derive(Debug, Clone, Default)]
+pub struct Access {
+    exclusive: bool,
+    reads: Vec<(TypeId, &'static str)>,
+    writes: Vec<(TypeId, &'static str)>,
+    aux: Vec<(TypeId, &'static str)>,
+}
+
+impl Access {
+    /// Creates a new declaration that does not access anything.
+    pub fn new() -> Self {
+        Default::default()
+    }
+    /// Creates a declaration that conflicts with every other system. Systems with exclusive
+    /// access always run on their own and receive the entire `Assembly`.
+    pub fn exclusive() -> Self {
+        Access {
+            exclusive: true,
+            ..Default::default()
+        }
+    }
+    /// Declares immutable access to the components of the specified type.
+    pub fn read<C: ComponentTrait + Send + Sync>(mut self) -> Self {
+        self.reads.push((TypeId::of::<C>(), type_name::<C>()));
+        self
+    }
+    /// Declares mutable access to the components of the specified type. This includes adding
+    /// and removing components of that type.
+    pub fn write<C: ComponentTrait + Send + Sync>(mut self) -> Self {
+        self.writes.push((TypeId::of::<C>(), type_name::<C>()));
+        self
+    }
+    /// Declares access to the `AuxPart` of the specified type within the auxiliary object. Parts
+    /// are moved to the system that declares them, thus declaring a part implies mutable access.
+    pub fn aux<T: Send + 'static>(mut self) -> Self {
+        self.aux.push((TypeId::of::<T>(), type_name::<T>()));
+        self
+    }
+    /// Returns `true` if the declaration conflicts with every other system.
+    pub fn is_exclusive(&self) -> bool {
+        self.exclusive
+    }
+    /// Returns `true` if the `AuxPart` of the specified type is declared.
+    pub fn declares_aux<T: 'static>(&self) -> bool {
+        self.aux.iter().any(|&(t, _)| t == TypeId::of::<T>())
+    }
+    /// Returns `true` if the two declarations may not run concurrently, that is if either is
+    /// exclusive, if either writes a component type the other accesses, or if both access the
+    /// same auxiliary part.
+    pub fn conflicts_with(&self, other: &Access) -> bool {
+        if self.exclusive || other.exclusive {
+            return true;
+        }
+
+        let components = |a: &Access, b: &Access| {
+            a.writes
+                .iter()
+                .any(|&(t, _)| b.reads.iter().chain(&b.writes).any(|&(u, _)| t == u))
+        };
+        let aux = self.aux
+            .iter()
+            .any(|&(t, _)| other.aux.iter().any(|&(u, _)| t == u));
+
+        components(self, other) || components(other, self) || aux
+    }
+    /// Returns the component types declared for reading, but not for writing.
+    pub(crate) fn reads(&self) -> impl Iterator<Item = TypeId> + '_ {
+        self.reads
+            .iter()
+            .map(|&(t, _)| t)
+            .filter(move |t| !self.writes.iter().any(|&(u, _)| *t == u))
+    }
+    /// Returns the component types declared for writing.
+    pub(crate) fn writes(&self) -> impl Iterator<Item = TypeId> + '_ {
+        self.writes.iter().map(|&(t, _)| t)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    struct Position;
+    impl ComponentTrait for Position {}
+
+    struct Velocity;
+    impl ComponentTrait for Velocity {}
+
+    struct Clock;
+
+    #[test]
+    fn test_conflicts() {
+        let a = Access::new().write::<Position>().read::<Velocity>();
+        let b = Access::new().read::<Velocity>();
+        let c = Access::new().read::<Position>();
+        let d = Access::new().aux::<Clock>();
+        let e = Access::new().read::<Velocity>().aux::<Clock>();
+
+        assert!(!a.conflicts_with(&b));
+        assert!(a.conflicts_with(&c));
+        assert!(c.conflicts_with(&a));
+        assert!(!b.conflicts_with(&c));
+        assert!(d.conflicts_with(&e));
+        assert!(!a.conflicts_with(&d));
+        assert!(!b.conflicts_with(&e));
+        assert!(e.declares_aux::<Clock>());
+        assert!(!a.declares_aux::<Clock>());
+        assert!(Access::exclusive().conflicts_with(&Access::new()));
+        assert!(!Access::new().conflicts_with(&Access::new()));
+    }
+
+    #[test]
+    fn test_reads_and_writes() {
+        let a = Access::new()
+            .read::<Position>()
+            .read::<Velocity>()
+            .write::<Position>();
+
+        assert_eq!(a.reads().collect::<Vec<_>>(), vec![TypeId::of::<Velocity>()]);
+        assert_eq!(a.writes().collect::<Vec<_>>(), vec![TypeId::of::<Position>()]);
+    }
+}
@@ -1,14 +1,92 @@
-use std::collections::HashMap;
+use std::any::{type_name, TypeId};
+use std::collections::{HashMap, HashSet};
+use std::cmp;
+use std::mem;
 
 use error::EcsError;
 use entity::Entity;
 use component_group::{ComponentGroup, ComponentTrait};
+use storage::{AnyStorage, Storage};
+use query::QueryExt;
+use command::CommandBuffer;
+use access::Access;
+use event::EventStatistics;
+use timer::Timers;
+use scene::{ComponentRegistry, Scene};
+use snapshot::Snapshot;
 
-/// Defines a collection of `Entity`s and their components.
-#[derive(Default, Debug)]
+const DOWNCAST_ERROR: &str = "Was unable to downcast the requested storage from Any.";
+/// Specifies the generation of newly allocated entity indices. Generation zero is reserved for
+/// the default `Entity`.
+const FIRST_GENERATION: u32 = 1;
+
+/// Describes a structural change to an `Entity` that concerns an observed component type. The
+/// `World` uses these to invoke lifecycle hooks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Lifecycle {
+    Added(TypeId, Entity),
+    Removed(TypeId, Entity),
+}
+
+/// Defines a collection of `Entity`s and their components. Components are stored per type, such
+/// that queries only visit entities that have the requested components. Additionally, the
+/// `Assembly` tracks the tick at which components were added, mutably accessed or removed, such
+/// that systems can react to changes since their last run.
+#[derive(Debug)]
 pub struct Assembly {
-    base_entity: Entity,
-    entities: HashMap<Entity, ComponentGroup>,
+    /// Holds the highest generation issued so far for each entity index.
+    generations: Vec<u32>,
+    /// Holds the generation of the live `Entity` at each index, if any. Usually, this is the
+    /// highest generation issued, unless an older `Entity` was revived.
+    alive: Vec<Option<u32>>,
+    /// Holds the indices of destroyed entities, which will be reused by `create_entity`.
+    free_indices: Vec<u32>,
+    /// Holds the number of live entities.
+    entity_count: usize,
+    /// Holds one storage per component type.
+    storages: HashMap<TypeId, Box<dyn AnyStorage>>,
+    /// Holds the current change tick.
+    tick: u64,
+    /// Holds the removed components per type, along with the tick of removal.
+    removed: HashMap<TypeId, Vec<(Entity, u64)>>,
+    /// Holds the component types for which lifecycle events are recorded.
+    observed: HashSet<TypeId>,
+    /// Holds the lifecycle events that have not yet been processed by the `World`.
+    lifecycle: Vec<Lifecycle>,
+    /// Holds the command buffers that await application by the `World`.
+    deferred: Vec<CommandBuffer>,
+    /// Holds read-only storages that are owned by the parent of a restricted `Assembly`.
+    shared: HashMap<TypeId, *const dyn AnyStorage>,
+    /// Holds the declared access of a restricted `Assembly`, which is handed to a system that
+    /// runs concurrently with others.
+    access: Option<Access>,
+    /// Holds the event statistics of the previous frame.
+    statistics: EventStatistics,
+    /// Holds the events scheduled for later dispatch.
+    timers: Timers,
+}
+
+impl Default for Assembly {
+    /// Creates an empty `Assembly`. The tick starts at one, such that systems may use zero to
+    /// refer to the time before any changes.
+    fn default() -> Self {
+        Assembly {
+            generations: Vec::new(),
+            alive: Vec::new(),
+            free_indices: Vec::new(),
+            entity_count: 0,
+            storages: HashMap::new(),
+            tick: 1,
+            removed: HashMap::new(),
+            observed: HashSet::new(),
+            lifecycle: Vec::new(),
+            deferred: Vec::new(),
+            shared: HashMap::new(),
+            access: None,
+            statistics: EventStatistics::new(),
+            timers: Timers::new(),
+        }
+    }
 }
 
 impl Assembly {
@@ -16,87 +94,245 @@ impl Assembly {
     pub fn new() -> Self {
         Default::default()
     }
-    /// Creates a new `Entity` and registers it with the `Assembly`.
+    /// Creates a new `Entity` and registers it with the `Assembly`. Indices of previously
+    /// destroyed entities are reused with an incremented generation. Generations start at one,
+    /// such that the default `Entity` never refers to a live `Entity`.
     pub fn create_entity(&mut self) -> Entity {
-        let e = self.base_entity.clone();
-        self.base_entity.increment();
-        self.entities.insert(e.clone(), ComponentGroup::new());
-        e
+        self.assert_unrestricted();
+        self.entity_count += 1;
+        match self.free_indices.pop() {
+            Some(index) => {
+                let i = index as usize;
+                self.generations[i] += 1;
+                self.alive[i] = Some(self.generations[i]);
+                Entity::from_parts(index, self.generations[i])
+            }
+            None => {
+                let index = self.generations.len() as u32;
+                self.generations.push(FIRST_GENERATION);
+                self.alive.push(Some(FIRST_GENERATION));
+                Entity::from_parts(index, FIRST_GENERATION)
+            }
+        }
     }
-    /// Deletes the specified `Entity` from the `Assembly` and may return a `ComponentGroup`.
+    /// Deletes the specified `Entity` from the `Assembly` and may return its components as a
+    /// `ComponentGroup`. The index of the `Entity` is subsequently free for reuse.
     pub fn destroy_entity(&mut self, entity: &Entity) -> Option<ComponentGroup> {
-        self.entities.remove(entity)
+        self.assert_unrestricted();
+        if self.verify_entity(entity).is_err() {
+            return None;
+        }
+
+        let index = entity.index();
+        let mut group = ComponentGroup::new();
+        for (type_id, storage) in &mut self.storages {
+            if let Some(c) = storage.remove_any(index) {
+                group.insert_any(*type_id, c);
+                self.removed
+                    .entry(*type_id)
+                    .or_default()
+                    .push((*entity, self.tick));
+                if self.observed.contains(type_id) {
+                    self.lifecycle.push(Lifecycle::Removed(*type_id, *entity));
+                }
+            }
+        }
+
+        // Once the generation is exhausted, the index is retired for good, such that no stale
+        // handle becomes valid again.
+        self.alive[index] = None;
+        self.entity_count -= 1;
+        if self.generations[index] < u32::MAX {
+            self.free_indices.push(index as u32);
+        }
+        Some(group)
+    }
+    /// Recreates the specified `Entity` with its original index and generation, e.g. to roll
+    /// back to a snapshot. Errors with `EcsError::OccupiedEntityIndex` if another `Entity`
+    /// currently occupies the index. Handles issued for the index since are not reissued.
+    pub(crate) fn revive_entity(&mut self, entity: &Entity) -> Result<(), EcsError> {
+        self.assert_unrestricted();
+        let index = entity.index();
+        match self.alive.get(index) {
+            Some(&Some(g)) if g == entity.generation() => return Ok(()),
+            Some(&Some(g)) => {
+                let occupant = Entity::from_parts(index as u32, g);
+                return Err(EcsError::OccupiedEntityIndex(*entity, occupant));
+            }
+            _ => (),
+        }
+
+        while self.generations.len() <= index {
+            self.free_indices.push(self.generations.len() as u32);
+            // No generation was issued for the skipped index yet.
+            self.generations.push(FIRST_GENERATION - 1);
+            self.alive.push(None);
+        }
+        self.free_indices.retain(|&i| i as usize != index);
+        self.entity_count += 1;
+        self.generations[index] = cmp::max(self.generations[index], entity.generation());
+        self.alive[index] = Some(entity.generation());
+        Ok(())
     }
-    /// Returns `true` if the specified `Entity` is known to the `Assembly`.
+    /// Returns `true` if the specified `Entity` is alive within the `Assembly`. Stale handles to
+    /// destroyed entities are rejected.
     pub fn has_entity(&self, entity: &Entity) -> bool {
-        self.entities.contains_key(entity)
+        self.verify_entity(entity).is_ok()
+    }
+    /// Determines whether the specified `Entity` is alive. Errors with `EcsError::DeadEntity` if
+    /// the handle refers to a destroyed `Entity`, and with `EcsError::EntityNotFound` if the
+    /// handle was never issued by the `Assembly`.
+    pub fn verify_entity(&self, entity: &Entity) -> Result<(), EcsError> {
+        let index = entity.index();
+        match self.alive.get(index) {
+            Some(&Some(g)) if entity.generation() == g => Ok(()),
+            Some(_) if entity.generation() <= self.generations[index] => {
+                Err(EcsError::DeadEntity(*entity))
+            }
+            _ => Err(EcsError::EntityNotFound(*entity)),
+        }
     }
     /// Returns the number of `Entity`s in the `Assembly`.
     pub fn entity_count(&self) -> usize {
-        self.entities.len()
+        self.entity_count
+    }
+    /// Returns an iterator over all live `Entity`s in the order of their indices.
+    pub fn entities(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.alive
+            .iter()
+            .enumerate()
+            .filter_map(|(i, a)| a.map(|g| Entity::from_parts(i as u32, g)))
+    }
+    /// Serializes the components of all entities that are known to the registry.
+    pub fn save_scene<X>(&self, registry: &ComponentRegistry<X>) -> Result<Scene, EcsError> {
+        registry.save(self)
+    }
+    /// Creates the entities described by the scene and returns them in the order of the scene.
+    /// The context is passed on to the functions that rebuild resource-backed components.
+    pub fn load_scene<X>(
+        &mut self,
+        registry: &ComponentRegistry<X>,
+        scene: &Scene,
+        context: &mut X,
+    ) -> Result<Vec<Entity>, EcsError> {
+        registry.load(self, scene, context)
+    }
+    /// Captures the serializable components of all entities at the current update tick.
+    pub fn snapshot<X>(&self, registry: &ComponentRegistry<X>) -> Result<Snapshot, EcsError> {
+        registry.snapshot(self)
+    }
+    /// Rolls the entities and their serializable components back to the state of the snapshot
+    /// (see `ComponentRegistry::restore`).
+    pub fn restore<X>(
+        &mut self,
+        registry: &ComponentRegistry<X>,
+        snapshot: &Snapshot,
+        context: &mut X,
+    ) -> Result<(), EcsError> {
+        registry.restore(self, snapshot, context)
     }
-    /// Adds a component to the group assigned to the specified `Entity`.
+    /// Adds a component to the specified `Entity`. If available, returns the previous component
+    /// of the same type. Errors with `EcsError::MissingRequiredComponent` if the `Entity` lacks
+    /// any of the components required by `C`.
     pub fn add_component<C>(&mut self, entity: &Entity, component: C) -> Result<Option<C>, EcsError>
     where
         C: ComponentTrait,
     {
-        self.entities
-            .get_mut(entity)
-            .ok_or_else(|| EcsError::EntityNotFound(entity.clone()))
-            .map(|g| g.insert(component))
+        self.verify_entity(entity)?;
+        for (type_id, name) in C::required_components() {
+            let present = self.any_storage(&type_id)
+                .map(|s| s.contains_index(entity.index()))
+                .unwrap_or_default();
+            if !present {
+                return Err(EcsError::MissingRequiredComponent(*entity, C::name(), name));
+            }
+        }
+        let tick = self.tick;
+        let previous = self.storage_or_insert::<C>()
+            .insert(entity.index(), component, tick);
+        if previous.is_none() && self.observed.contains(&TypeId::of::<C>()) {
+            self.lifecycle
+                .push(Lifecycle::Added(TypeId::of::<C>(), *entity));
+        }
+        Ok(previous)
     }
-    /// Removes the component of the secified type from the group assigned to the specified `Entity`.
+    /// Removes the component of the secified type from the specified `Entity`.
     pub fn remove_component<C>(&mut self, entity: &Entity) -> Result<Option<C>, EcsError>
     where
         C: ComponentTrait,
     {
-        self.entities
-            .get_mut(entity)
-            .ok_or_else(|| EcsError::EntityNotFound(entity.clone()))
-            .map(|g| g.remove::<C>())
+        self.verify_entity(entity)?;
+        let component = self.storage_mut::<C>()
+            .and_then(|s| s.remove(entity.index()));
+        if component.is_some() {
+            let type_id = TypeId::of::<C>();
+            self.removed
+                .entry(type_id)
+                .or_default()
+                .push((*entity, self.tick));
+            if self.observed.contains(&type_id) {
+                self.lifecycle.push(Lifecycle::Removed(type_id, *entity));
+            }
+        }
+        Ok(component)
     }
     /// Checks whether the supplied `Entity` has the specified component type.
     pub fn has_component<C>(&self, entity: &Entity) -> bool
     where
         C: ComponentTrait,
     {
-        self.entities
-            .get(entity)
-            .map(|g| g.has::<C>())
+        self.has_entity(entity) && self.storage::<C>()
+            .map(|s| s.contains(entity.index()))
             .unwrap_or_default()
     }
+    /// Returns the names of all components of the specified `Entity`, in alphabetical order.
+    pub fn component_names(&self, entity: &Entity) -> Result<Vec<&'static str>, EcsError> {
+        self.verify_entity(entity)?;
+        let mut names = self.storages
+            .values()
+            .map(|s| &**s)
+            .chain(self.shared.keys().filter_map(|t| self.any_storage(t)))
+            .filter(|s| s.contains_index(entity.index()))
+            .map(|s| s.component_name())
+            .collect::<Vec<_>>();
+        names.sort();
+        Ok(names)
+    }
     /// Borrows a single component from the specified `Entity`.
     pub fn borrow_component<C>(&self, entity: &Entity) -> Result<&C, EcsError>
     where
         C: ComponentTrait,
     {
-        self.entities
-            .get(entity)
-            .ok_or_else(|| EcsError::EntityNotFound(entity.clone()))
-            .and_then(|g| g.borrow::<C>())
+        self.verify_entity(entity)?;
+        self.storage::<C>()
+            .and_then(|s| s.get(entity.index()))
+            .ok_or(EcsError::ComponentNotFound)
     }
     /// Mutably borrows a single component from the specified `Entity`.
     pub fn borrow_component_mut<C>(&mut self, entity: &Entity) -> Result<&mut C, EcsError>
     where
         C: ComponentTrait,
     {
-        self.entities
-            .get_mut(entity)
-            .ok_or_else(|| EcsError::EntityNotFound(entity.clone()))
-            .and_then(|g| g.borrow_mut::<C>())
+        self.verify_entity(entity)?;
+        let tick = self.tick;
+        self.storage_mut::<C>()
+            .and_then(|s| s.get_mut(entity.index(), tick))
+            .ok_or(EcsError::ComponentNotFound)
+    }
+    /// Mutably iterates over all instances of the specified component type.
+    pub fn w1_iter<C: ComponentTrait>(&mut self) -> impl Iterator<Item = (Entity, &mut C)> + '_ {
+        self.assert_access(TypeId::of::<C>(), type_name::<C>(), true);
+        let alive = &self.alive;
+        let tick = self.tick;
+        self.storages
+            .get_mut(&TypeId::of::<C>())
+            .into_iter()
+            .flat_map(move |s| downcast_mut::<C>(&mut **s).iter_mut(tick))
+            .map(move |(i, c)| (Entity::from_parts(i as u32, alive[i].unwrap_or_default()), c))
     }
     /// Provides mutable access to all instances of the specified component type.
     pub fn w1<C: ComponentTrait>(&mut self) -> Vec<(Entity, &mut C)> {
-        self.entities
-            .iter_mut()
-            .filter(|&(_, ref g)| g.has::<C>())
-            .map(|(e, g)| {
-                (
-                    e.clone(),
-                    g.borrow_mut::<C>().unwrap_or_else(|_| unreachable!()),
-                )
-            })
-            .collect()
+        self.w1_iter::<C>().collect()
     }
     /// Provides mutable access to all entities' components that match the specified type and
     /// supplied filter.
@@ -104,28 +340,12 @@ impl Assembly {
     where
         for<'r> F: FnMut(&'r (Entity, &mut C)) -> bool,
     {
-        self.entities
-            .iter_mut()
-            .filter(|&(_, ref g)| g.has::<C>())
-            .map(|(e, g)| {
-                (
-                    e.clone(),
-                    g.borrow_mut::<C>().unwrap_or_else(|_| unreachable!()),
-                )
-            })
-            .filter(filter)
-            .collect()
+        self.w1_iter::<C>().filter(filter).collect()
     }
     /// Ensures that only a single entity matches the bounds given by the specified component
-    /// type and filter. Errors otherwise. Mutable version.
+    /// type. Errors otherwise. Mutable version.
     pub fn ws1<C: ComponentTrait>(&mut self) -> Result<(Entity, &mut C), EcsError> {
-        let mut components = self.w1::<C>();
-
-        match components.len() {
-            0 => Err(EcsError::ComponentNotFound),
-            1 => Ok(components.pop().unwrap_or_else(|| unreachable!())),
-            _ => Err(EcsError::MultipleComponentsFound),
-        }
+        self.w1_iter::<C>().single()
     }
     /// Ensures that only a single entity matches the bounds given by the specified component
     /// type and filter. Errors otherwise. Mutable version.
@@ -133,34 +353,319 @@ impl Assembly {
     where
         for<'r> F: FnMut(&'r (Entity, &mut C)) -> bool,
     {
-        let mut components = self.wf1::<F, C>(filter);
+        self.w1_iter::<C>().filter(filter).single()
+    }
+    /// Returns the current change tick. Systems may store this value to query changes since
+    /// their last run via `added`, `changed` and `removed`.
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+    /// Returns the number of events dispatched during the previous frame per event type, as well
+    /// as the errors that occurred while dispatching them.
+    pub fn event_statistics(&self) -> &EventStatistics {
+        &self.statistics
+    }
+    /// Returns the events scheduled for later dispatch, along with the current game time.
+    pub fn timers(&self) -> &Timers {
+        &self.timers
+    }
+    /// Returns the events scheduled for later dispatch mutably, such that events may be scheduled
+    /// or cancelled. Errors if the `Assembly` is restricted, because systems that run concurrently
+    /// must not schedule or cancel timers.
+    pub fn timers_mut(&mut self) -> Result<&mut Timers, EcsError> {
+        if self.access.is_some() {
+            return Err(EcsError::RestrictedTimers);
+        }
+        Ok(&mut self.timers)
+    }
+    /// Replaces the event statistics at the end of a frame.
+    pub(crate) fn set_event_statistics(&mut self, statistics: EventStatistics) {
+        self.statistics = statistics;
+    }
+    /// Advances the change tick and returns the new value. The `World` calls this after each
+    /// system invocation, such that subsequent changes are distinguishable.
+    pub fn advance_tick(&mut self) -> u64 {
+        self.tick += 1;
+        self.tick
+    }
+    /// Iterates over all entities whose component of the specified type was added after the
+    /// specified tick.
+    pub fn added<C: ComponentTrait>(&self, since: u64) -> impl Iterator<Item = Entity> + '_ {
+        self.storage::<C>()
+            .into_iter()
+            .flat_map(move |s| s.added_since(since))
+            .map(move |i| self.entity_at(i))
+    }
+    /// Iterates over all entities whose component of the specified type was added or mutably
+    /// borrowed after the specified tick. Mutable borrows count as changes, regardless of whether
+    /// the component was actually modified.
+    pub fn changed<C: ComponentTrait>(&self, since: u64) -> impl Iterator<Item = Entity> + '_ {
+        self.storage::<C>()
+            .into_iter()
+            .flat_map(move |s| s.changed_since(since))
+            .map(move |i| self.entity_at(i))
+    }
+    /// Iterates over all entities whose component of the specified type was removed (or which
+    /// were destroyed) after the specified tick. Removals are retained until
+    /// `clear_removed` discards them.
+    pub fn removed<C: ComponentTrait>(&self, since: u64) -> impl Iterator<Item = Entity> + '_ {
+        self.removed
+            .get(&TypeId::of::<C>())
+            .into_iter()
+            .flat_map(|r| r.iter())
+            .filter(move |&&(_, t)| t > since)
+            .map(|&(e, _)| e)
+    }
+    /// Discards all records of removals that happened at or before the specified tick.
+    pub fn clear_removed(&mut self, before: u64) {
+        for r in self.removed.values_mut() {
+            r.retain(|&(_, t)| t > before);
+        }
+    }
+    /// Starts recording lifecycle events for the specified component type.
+    pub(crate) fn observe(&mut self, type_id: TypeId) {
+        self.observed.insert(type_id);
+    }
+    /// Returns all recorded lifecycle events and clears the record.
+    pub(crate) fn take_lifecycle(&mut self) -> Vec<Lifecycle> {
+        mem::take(&mut self.lifecycle)
+    }
+    /// Hands a `CommandBuffer` to the `Assembly`. The `World` applies it at the end of the
+    /// current loop stage.
+    pub fn defer(&mut self, buffer: CommandBuffer) {
+        if !buffer.is_empty() {
+            self.deferred.push(buffer);
+        }
+    }
+    /// Applies all deferred command buffers in the order they were handed over. Errors with the
+    /// first error encountered, though all commands are applied regardless.
+    pub fn apply_deferred(&mut self) -> Result<(), EcsError> {
+        let mut result = Ok(());
+        for buffer in mem::take(&mut self.deferred) {
+            let r = buffer.apply(self);
+            if result.is_ok() {
+                result = r;
+            }
+        }
+        result
+    }
+    /// Creates a restricted `Assembly` for a system that runs concurrently with others. The
+    /// storages of the component types declared for writing are moved to the new `Assembly`,
+    /// while those declared for reading are shared. The storages must be returned with `merge`
+    /// before the current `Assembly` is used again, and the current `Assembly` must outlive the
+    /// restricted one.
+    pub(crate) fn split(&mut self, access: &Access, tick: u64) -> Assembly {
+        let mut sub = Assembly {
+            generations: self.generations.clone(),
+            alive: self.alive.clone(),
+            free_indices: self.free_indices.clone(),
+            entity_count: self.entity_count,
+            tick,
+            observed: self.observed.clone(),
+            access: Some(access.clone()),
+            statistics: self.statistics.clone(),
+            timers: self.timers.empty_copy(),
+            ..Default::default()
+        };
+
+        for t in access.writes() {
+            if let Some(s) = self.storages.remove(&t) {
+                sub.storages.insert(t, s);
+            }
+        }
+        for t in access.reads() {
+            if let Some(s) = self.storages.get(&t) {
+                sub.shared.insert(t, &**s as *const dyn AnyStorage);
+            }
+        }
+        for t in access.reads().chain(access.writes()) {
+            if let Some(r) = self.removed.get(&t) {
+                sub.removed.insert(t, r.clone());
+            }
+        }
 
-        match components.len() {
-            0 => Err(EcsError::ComponentNotFound),
-            1 => Ok(components.pop().unwrap_or_else(|| unreachable!())),
-            _ => Err(EcsError::MultipleComponentsFound),
+        sub
+    }
+    /// Returns the storages, removal records, lifecycle events and deferred command buffers of
+    /// a restricted `Assembly` created by `split`.
+    pub(crate) fn merge(&mut self, mut sub: Assembly) {
+        let access = sub.access.take().expect("Only restricted assemblies may be merged");
+        for t in access.writes() {
+            if let Some(r) = sub.removed.remove(&t) {
+                self.removed.insert(t, r);
+            }
+        }
+        self.storages.extend(sub.storages.drain());
+        self.lifecycle.append(&mut sub.lifecycle);
+        self.deferred.append(&mut sub.deferred);
+    }
+    /// Panics if the `Assembly` is restricted, because structural changes must not happen while
+    /// systems run concurrently.
+    fn assert_unrestricted(&self) {
+        if self.access.is_some() {
+            panic!("Systems that run concurrently must defer the creation and destruction of entities");
+        }
+    }
+    /// Panics if the `Assembly` is restricted and the specified component type was not declared
+    /// for the requested kind of access.
+    fn assert_access(&self, type_id: TypeId, name: &str, write: bool) {
+        if let Some(ref a) = self.access {
+            let declared = if write {
+                a.writes().any(|t| t == type_id)
+            } else {
+                a.writes().chain(a.reads()).any(|t| t == type_id)
+            };
+            if !declared {
+                panic!(
+                    "The component type '{}' was accessed {} without being declared by the system",
+                    name,
+                    if write { "mutably" } else { "immutably" }
+                );
+            }
+        }
+    }
+    /// Borrows the type-erased storage of the specified component type, including storages
+    /// shared with a restricted `Assembly`.
+    fn any_storage(&self, type_id: &TypeId) -> Option<&dyn AnyStorage> {
+        match self.storages.get(type_id) {
+            Some(s) => Some(&**s),
+            // Shared storages are owned by the parent `Assembly`, which outlives the restricted
+            // one and leaves them untouched until `merge` is called.
+            None => self.shared.get(type_id).map(|&s| unsafe { &*s }),
         }
     }
+    /// Returns the `Entity` currently occupying the specified index.
+    fn entity_at(&self, index: usize) -> Entity {
+        Entity::from_parts(index as u32, self.alive[index].unwrap_or_default())
+    }
+    /// Borrows the storage of the specified component type.
+    fn storage<C: ComponentTrait>(&self) -> Option<&Storage<C>> {
+        self.assert_access(TypeId::of::<C>(), type_name::<C>(), false);
+        self.any_storage(&TypeId::of::<C>())
+            .map(|s| downcast_ref::<C>(s))
+    }
+    /// Mutably borrows the storage of the specified component type.
+    fn storage_mut<C: ComponentTrait>(&mut self) -> Option<&mut Storage<C>> {
+        self.assert_access(TypeId::of::<C>(), type_name::<C>(), true);
+        self.storages.get_mut(&TypeId::of::<C>()).map(|s| downcast_mut::<C>(&mut **s))
+    }
+    /// Mutably borrows the storage of the specified component type and creates it if necessary.
+    fn storage_or_insert<C: ComponentTrait>(&mut self) -> &mut Storage<C> {
+        self.assert_access(TypeId::of::<C>(), type_name::<C>(), true);
+        let s = self.storages
+            .entry(TypeId::of::<C>())
+            .or_insert_with(|| Box::new(Storage::<C>::new(C::storage_kind())));
+        downcast_mut::<C>(&mut **s)
+    }
     impl_count!(count1, A);
-    impl_count!(count2, A, B);
-    impl_count!(count3, A, B, C);
-    impl_count!(count4, A, B, C, D);
-    impl_read!(r1, A);
-    impl_read!(r2, A, B);
-    impl_read!(r3, A, B, C);
-    impl_read!(r4, A, B, C, D);
-    impl_read_filtered!(rf1, A);
-    impl_read_filtered!(rf2, A, B);
-    impl_read_filtered!(rf3, A, B, C);
-    impl_read_filtered!(rf4, A, B, C, D);
-    impl_read_single!(rs1, r1, A);
-    impl_read_single!(rs2, r2, A, B);
-    impl_read_single!(rs3, r3, A, B, C);
-    impl_read_single!(rs4, r4, A, B, C, D);
-    impl_read_single_filtered!(rsf1, rf1, A);
-    impl_read_single_filtered!(rsf2, rf2, A, B);
-    impl_read_single_filtered!(rsf3, rf3, A, B, C);
-    impl_read_single_filtered!(rsf4, rf4, A, B, C, D);
+    impl_count!(count2, r2_iter, A, B);
+    impl_count!(count3, r3_iter, A, B, C);
+    impl_count!(count4, r4_iter, A, B, C, D);
+    impl_read_iter!(r1_iter, A);
+    impl_read_iter!(r2_iter, A, B);
+    impl_read_iter!(r3_iter, A, B, C);
+    impl_read_iter!(r4_iter, A, B, C, D);
+    impl_read!(r1, r1_iter, A);
+    impl_read!(r2, r2_iter, A, B);
+    impl_read!(r3, r3_iter, A, B, C);
+    impl_read!(r4, r4_iter, A, B, C, D);
+    impl_read_filtered!(rf1, r1_iter, A);
+    impl_read_filtered!(rf2, r2_iter, A, B);
+    impl_read_filtered!(rf3, r3_iter, A, B, C);
+    impl_read_filtered!(rf4, r4_iter, A, B, C, D);
+    impl_read_single!(rs1, r1_iter, A);
+    impl_read_single!(rs2, r2_iter, A, B);
+    impl_read_single!(rs3, r3_iter, A, B, C);
+    impl_read_single!(rs4, r4_iter, A, B, C, D);
+    impl_read_single_filtered!(rsf1, r1_iter, A);
+    impl_read_single_filtered!(rsf2, r2_iter, A, B);
+    impl_read_single_filtered!(rsf3, r3_iter, A, B, C);
+    impl_read_single_filtered!(rsf4, r4_iter, A, B, C, D);
+    impl_write_iter!(w2_iter, [A, B], []);
+    impl_write_iter!(w3_iter, [A, B, C], []);
+    impl_write_iter!(w4_iter, [A, B, C, D], []);
+    impl_write_iter!(w1r1_iter, [A], [B]);
+    impl_write_iter!(w1r2_iter, [A], [B, C]);
+    impl_write_iter!(w1r3_iter, [A], [B, C, D]);
+    impl_write_iter!(w2r1_iter, [A, B], [C]);
+    impl_write_iter!(w2r2_iter, [A, B], [C, D]);
+    impl_write_iter!(w3r1_iter, [A, B, C], [D]);
+    impl_write!(w2, w2_iter, [A, B], []);
+    impl_write!(w3, w3_iter, [A, B, C], []);
+    impl_write!(w4, w4_iter, [A, B, C, D], []);
+    impl_write!(w1r1, w1r1_iter, [A], [B]);
+    impl_write!(w1r2, w1r2_iter, [A], [B, C]);
+    impl_write!(w1r3, w1r3_iter, [A], [B, C, D]);
+    impl_write!(w2r1, w2r1_iter, [A, B], [C]);
+    impl_write!(w2r2, w2r2_iter, [A, B], [C, D]);
+    impl_write!(w3r1, w3r1_iter, [A, B, C], [D]);
+    impl_write_filtered!(wf2, w2_iter, [A, B], []);
+    impl_write_filtered!(wf3, w3_iter, [A, B, C], []);
+    impl_write_filtered!(wf4, w4_iter, [A, B, C, D], []);
+    impl_write_filtered!(wf1r1, w1r1_iter, [A], [B]);
+    impl_write_filtered!(wf1r2, w1r2_iter, [A], [B, C]);
+    impl_write_filtered!(wf1r3, w1r3_iter, [A], [B, C, D]);
+    impl_write_filtered!(wf2r1, w2r1_iter, [A, B], [C]);
+    impl_write_filtered!(wf2r2, w2r2_iter, [A, B], [C, D]);
+    impl_write_filtered!(wf3r1, w3r1_iter, [A, B, C], [D]);
+    impl_write_single!(ws2, w2_iter, [A, B], []);
+    impl_write_single!(ws3, w3_iter, [A, B, C], []);
+    impl_write_single!(ws4, w4_iter, [A, B, C, D], []);
+    impl_write_single!(ws1r1, w1r1_iter, [A], [B]);
+    impl_write_single!(ws1r2, w1r2_iter, [A], [B, C]);
+    impl_write_single!(ws1r3, w1r3_iter, [A], [B, C, D]);
+    impl_write_single!(ws2r1, w2r1_iter, [A, B], [C]);
+    impl_write_single!(ws2r2, w2r2_iter, [A, B], [C, D]);
+    impl_write_single!(ws3r1, w3r1_iter, [A, B, C], [D]);
+    impl_write_single_filtered!(wsf2, w2_iter, [A, B], []);
+    impl_write_single_filtered!(wsf3, w3_iter, [A, B, C], []);
+    impl_write_single_filtered!(wsf4, w4_iter, [A, B, C, D], []);
+    impl_write_single_filtered!(wsf1r1, w1r1_iter, [A], [B]);
+    impl_write_single_filtered!(wsf1r2, w1r2_iter, [A], [B, C]);
+    impl_write_single_filtered!(wsf1r3, w1r3_iter, [A], [B, C, D]);
+    impl_write_single_filtered!(wsf2r1, w2r1_iter, [A, B], [C]);
+    impl_write_single_filtered!(wsf2r2, w2r2_iter, [A, B], [C, D]);
+    impl_write_single_filtered!(wsf3r1, w3r1_iter, [A, B, C], [D]);
+}
+
+/// Downcasts a type-erased storage to the storage of the specified component type.
+fn downcast_ref<C: ComponentTrait>(storage: &dyn AnyStorage) -> &Storage<C> {
+    storage
+        .as_any()
+        .downcast_ref::<Storage<C>>()
+        .expect(DOWNCAST_ERROR)
+}
+
+/// Downcasts a type-erased storage to the storage of the specified component type.
+fn downcast_mut<C: ComponentTrait>(storage: &mut dyn AnyStorage) -> &mut Storage<C> {
+    storage
+        .as_any_mut()
+        .downcast_mut::<Storage<C>>()
+        .expect(DOWNCAST_ERROR)
+}
+
+/// Returns the shortest of the specified member lists. If any list is missing, no entity can
+/// match and the result is empty.
+fn smallest<'a>(members: &[Option<&'a [u32]>]) -> &'a [u32] {
+    let mut smallest: Option<&[u32]> = None;
+    for m in members {
+        match *m {
+            Some(m) if smallest.map(|s| m.len() < s.len()).unwrap_or(true) => smallest = Some(m),
+            Some(_) => (),
+            None => return &[],
+        }
+    }
+    smallest.unwrap_or(&[])
+}
+
+/// Panics if the specified component types are not pairwise distinct, because a query must never
+/// borrow a component mutably while it is borrowed elsewhere.
+fn assert_distinct(types: &[(TypeId, &str)]) {
+    for (i, &(t, name)) in types.iter().enumerate() {
+        if types[..i].iter().any(|&(u, _)| u == t) {
+            panic!("The component type '{}' was specified more than once in a mutable query", name);
+        }
+    }
 }
 
 #[cfg(test)]
@@ -207,5 +712,261 @@ mod test {
 
         let f = a.create_entity();
         assert!(a.has_entity(&f));
+        assert!(!a.has_entity(&e));
+    }
+
+    #[test]
+    fn test_entity_recycling() {
+        let mut a = Assembly::new();
+
+        let e = a.create_entity();
+        assert!(a.destroy_entity(&e).is_some());
+        assert!(a.destroy_entity(&e).is_none());
+
+        let f = a.create_entity();
+        assert_eq!(e.index(), f.index());
+        assert!(e != f);
+        assert!(!a.has_entity(&e));
+        assert!(a.has_entity(&f));
+        assert_eq!(a.entity_count(), 1);
+    }
+
+    #[test]
+    fn test_generation_overflow() {
+        let mut a = Assembly::new();
+
+        let e = a.create_entity();
+        a.destroy_entity(&e);
+        a.generations[e.index()] = u32::MAX - 1;
+        let f = a.create_entity();
+        assert_eq!(f.generation(), u32::MAX);
+        assert!(a.destroy_entity(&f).is_some());
+
+        let g = a.create_entity();
+        assert!(g.index() != f.index());
+        assert!(!a.has_entity(&f));
+        assert!(!a.has_entity(&e));
+        assert_eq!(a.entity_count(), 1);
+    }
+
+    #[test]
+    fn test_revive_exhausted_index() {
+        let mut a = Assembly::new();
+
+        let e = a.create_entity();
+        a.destroy_entity(&e);
+        a.generations[e.index()] = u32::MAX - 1;
+        let f = a.create_entity();
+        a.destroy_entity(&f);
+
+        a.revive_entity(&e).unwrap();
+        assert!(a.has_entity(&e));
+        assert!(!a.has_entity(&f));
+        assert_eq!(a.entity_count(), 1);
+
+        // The revived entity keeps the index retired, since its successors were issued already.
+        a.destroy_entity(&e);
+        let g = a.create_entity();
+        assert!(g.index() != e.index());
+        assert!(!a.has_entity(&e));
+        assert!(!a.has_entity(&f));
+    }
+
+    #[test]
+    fn test_stale_entity() {
+        #[derive(Debug)]
+        struct Component;
+        impl ComponentTrait for Component {}
+
+        let mut a = Assembly::new();
+
+        let e = a.create_entity();
+        a.add_component(&e, Component).unwrap();
+        a.destroy_entity(&e);
+        let f = a.create_entity();
+        a.add_component(&f, Component).unwrap();
+
+        match a.borrow_component::<Component>(&e) {
+            Err(EcsError::DeadEntity(d)) => assert_eq!(d, e),
+            r => panic!("Expected a dead entity error, got {:?} instead", r),
+        }
+        match a.add_component(&e, Component) {
+            Err(EcsError::DeadEntity(d)) => assert_eq!(d, e),
+            r => panic!("Expected a dead entity error, got {:?} instead", r),
+        }
+        assert!(!a.has_component::<Component>(&e));
+        assert!(a.borrow_component::<Component>(&f).is_ok());
+    }
+
+    #[test]
+    fn test_queries() {
+        use storage::StorageKind;
+
+        #[derive(Debug, PartialEq)]
+        struct Position(u32);
+        impl ComponentTrait for Position {}
+
+        #[derive(Debug, PartialEq)]
+        struct Tag;
+        impl ComponentTrait for Tag {
+            fn storage_kind() -> StorageKind {
+                StorageKind::Sparse
+            }
+        }
+
+        let mut a = Assembly::new();
+        let entities = (0..10).map(|_| a.create_entity()).collect::<Vec<_>>();
+        for (i, e) in entities.iter().enumerate() {
+            a.add_component(e, Position(i as u32)).unwrap();
+            if i % 3 == 0 {
+                a.add_component(e, Tag).unwrap();
+            }
+        }
+        a.destroy_entity(&entities[3]);
+
+        assert_eq!(a.count1::<Position>(), 9);
+        assert_eq!(a.count2::<Position, Tag>(), 3);
+
+        let mut tagged = a.r2::<Position, Tag>()
+            .into_iter()
+            .map(|(e, p, _)| (e, p.0))
+            .collect::<Vec<_>>();
+        tagged.sort_by_key(|&(_, p)| p);
+        assert_eq!(tagged, vec![(entities[0], 0), (entities[6], 6), (entities[9], 9)]);
+
+        for (_, p) in a.w1::<Position>() {
+            p.0 += 1;
+        }
+        assert_eq!(a.borrow_component::<Position>(&entities[9]).unwrap(), &Position(10));
+        assert!(a.rs2::<Position, Tag>().is_err());
+        assert!(a.rsf2::<_, Position, Tag>(|&(_, p, _)| p.0 == 7).is_ok());
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Position(f32);
+    impl ComponentTrait for Position {}
+
+    #[derive(Debug, PartialEq)]
+    struct Velocity(f32);
+    impl ComponentTrait for Velocity {}
+
+    #[test]
+    fn test_write_queries() {
+        let mut a = Assembly::new();
+        let e = a.create_entity();
+        a.add_component(&e, Position(0.0)).unwrap();
+        a.add_component(&e, Velocity(2.0)).unwrap();
+        let f = a.create_entity();
+        a.add_component(&f, Position(1.0)).unwrap();
+
+        for (_, p, v) in a.w1r1::<Position, Velocity>() {
+            p.0 += v.0;
+        }
+        assert_eq!(a.borrow_component::<Position>(&e).unwrap(), &Position(2.0));
+        assert_eq!(a.borrow_component::<Position>(&f).unwrap(), &Position(1.0));
+
+        for (_, p, v) in a.w2::<Position, Velocity>() {
+            p.0 += 1.0;
+            v.0 = 0.0;
+        }
+        assert_eq!(a.borrow_component::<Position>(&e).unwrap(), &Position(3.0));
+        assert_eq!(a.borrow_component::<Velocity>(&e).unwrap(), &Velocity(0.0));
+        assert_eq!(a.borrow_component::<Position>(&f).unwrap(), &Position(1.0));
+
+        assert!(a.ws2::<Position, Velocity>().is_ok());
+        assert!(a.wsf1r1::<_, Position, Velocity>(|(_, p, _)| p.0 > 10.0).is_err());
+    }
+
+    #[test]
+    fn test_iterator_queries() {
+        let mut a = Assembly::new();
+        for i in 0..4 {
+            let e = a.create_entity();
+            a.add_component(&e, Position(i as f32)).unwrap();
+            if i % 2 == 0 {
+                a.add_component(&e, Velocity(1.0)).unwrap();
+            }
+        }
+
+        assert_eq!(a.r2_iter::<Position, Velocity>().count(), 2);
+        assert!(a.r2_iter::<Position, Velocity>().first().is_ok());
+        assert!(a.r2_iter::<Position, Velocity>().single().is_err());
+        assert!(a.r1_iter::<Position>().filter(|&(_, p)| p.0 > 2.0).single().is_ok());
+
+        for (_, p, v) in a.w1r1_iter::<Position, Velocity>() {
+            p.0 += v.0;
+        }
+        let sum = a.r1_iter::<Position>().map(|(_, p)| p.0).sum::<f32>();
+        assert_eq!(sum, 8.0);
+    }
+
+    #[test]
+    fn test_restricted_timers() {
+        let mut a = Assembly::new();
+        assert!(a.timers_mut().is_ok());
+
+        let tick = a.tick();
+        let mut sub = a.split(&Access::new().write::<Position>(), tick);
+        match sub.timers_mut() {
+            Err(EcsError::RestrictedTimers) => (),
+            r => panic!("Expected a restricted timers error, got {:?} instead", r),
+        }
+        a.merge(sub);
+        assert!(a.timers_mut().is_ok());
+    }
+
+    #[test]
+    fn test_required_components() {
+        #[derive(Debug)]
+        struct Collider;
+        impl ComponentTrait for Collider {
+            fn name() -> &'static str {
+                "collider"
+            }
+            fn required_components() -> Vec<(TypeId, &'static str)> {
+                vec![(TypeId::of::<Position>(), "position")]
+            }
+        }
+
+        let mut a = Assembly::new();
+        let e = a.create_entity();
+
+        match a.add_component(&e, Collider) {
+            Err(EcsError::MissingRequiredComponent(f, "collider", "position")) => assert_eq!(f, e),
+            r => panic!("Expected a missing required component error, got {:?} instead", r),
+        }
+        a.add_component(&e, Position(0.0)).unwrap();
+        assert!(a.add_component(&e, Collider).is_ok());
+
+        let names = a.component_names(&e).unwrap();
+        assert_eq!(names.len(), 2);
+        assert!(names.contains(&"collider"));
+    }
+
+    #[test]
+    #[should_panic(expected = "more than once")]
+    fn test_write_queries_duplicate_types() {
+        let mut a = Assembly::new();
+        let e = a.create_entity();
+        a.add_component(&e, Position(0.0)).unwrap();
+
+        let _ = a.w2::<Position, Position>();
+    }
+
+    #[test]
+    fn test_unknown_entity() {
+        use entity::Entity;
+
+        let mut a = Assembly::new();
+        let e = a.create_entity();
+
+        match a.verify_entity(&e.next_generation().unwrap()) {
+            Err(EcsError::EntityNotFound(_)) => (),
+            r => panic!("Expected an unknown entity error, got {:?} instead", r),
+        }
+        match a.verify_entity(&Entity::from_parts(1, 0)) {
+            Err(EcsError::EntityNotFound(_)) => (),
+            r => panic!("Expected an unknown entity error, got {:?} instead", r),
+        }
     }
 }
@@ -0,0 +1,111 @@
+use std::any::type_name;
+use std::ops::{Deref, DerefMut};
+
+use access::Access;
+
+/// Holds a part of the auxiliary object of a `World`. Systems declare the parts they access with
+/// `Access::aux`, such that the `World` may move each part to the one system that declared it,
+/// while systems run concurrently. Meanwhile, the part is absent from the auxiliary objects of all
+/// other systems, and any access to it panics.
+#[derive(Debug)]
+pub struct AuxPart<T>(Option<T>);
+
+impl<T> AuxPart<T> {
+    /// Creates a new `AuxPart` that holds the specified value.
+    pub fn new(value: T) -> Self {
+        AuxPart(Some(value))
+    }
+    /// Moves the value into a new `AuxPart` if the `Access` declares the current part. Otherwise,
+    /// the new `AuxPart` is absent.
+    pub fn split(&mut self, access: &Access) -> Self
+    where
+        T: 'static,
+    {
+        if access.declares_aux::<T>() {
+            AuxPart(self.0.take())
+        } else {
+            AuxPart(None)
+        }
+    }
+    /// Moves the value of an `AuxPart` created by `split` back into the current part.
+    pub fn merge(&mut self, part: AuxPart<T>) {
+        if part.0.is_some() {
+            self.0 = part.0;
+        }
+    }
+}
+
+impl<T: Default> Default for AuxPart<T> {
+    fn default() -> Self {
+        AuxPart::new(Default::default())
+    }
+}
+
+impl<T> Deref for AuxPart<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        match self.0 {
+            Some(ref v) => v,
+            None => absent::<T>(),
+        }
+    }
+}
+
+impl<T> DerefMut for AuxPart<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        match self.0 {
+            Some(ref mut v) => v,
+            None => absent::<T>(),
+        }
+    }
+}
+
+/// Panics, because a system accessed a part of the auxiliary object that it did not declare.
+fn absent<T>() -> ! {
+    panic!(
+        "The auxiliary part '{}' was accessed without being declared by the system",
+        type_name::<T>()
+    );
+}
+
+/// Splits the auxiliary object of a `World` into the `AuxPart`s declared by systems (see
+/// `Access::aux`), such that systems may run concurrently in `ExecutionMode::Parallel`.
+///
+/// # Safety
+///
+/// The `World` hands the auxiliary objects returned by `split` to other threads. Implementations
+/// must only move `AuxPart`s into them via `AuxPart::split`, which moves only parts that are `Send`,
+/// and all other fields must neither own nor share any state.
+pub unsafe trait SplitAux: Sized {
+    /// Moves the parts declared by the `Access` into a new auxiliary object.
+    fn split(&mut self, access: &Access) -> Self;
+    /// Moves the parts of an auxiliary object created by `split` back into the current object.
+    fn merge(&mut self, parts: Self);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_split_and_merge() {
+        let mut a = AuxPart::new(vec![1, 2]);
+        let b = a.split(&Access::new());
+        let mut c = a.split(&Access::new().aux::<Vec<i32>>());
+
+        c.push(3);
+        a.merge(b);
+        a.merge(c);
+        assert_eq!(*a, vec![1, 2, 3]);
+    }
+
+    #[test]
+    #[should_panic(expected = "without being declared")]
+    fn test_absent() {
+        let mut a = AuxPart::new(0u32);
+        let b = a.split(&Access::new().aux::<u32>());
+        assert_eq!(*b, 0);
+        assert_eq!(*a, 0);
+    }
+}
@@ -0,0 +1,201 @@
+use std::fmt;
+
+use error::EcsError;
+use entity::Entity;
+use component_group::ComponentTrait;
+use assembly::Assembly;
+
+/// Describes a single recorded structural change.
+type Command = Box<dyn FnOnce(&mut Assembly) -> Result<(), EcsError> + Send>;
+
+/// Describes the addition of a single component to a newly created `Entity`.
+type Insertion = Box<dyn FnOnce(&mut Assembly, &Entity) -> Result<(), EcsError> + Send>;
+
+/// Records structural changes (creation and destruction of entities, addition and removal of
+/// components) for later application. Systems hand their buffers to the `Assembly` via
+/// `Assembly::defer`, and the `World` applies them at the end of each loop stage. Recorded
+/// components must be `Send`, because buffers may be recorded by systems that run concurrently.
+#[derive(Default)]
+pub struct CommandBuffer {
+    commands: Vec<Command>,
+}
+
+impl CommandBuffer {
+    /// Creates a new, empty `CommandBuffer`.
+    pub fn new() -> Self {
+        Default::default()
+    }
+    /// Returns the number of recorded commands.
+    pub fn len(&self) -> usize {
+        self.commands.len()
+    }
+    /// Returns `true` if no commands were recorded.
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+    /// Records the creation of a new `Entity`. Components may be attached with
+    /// `EntityBuilder::with`, and the command is recorded once `EntityBuilder::build` is called.
+    pub fn create_entity(&mut self) -> EntityBuilder<'_> {
+        EntityBuilder {
+            buffer: self,
+            insertions: Vec::new(),
+        }
+    }
+    /// Records the destruction of the specified `Entity`.
+    pub fn destroy_entity(&mut self, entity: &Entity) {
+        let entity = *entity;
+        self.commands.push(Box::new(move |a: &mut Assembly| {
+            a.verify_entity(&entity)?;
+            a.destroy_entity(&entity);
+            Ok(())
+        }));
+    }
+    /// Records the addition of a component to the specified `Entity`.
+    pub fn add_component<C: ComponentTrait + Send>(&mut self, entity: &Entity, component: C) {
+        let entity = *entity;
+        self.commands.push(Box::new(move |a: &mut Assembly| {
+            a.add_component(&entity, component).map(|_| ())
+        }));
+    }
+    /// Records the removal of the component of the specified type from the specified `Entity`.
+    pub fn remove_component<C: ComponentTrait>(&mut self, entity: &Entity) {
+        let entity = *entity;
+        self.commands.push(Box::new(move |a: &mut Assembly| {
+            a.remove_component::<C>(&entity).map(|_| ())
+        }));
+    }
+    /// Applies all recorded commands to the `Assembly` in the order of recording. Commands that
+    /// target entities that no longer exist are skipped, because another system may have
+    /// destroyed them in the meantime. Any other error is returned after all commands were
+    /// applied.
+    pub fn apply(self, assembly: &mut Assembly) -> Result<(), EcsError> {
+        let mut result = Ok(());
+        for command in self.commands {
+            match command(assembly) {
+                Ok(()) | Err(EcsError::DeadEntity(_)) | Err(EcsError::EntityNotFound(_)) => (),
+                Err(e) => if result.is_ok() {
+                    result = Err(e);
+                },
+            }
+        }
+        result
+    }
+}
+
+impl fmt::Debug for CommandBuffer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CommandBuffer")
+            .field("commands", &self.commands.len())
+            .finish()
+    }
+}
+
+/// Collects the components of an `Entity` that is to be created by a `CommandBuffer`.
+#[must_use = "The entity is only recorded once build() is called"]
+pub struct EntityBuilder<'a> {
+    buffer: &'a mut CommandBuffer,
+    insertions: Vec<Insertion>,
+}
+
+impl<'a> EntityBuilder<'a> {
+    /// Attaches a component to the new `Entity`.
+    pub fn with<C: ComponentTrait + Send>(mut self, component: C) -> Self {
+        self.insertions.push(Box::new(move |a: &mut Assembly, e: &Entity| {
+            a.add_component(e, component).map(|_| ())
+        }));
+        self
+    }
+    /// Records the creation of the `Entity` and its components. If any component cannot be
+    /// added, e.g. because a required component is missing, the `Entity` is destroyed again.
+    pub fn build(self) {
+        let insertions = self.insertions;
+        self.buffer.commands.push(Box::new(move |a: &mut Assembly| {
+            let entity = a.create_entity();
+            for insertion in insertions {
+                if let Err(e) = insertion(a, &entity) {
+                    a.destroy_entity(&entity);
+                    return Err(e);
+                }
+            }
+            Ok(())
+        }));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Name(&'static str);
+    impl ComponentTrait for Name {}
+
+    #[test]
+    fn test_apply() {
+        let mut a = Assembly::new();
+        let e = a.create_entity();
+        let f = a.create_entity();
+        a.add_component(&f, Name("f")).unwrap();
+
+        let mut b = CommandBuffer::new();
+        b.create_entity().with(Name("g")).build();
+        b.add_component(&e, Name("e"));
+        b.remove_component::<Name>(&f);
+        b.destroy_entity(&f);
+        b.destroy_entity(&f);
+        assert_eq!(b.len(), 5);
+
+        // Recording does not touch the assembly.
+        assert_eq!(a.entity_count(), 2);
+
+        b.apply(&mut a).unwrap();
+        assert_eq!(a.entity_count(), 2);
+        assert!(!a.has_entity(&f));
+        assert_eq!(a.borrow_component::<Name>(&e).unwrap(), &Name("e"));
+        assert!(a.rsf1::<_, Name>(|&(_, n)| n.0 == "g").is_ok());
+    }
+
+    #[test]
+    fn test_apply_error() {
+        #[derive(Debug)]
+        struct Dependent;
+        impl ComponentTrait for Dependent {
+            fn required_components() -> Vec<(::std::any::TypeId, &'static str)> {
+                vec![(::std::any::TypeId::of::<Name>(), "name")]
+            }
+        }
+
+        let mut a = Assembly::new();
+        let e = a.create_entity();
+
+        let mut b = CommandBuffer::new();
+        b.add_component(&e, Dependent);
+        b.add_component(&e, Name("e"));
+
+        assert!(b.apply(&mut a).is_err());
+        assert!(a.has_component::<Name>(&e));
+    }
+
+    #[test]
+    fn test_build_error() {
+        #[derive(Debug)]
+        struct Dependent;
+        impl ComponentTrait for Dependent {
+            fn required_components() -> Vec<(::std::any::TypeId, &'static str)> {
+                vec![(::std::any::TypeId::of::<Name>(), "name")]
+            }
+        }
+
+        let mut a = Assembly::new();
+
+        let mut b = CommandBuffer::new();
+        b.create_entity().with(Dependent).with(Name("e")).build();
+
+        match b.apply(&mut a) {
+            Err(EcsError::MissingRequiredComponent(..)) => (),
+            r => panic!("Expected a missing required component error, got {:?} instead", r),
+        }
+        assert_eq!(a.entity_count(), 0);
+        assert!(a.rsf1::<_, Name>(|_| true).is_err());
+    }
+}
@@ -1,17 +1,45 @@
-use std::any::{Any, TypeId};
+use std::any::{type_name, Any, TypeId};
 use std::collections::HashMap;
 
 use error::EcsError;
+use storage::StorageKind;
 
 const DOWNCAST_ERROR: &str = "Was unable to downcast the requested component from Any.";
 
-/// All components must implement this trait.
-pub trait ComponentTrait: Any {}
+/// All components must implement this trait. Usually, the trait is implemented via
+/// `#[derive(Component)]`, which also accepts options to override the default methods.
+pub trait ComponentTrait: Any {
+    /// Returns the stable name of the component type, used by save files and the debug shell.
+    /// The derive uses the type identifier unless a name is given. The default implementation
+    /// falls back to `std::any::type_name`, which is not guaranteed to be stable across compiler
+    /// versions, thus manual implementations of serialized components should override it.
+    fn name() -> &'static str
+    where
+        Self: Sized,
+    {
+        type_name::<Self>()
+    }
+    /// Selects the kind of storage that holds all components of this type.
+    fn storage_kind() -> StorageKind
+    where
+        Self: Sized,
+    {
+        StorageKind::default()
+    }
+    /// Returns the types (and names) of the components that an `Entity` must have before a
+    /// component of this type may be added.
+    fn required_components() -> Vec<(TypeId, &'static str)>
+    where
+        Self: Sized,
+    {
+        Vec::new()
+    }
+}
 
 /// Groups multiple components of different types together.
 #[derive(Default, Debug)]
 pub struct ComponentGroup {
-    components: HashMap<TypeId, Box<Any>>,
+    components: HashMap<TypeId, Box<dyn Any>>,
 }
 
 impl ComponentGroup {
@@ -26,6 +54,10 @@ impl ComponentGroup {
             .insert(TypeId::of::<C>(), Box::new(component))
             .map(|c| *c.downcast::<C>().expect(DOWNCAST_ERROR))
     }
+    /// Inserts a type-erased component into the group.
+    pub(crate) fn insert_any(&mut self, type_id: TypeId, component: Box<dyn Any>) {
+        self.components.insert(type_id, component);
+    }
     /// Removes the component of a particular type from the group an return it.
     pub fn remove<C: ComponentTrait>(&mut self) -> Option<C> {
         self.components
@@ -2,23 +2,46 @@ use std::fmt;
 use std::fmt::Display;
 
 /// An `Entity` is nothing more than a unique identifier that stands for an object in the `World`.
-#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
-pub struct Entity(u64);
+/// It consists of an index and a generation. Indices are recycled by the `Assembly` once an
+/// `Entity` is destroyed, but each reuse increments the generation, such that stale handles never
+/// alias a newer `Entity`. Once the generation of an index is exhausted, the index is retired.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Entity {
+    index: u32,
+    generation: u32,
+}
 
 impl Entity {
-    /// Creates a new, initial `Entity`.
+    /// Creates a new, initial `Entity`. The `Assembly` never issues this `Entity`, thus it may
+    /// serve as a placeholder, e.g. as the root key of a hierarchy.
     pub fn new() -> Self {
         Default::default()
     }
-    /// Increments the internal ID.
-    pub fn increment(&mut self) {
-        self.0 += 1;
+    /// Creates an `Entity` from an index and a generation.
+    pub(crate) fn from_parts(index: u32, generation: u32) -> Self {
+        Entity { index, generation }
+    }
+    /// Returns the index of the `Entity`.
+    pub fn index(&self) -> usize {
+        self.index as usize
+    }
+    /// Returns the generation of the `Entity`.
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+    /// Returns the `Entity` that reuses the current index after the current one was destroyed,
+    /// or `None` if the generation would overflow.
+    pub fn next_generation(&self) -> Option<Self> {
+        self.generation.checked_add(1).map(|generation| Entity {
+            index: self.index,
+            generation,
+        })
     }
 }
 
 impl Display for Entity {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "Entity({})", self.0)
+        write!(f, "Entity({}v{})", self.index, self.generation)
     }
 }
 
@@ -33,11 +56,15 @@ mod test {
     }
 
     #[test]
-    fn test_increment() {
-        let mut e = Entity::new();
-        let f = e.clone();
-        e.increment();
+    fn test_next_generation() {
+        let e = Entity::new();
+        let f = e.next_generation().unwrap();
+
+        assert_eq!(e.index(), f.index());
+        assert_eq!(e.generation() + 1, f.generation());
+        assert!(e != f);
 
-        assert_eq!(e.0, f.0 + 1);
+        let g = Entity::from_parts(0, u32::MAX);
+        assert!(g.next_generation().is_none());
     }
 }
@@ -1,4 +1,7 @@
+use std::io;
+use serde_json;
 use entity::Entity;
+use system::SystemHandle;
 
 #[derive(Debug, Fail)]
 pub enum EcsError {
@@ -6,5 +9,37 @@ pub enum EcsError {
     #[fail(display = "The specified component(s) were found more than once")]
     MultipleComponentsFound,
     #[fail(display = "The entity '{}' was not found in the assembly", _0)] EntityNotFound(Entity),
+    #[fail(display = "The entity '{}' has already been destroyed", _0)] DeadEntity(Entity),
+    #[fail(display = "The entity '{}' cannot be revived while '{}' is alive", _0, _1)]
+    OccupiedEntityIndex(Entity, Entity),
+    #[fail(display = "The component '{}' requires the component '{}' on the entity '{}'", _1, _2, _0)]
+    MissingRequiredComponent(Entity, &'static str, &'static str),
     #[fail(display = "The system's requirements were not satisfied")] UnsatisfiedRequirements,
+    #[fail(display = "The ordering constraints of the systems {:?} form a cycle", _0)]
+    CyclicSystemOrder(Vec<String>),
+    #[fail(display = "The system '{}' was not found in the world", _0)] SystemNotFound(SystemHandle),
+    #[fail(display = "Systems that run concurrently must not schedule or cancel timers")]
+    RestrictedTimers,
+    #[fail(display = "Priority events exceeded the recursion limit of {}", _0)]
+    EventRecursionLimit(usize),
+    #[fail(display = "The systems {:?} emit priority events to each other without end", _0)]
+    EventCycle(Vec<String>),
+    #[fail(display = "The component '{}' is not registered for serialization", _0)]
+    UnregisteredComponent(String),
+    #[fail(display = "The component '{}' could not be (de)serialized: {}", _0, _1)]
+    ComponentSerialization(String, String),
+    #[fail(display = "{}", _0)] SceneIo(#[cause] io::Error),
+    #[fail(display = "{}", _0)] SceneFormat(#[cause] serde_json::Error),
+}
+
+impl From<io::Error> for EcsError {
+    fn from(value: io::Error) -> Self {
+        EcsError::SceneIo(value)
+    }
+}
+
+impl From<serde_json::Error> for EcsError {
+    fn from(value: serde_json::Error) -> Self {
+        EcsError::SceneFormat(value)
+    }
 }
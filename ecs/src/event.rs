@@ -1,19 +1,218 @@
+use std::any::{type_name, Any, TypeId};
+use std::collections::HashMap;
+use std::fmt;
+
+use error::EcsError;
+
 /// `EcsEvent` defines a set of foundational events that will cause `World` to do different
 /// things, independent of the actual events later used by the engine.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum EcsEvent {
     Shutdown,
     ImmediateShutdown,
     Ready,
 }
 
-/// Every engine event must implement the trait `EventTrait`, as some events must be converted to
-/// `EcsEvent`s for `World` to interact properly with the engine.
-pub trait EventTrait: Clone + From<EcsEvent> {
-    /// Specifies a type (ex. bitflags) that may select multiple events.
-    type EventFlag;
-    /// Returns true if the specified filter selects the current enum variant.
-    fn match_filter(&self, filter: Self::EventFlag) -> bool;
+impl EventTrait for EcsEvent {}
+
+/// Every event type must implement the trait `EventTrait`. Events of any type may be dispatched
+/// via the `World`, and systems select the event types they receive with an `EventFilter`.
+pub trait EventTrait: Any + Send + fmt::Debug {}
+
+/// Provides type-erased access to an event.
+trait AnyEvent: Any + Send + fmt::Debug {
+    /// Casts the event to `Any` for downcasting.
+    fn as_any(&self) -> &dyn Any;
+    /// Returns the name of the event type.
+    fn type_name(&self) -> &'static str;
+}
+
+impl<T: EventTrait> AnyEvent for T {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    fn type_name(&self) -> &'static str {
+        type_name::<T>()
+    }
+}
+
+/// Holds a single event of any type that implements `EventTrait`.
+pub struct Event(Box<dyn AnyEvent>);
+
+impl Event {
+    /// Wraps the specified event.
+    pub fn new<T: EventTrait>(event: T) -> Self {
+        Event(Box::new(event))
+    }
+    /// Returns the type of the wrapped event.
+    pub fn event_type(&self) -> TypeId {
+        self.0.as_any().type_id()
+    }
+    /// Returns the name of the type of the wrapped event.
+    pub fn name(&self) -> &'static str {
+        self.0.type_name()
+    }
+    /// Returns `true` if the wrapped event is of the specified type.
+    pub fn is<T: EventTrait>(&self) -> bool {
+        self.0.as_any().is::<T>()
+    }
+    /// Borrows the wrapped event, if it is of the specified type.
+    pub fn downcast_ref<T: EventTrait>(&self) -> Option<&T> {
+        self.0.as_any().downcast_ref::<T>()
+    }
     /// Attempts to convert to a `EcsEvent`.
-    fn as_ecs_event(&self) -> Option<EcsEvent>;
+    pub fn as_ecs_event(&self) -> Option<&EcsEvent> {
+        self.downcast_ref::<EcsEvent>()
+    }
+}
+
+impl fmt::Debug for Event {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<T: EventTrait> From<T> for Event {
+    fn from(value: T) -> Self {
+        Event::new(value)
+    }
+}
+
+/// Selects the event types that a system receives.
+#[derive(Debug, Clone, Default)]
+pub struct EventFilter {
+    all: bool,
+    included: Vec<TypeId>,
+    excluded: Vec<TypeId>,
+}
+
+impl EventFilter {
+    /// Creates a filter that selects no events.
+    pub fn new() -> Self {
+        Default::default()
+    }
+    /// Creates a filter that selects all events.
+    pub fn all() -> Self {
+        EventFilter {
+            all: true,
+            ..Default::default()
+        }
+    }
+    /// Additionally selects the events of the specified type.
+    pub fn with<T: EventTrait>(mut self) -> Self {
+        self.included.push(TypeId::of::<T>());
+        self.excluded.retain(|&t| t != TypeId::of::<T>());
+        self
+    }
+    /// Deselects the events of the specified type.
+    pub fn without<T: EventTrait>(mut self) -> Self {
+        self.excluded.push(TypeId::of::<T>());
+        self.included.retain(|&t| t != TypeId::of::<T>());
+        self
+    }
+    /// Returns `true` if the filter selects the specified event.
+    pub fn matches(&self, event: &Event) -> bool {
+        let t = event.event_type();
+        if self.all {
+            !self.excluded.contains(&t)
+        } else {
+            self.included.contains(&t)
+        }
+    }
+}
+
+/// Counts the events that were dispatched during a single frame per event type, and records the
+/// errors that occurred while dispatching them.
+#[derive(Debug, Clone, Default)]
+pub struct EventStatistics {
+    counts: HashMap<TypeId, (&'static str, usize)>,
+    errors: Vec<String>,
+}
+
+impl EventStatistics {
+    /// Creates empty statistics.
+    pub fn new() -> Self {
+        Default::default()
+    }
+    /// Returns the number of dispatched events of the specified type.
+    pub fn count<T: EventTrait>(&self) -> usize {
+        self.counts
+            .get(&TypeId::of::<T>())
+            .map_or(0, |&(_, c)| c)
+    }
+    /// Returns the total number of dispatched events.
+    pub fn total(&self) -> usize {
+        self.counts.values().map(|&(_, c)| c).sum()
+    }
+    /// Returns the name and number of dispatched events of each type, sorted by name.
+    pub fn counts(&self) -> Vec<(&'static str, usize)> {
+        let mut counts = self.counts.values().cloned().collect::<Vec<_>>();
+        counts.sort();
+        counts
+    }
+    /// Returns the errors that occurred while dispatching events.
+    pub fn errors(&self) -> &[String] {
+        &self.errors
+    }
+    /// Counts the specified event.
+    pub(crate) fn record(&mut self, event: &Event) {
+        self.counts
+            .entry(event.event_type())
+            .or_insert((event.name(), 0))
+            .1 += 1;
+    }
+    /// Records the specified error.
+    pub(crate) fn record_error(&mut self, error: &EcsError) {
+        self.errors.push(error.to_string());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Resize(u32, u32);
+    impl EventTrait for Resize {}
+
+    #[test]
+    fn test_event() {
+        let e = Event::new(Resize(1, 2));
+        assert!(e.is::<Resize>());
+        assert!(!e.is::<EcsEvent>());
+        assert_eq!(e.event_type(), TypeId::of::<Resize>());
+        assert_eq!(e.downcast_ref::<Resize>(), Some(&Resize(1, 2)));
+        assert!(e.as_ecs_event().is_none());
+        assert_eq!(format!("{:?}", e), "Resize(1, 2)");
+
+        let e: Event = EcsEvent::Shutdown.into();
+        assert_eq!(e.as_ecs_event(), Some(&EcsEvent::Shutdown));
+    }
+
+    #[test]
+    fn test_filter() {
+        let resize = Event::new(Resize(1, 2));
+        let shutdown = Event::new(EcsEvent::Shutdown);
+
+        assert!(!EventFilter::new().matches(&resize));
+        assert!(EventFilter::new().with::<Resize>().matches(&resize));
+        assert!(!EventFilter::new().with::<Resize>().matches(&shutdown));
+        assert!(EventFilter::all().matches(&resize));
+        assert!(!EventFilter::all().without::<Resize>().matches(&resize));
+        assert!(EventFilter::all().without::<Resize>().matches(&shutdown));
+    }
+
+    #[test]
+    fn test_statistics() {
+        let mut s = EventStatistics::new();
+        s.record(&Event::new(Resize(1, 2)));
+        s.record(&Event::new(Resize(3, 4)));
+        s.record(&Event::new(EcsEvent::Ready));
+
+        assert_eq!(s.count::<Resize>(), 2);
+        assert_eq!(s.count::<EcsEvent>(), 1);
+        assert_eq!(s.total(), 3);
+        assert_eq!(s.counts().len(), 2);
+        assert!(s.errors().is_empty());
+    }
 }
@@ -1,14 +1,15 @@
 #[macro_export]
 macro_rules! impl_system_group {
     (
-        pub enum $name:ident<$event_type:ty, $event_flag: ty, $aux_type:ty> {
+        pub enum $name:ident<$aux_type:ty> {
             $(
                 $variant:ident($inner:path),
             )+
         }
     ) => {
         use std::time::Duration;
-        use ecs::{SystemTrait, Assembly, LoopStageFlag, DispatchEvents};
+        use ecs::{SystemTrait, Assembly, LoopStageFlag, DispatchEvents, Access, DowncastSystem,
+                  Event, EventFilter};
 
         pub enum $name {
             $(
@@ -24,7 +25,26 @@ macro_rules! impl_system_group {
         }
         )+
 
-        impl SystemTrait<$event_type, $aux_type> for $name {
+        $(
+        impl DowncastSystem<$inner> for $name {
+            fn downcast_ref(&self) -> Option<&$inner> {
+                match *self {
+                    $name::$variant(ref s) => Some(s),
+                    #[allow(unreachable_patterns)]
+                    _ => None,
+                }
+            }
+            fn downcast_mut(&mut self) -> Option<&mut $inner> {
+                match *self {
+                    $name::$variant(ref mut s) => Some(s),
+                    #[allow(unreachable_patterns)]
+                    _ => None,
+                }
+            }
+        }
+        )+
+
+        impl SystemTrait<$aux_type> for $name {
             fn verify_requirements(&self, entities: &Assembly) -> bool {
                 match *self {
                     $(
@@ -39,7 +59,42 @@ macro_rules! impl_system_group {
                     )+
                 }
             }
-            fn get_event_filter(&self) -> $event_flag {
+            fn get_label(&self) -> Option<&'static str> {
+                match *self {
+                    $(
+                        $name::$variant(ref s) => s.get_label(),
+                    )+
+                }
+            }
+            fn get_before(&self) -> &'static [&'static str] {
+                match *self {
+                    $(
+                        $name::$variant(ref s) => s.get_before(),
+                    )+
+                }
+            }
+            fn get_after(&self) -> &'static [&'static str] {
+                match *self {
+                    $(
+                        $name::$variant(ref s) => s.get_after(),
+                    )+
+                }
+            }
+            fn get_access(&self) -> Access {
+                match *self {
+                    $(
+                        $name::$variant(ref s) => s.get_access(),
+                    )+
+                }
+            }
+            fn as_send(&mut self) -> Option<&mut (dyn SystemTrait<$aux_type> + Send)> {
+                match *self {
+                    $(
+                        $name::$variant(ref mut s) => s.as_send(),
+                    )+
+                }
+            }
+            fn get_event_filter(&self) -> EventFilter {
                 match *self {
                     $(
                         $name::$variant(ref s) => s.get_event_filter(),
@@ -47,7 +102,7 @@ macro_rules! impl_system_group {
                 }
             }
             fn handle_event(&mut self, entities: &mut Assembly, aux: &mut $aux_type,
-                            event: &EngineEvent) -> DispatchEvents<$event_type> {
+                            event: &Event) -> DispatchEvents {
                 match *self {
                     $(
                         $name::$variant(ref mut s) => s.handle_event(entities, aux, event),
@@ -55,7 +110,7 @@ macro_rules! impl_system_group {
                 }
             }
             fn update(&mut self, entities: &mut Assembly, aux: &mut $aux_type, time: &Duration,
-                      delta_time: &Duration) -> DispatchEvents<$event_type> {
+                      delta_time: &Duration) -> DispatchEvents {
                 match *self {
                     $(
                         $name::$variant(ref mut s) => s.update(entities, aux, time, delta_time),
@@ -64,7 +119,7 @@ macro_rules! impl_system_group {
             }
             fn dynamic_update(&mut self, entities: &mut Assembly, aux: &mut $aux_type,
                               time: &Duration, delta_time: &Duration)
-                    -> DispatchEvents<$event_type> {
+                    -> DispatchEvents {
                 match *self {
                     $(
                         $name::$variant(ref mut s) => s.dynamic_update(entities, aux, time,
@@ -73,10 +128,11 @@ macro_rules! impl_system_group {
                 }
             }
             fn render(&mut self, entities: &Assembly, aux: &mut $aux_type, time: &Duration,
-                      delta_time: &Duration) {
+                      delta_time: &Duration, alpha: f32) {
                 match *self {
                     $(
-                        $name::$variant(ref mut s) => s.render(entities, aux, time, delta_time),
+                        $name::$variant(ref mut s) => s.render(entities, aux, time, delta_time,
+                                                               alpha),
                     )+
                 }
             }
@@ -88,40 +144,54 @@ macro_rules! impl_count {
     ($name:ident, $t:tt) => {
         /// Counts the number of entities with the specified component.
         pub fn $name<$t: ComponentTrait>(&self) -> usize {
-            self.entities.values()
-                .filter(|g| g.has::<$t>())
-                .count()
+            self.storage::<$t>()
+                .map(|s| s.len())
+                .unwrap_or_default()
         }
     };
-    ($name:ident, $($t:tt),*) => {
+    ($name:ident, $base:ident, $($t:tt),*) => {
         /// Counts the number of entities with the specified components.
         pub fn $name<$($t: ComponentTrait),*>(&self) -> usize {
-            self.entities.values()
-                .filter(|g| $(g.has::<$t>())&&*)
-                .count()
+            self.$base::<$($t),*>().count()
         }
     };
 }
 
-/// Implements methods that collect all entities' components under the condition that all specified
-/// types must be present in each entity.
-macro_rules! impl_read {
+/// Implements methods that lazily iterate over all entities' components under the condition that
+/// all specified types must be present in each entity. Only the members of the smallest storage
+/// are visited, and no memory is allocated.
+macro_rules! impl_read_iter {
     ($name:ident, $t:tt) => {
-        /// Borrows all instances of the specified component.
-        pub fn $name<$t: ComponentTrait>(&self) -> Vec<(Entity, &$t)> {
-            self.entities.iter()
-                .filter(|&(_, ref g)| g.has::<$t>())
-                .map(|(e, g)| (e.clone(), g.borrow::<$t>().unwrap_or_else(|_| unreachable!())))
-                .collect()
+        /// Iterates over all instances of the specified component.
+        pub fn $name<$t: ComponentTrait>(&self) -> impl Iterator<Item = (Entity, &$t)> + '_ {
+            self.storage::<$t>()
+                .into_iter()
+                .flat_map(|s| s.iter())
+                .map(move |(i, c)| (self.entity_at(i), c))
         }
     };
     ($name:ident, $($t:tt),*) => {
+        /// Iterates over all entities that have all specified components.
+        #[allow(non_snake_case)]
+        pub fn $name<$($t: ComponentTrait),*>(&self)
+                -> impl Iterator<Item = (Entity, $(&$t),*)> + '_ {
+            $(let $t = self.storage::<$t>();)*
+            smallest(&[$($t.map(|s| s.members())),*]).iter()
+                .filter_map(move |&i| {
+                    let i = i as usize;
+                    Some((self.entity_at(i), $($t?.get(i)?),*))
+                })
+        }
+    };
+}
+
+/// Implements methods that collect all entities' components under the condition that all specified
+/// types must be present in each entity.
+macro_rules! impl_read {
+    ($name:ident, $base:ident, $($t:tt),*) => {
         /// Borrows from all entities that have all specified components.
         pub fn $name<$($t: ComponentTrait),*>(&self) -> Vec<(Entity, $(&$t),*)> {
-            self.entities.iter()
-                .filter(|&(_, g)| $(g.has::<$t>())&&*)
-                .map(|(e, g)| (e.clone(), $(g.borrow::<$t>().unwrap_or_else(|_| unreachable!())),*))
-                .collect()
+            self.$base::<$($t),*>().collect()
         }
     };
 }
@@ -130,28 +200,12 @@ macro_rules! impl_read {
 /// specified types must be present in each entity. Additionally accepts a filter function to
 /// filter components by their contents.
 macro_rules! impl_read_filtered {
-    ($name:ident, $t:tt) => {
-        /// Borrows all instances of the specified component, if their values pass the specified
-        /// filter.
-        pub fn $name<F, $t: ComponentTrait>(&self, filter: F) -> Vec<(Entity, &$t)>
-                where for<'r> F: FnMut(&'r (Entity, &$t)) -> bool {
-            self.entities.iter()
-                .filter(|&(_, ref g)| g.has::<$t>())
-                .map(|(e, g)| (e.clone(), g.borrow::<$t>().unwrap_or_else(|_| unreachable!())))
-                .filter(filter)
-                .collect()
-        }
-    };
-    ($name:ident, $($t:tt),*) => {
+    ($name:ident, $base:ident, $($t:tt),*) => {
         /// Borrows from all entities that have all specified components and whose values pass the
         /// specified filter.
         pub fn $name<F, $($t: ComponentTrait),*>(&self, filter: F) -> Vec<(Entity, $(&$t),*)>
                 where for<'r> F: FnMut(&'r (Entity, $(&$t),*)) -> bool {
-            self.entities.iter()
-                .filter(|&(_, ref g)| $(g.has::<$t>())&&*)
-                .map(|(e, g)| (e.clone(), $(g.borrow::<$t>().unwrap_or_else(|_| unreachable!())),*))
-                .filter(filter)
-                .collect()
+            self.$base::<$($t),*>().filter(filter).collect()
         }
     };
 }
@@ -159,30 +213,11 @@ macro_rules! impl_read_filtered {
 /// Implements methods that ensure only a single entity matches the bounds given by the components.
 /// Errors otherwise.
 macro_rules! impl_read_single {
-    ($name:ident, $base:ident, $t:tt) => {
-        /// Borrows the specified component, ensuring that only a single entity matches the given
-        /// conditions.
-        pub fn $name<$t: ComponentTrait>(&self) -> Result<(Entity, &$t), EcsError> {
-            let mut components = self.$base::<$t>();
-
-            match components.len() {
-                0 => Err(EcsError::ComponentNotFound),
-                1 => Ok(components.pop().unwrap_or_else(|| unreachable!())),
-                _ => Err(EcsError::MultipleComponentsFound),
-            }
-        }
-    };
     ($name:ident, $base:ident, $($t:tt),*) => {
         /// Borrows the specified components, ensuring that only a single entity matches the given
         /// conditions.
         pub fn $name<$($t: ComponentTrait),*>(&self) -> Result<(Entity, $(&$t),*), EcsError> {
-            let mut components = self.$base::<$($t),*>();
-
-            match components.len() {
-                0 => Err(EcsError::ComponentNotFound),
-                1 => Ok(components.pop().unwrap_or_else(|| unreachable!())),
-                _ => Err(EcsError::MultipleComponentsFound),
-            }
+            self.$base::<$($t),*>().single()
         }
     };
 }
@@ -190,33 +225,126 @@ macro_rules! impl_read_single {
 /// Implements methods that ensure only a single entity matches the bounds given by the components
 /// and the specified filter. Errors otherwise.
 macro_rules! impl_read_single_filtered {
-    ($name:ident, $base:ident, $t:tt) => {
-        /// Borrows the specified component, ensuring that only a single entity matches the given
-        /// conditions (defined by the component and filter).
-        pub fn $name<F, $t: ComponentTrait>(&self, filter: F) -> Result<(Entity, &$t), EcsError>
-                where for<'r> F: FnMut(&'r (Entity, &$t)) -> bool {
-            let mut components = self.$base::<F, $t>(filter);
-
-            match components.len() {
-                0 => Err(EcsError::ComponentNotFound),
-                1 => Ok(components.pop().unwrap_or_else(|| unreachable!())),
-                _ => Err(EcsError::MultipleComponentsFound),
-            }
-        }
-    };
     ($name:ident, $base:ident, $($t:tt),*) => {
         /// Borrows the specified components, ensuring that only a single entity matches the given
         /// conditions (defined by the components and filter).
         pub fn $name<F, $($t: ComponentTrait),*>(&self, filter: F)
                 -> Result<(Entity, $(&$t),*), EcsError>
-                where for<'r> F: FnMut(&'r (Entity, $(&$t),*)) -> bool{
-            let mut components = self.$base::<F, $($t),*>(filter);
+                where for<'r> F: FnMut(&'r (Entity, $(&$t),*)) -> bool {
+            self.$base::<$($t),*>().filter(filter).single()
+        }
+    };
+}
 
-            match components.len() {
-                0 => Err(EcsError::ComponentNotFound),
-                1 => Ok(components.pop().unwrap_or_else(|| unreachable!())),
-                _ => Err(EcsError::MultipleComponentsFound),
+/// Implements methods that lazily iterate over all entities that have all specified components,
+/// mutably borrowing the components of the first type list and immutably borrowing the components
+/// of the second type list. Each storage is looked up once, and only the members of the smallest
+/// storage are visited.
+macro_rules! impl_write_iter {
+    ($name:ident, [$($w:tt),*], [$($r:tt),*]) => {
+        /// Mutably iterates over all entities that have all specified components.
+        ///
+        /// # Panics
+        ///
+        /// Panics if the same component type is specified more than once.
+        #[allow(non_snake_case)]
+        pub fn $name<$($w: ComponentTrait,)* $($r: ComponentTrait),*>(&mut self)
+                -> impl Iterator<Item = (Entity, $(&mut $w),* $(, &$r)*)> + '_ {
+            assert_distinct(&[$((TypeId::of::<$w>(), type_name::<$w>()),)* $((TypeId::of::<$r>(), type_name::<$r>())),*]);
+            $(self.assert_access(TypeId::of::<$w>(), type_name::<$w>(), true);)*
+            $(self.assert_access(TypeId::of::<$r>(), type_name::<$r>(), false);)*
+
+            let generations = &self.generations;
+            let tick = self.tick;
+            $(let mut $w = None;)*
+            $(let mut $r = None;)*
+            for (t, s) in &mut self.storages {
+                $(if *t == TypeId::of::<$w>() { $w = Some(downcast_mut::<$w>(&mut **s).join_mut(tick)); continue; })*
+                $(if *t == TypeId::of::<$r>() { $r = Some(downcast_ref::<$r>(&**s)); continue; })*
             }
+            // See `Assembly::any_storage` for the validity of shared storages.
+            $(if $r.is_none() {
+                $r = self.shared.get(&TypeId::of::<$r>()).map(|&s| downcast_ref::<$r>(unsafe { &*s }));
+            })*
+
+            smallest(&[$($w.as_ref().map(|s| s.members()),)* $($r.map(|s| s.members())),*]).iter()
+                .filter_map(move |&i| {
+                    let i = i as usize;
+                    // The member list holds each index only once, thus every component is
+                    // borrowed at most once.
+                    Some((Entity::from_parts(i as u32, generations[i]),
+                          $(unsafe { $w.as_ref()?.get(i)? }),* $(, $r?.get(i)?)*))
+                })
+        }
+    };
+}
+
+/// Implements methods that collect all entities' components under the condition that all
+/// specified types must be present in each entity. Mutable version.
+macro_rules! impl_write {
+    ($name:ident, $base:ident, [$($w:tt),*], [$($r:tt),*]) => {
+        /// Mutably borrows from all entities that have all specified components.
+        ///
+        /// # Panics
+        ///
+        /// Panics if the same component type is specified more than once.
+        pub fn $name<$($w: ComponentTrait,)* $($r: ComponentTrait),*>(&mut self)
+                -> Vec<(Entity, $(&mut $w),* $(, &$r)*)> {
+            self.$base::<$($w,)* $($r),*>().collect()
+        }
+    };
+}
+
+/// Implements methods that collect all entities' components under the condition that all
+/// specified types must be present in each entity. Additionally accepts a filter function to
+/// filter components by their contents. Mutable version.
+macro_rules! impl_write_filtered {
+    ($name:ident, $base:ident, [$($w:tt),*], [$($r:tt),*]) => {
+        /// Mutably borrows from all entities that have all specified components and whose values
+        /// pass the specified filter.
+        ///
+        /// # Panics
+        ///
+        /// Panics if the same component type is specified more than once.
+        pub fn $name<F, $($w: ComponentTrait,)* $($r: ComponentTrait),*>(&mut self, filter: F)
+                -> Vec<(Entity, $(&mut $w),* $(, &$r)*)>
+                where for<'r> F: FnMut(&'r (Entity, $(&mut $w),* $(, &$r)*)) -> bool {
+            self.$base::<$($w,)* $($r),*>().filter(filter).collect()
+        }
+    };
+}
+
+/// Implements methods that ensure only a single entity matches the bounds given by the
+/// components. Errors otherwise. Mutable version.
+macro_rules! impl_write_single {
+    ($name:ident, $base:ident, [$($w:tt),*], [$($r:tt),*]) => {
+        /// Mutably borrows the specified components, ensuring that only a single entity matches
+        /// the given conditions.
+        ///
+        /// # Panics
+        ///
+        /// Panics if the same component type is specified more than once.
+        pub fn $name<$($w: ComponentTrait,)* $($r: ComponentTrait),*>(&mut self)
+                -> Result<(Entity, $(&mut $w),* $(, &$r)*), EcsError> {
+            self.$base::<$($w,)* $($r),*>().single()
+        }
+    };
+}
+
+/// Implements methods that ensure only a single entity matches the bounds given by the components
+/// and the specified filter. Errors otherwise. Mutable version.
+macro_rules! impl_write_single_filtered {
+    ($name:ident, $base:ident, [$($w:tt),*], [$($r:tt),*]) => {
+        /// Mutably borrows the specified components, ensuring that only a single entity matches
+        /// the given conditions (defined by the components and filter).
+        ///
+        /// # Panics
+        ///
+        /// Panics if the same component type is specified more than once.
+        pub fn $name<F, $($w: ComponentTrait,)* $($r: ComponentTrait),*>(&mut self, filter: F)
+                -> Result<(Entity, $(&mut $w),* $(, &$r)*), EcsError>
+                where for<'r> F: FnMut(&'r (Entity, $(&mut $w),* $(, &$r)*)) -> bool {
+            self.$base::<$($w,)* $($r),*>().filter(filter).single()
         }
     };
 }
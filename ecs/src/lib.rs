@@ -10,6 +10,13 @@
 extern crate bitflags;
 extern crate failure;
 #[macro_use]
+extern crate log;
+extern crate rayon;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
+#[macro_use]
 extern crate failure_derive;
 
 #[macro_use]
@@ -18,16 +25,36 @@ mod error;
 mod event;
 mod loop_stage;
 mod entity;
+mod access;
+mod aux_part;
 mod system;
 mod component_group;
+mod storage;
+mod query;
 mod assembly;
+mod command;
+mod schedule;
+mod timer;
+mod scene;
+mod prefab;
+mod snapshot;
 mod world;
 
 pub use self::error::EcsError;
-pub use self::event::{EcsEvent, EventTrait};
+pub use self::event::{EcsEvent, Event, EventFilter, EventStatistics, EventTrait};
 pub use self::loop_stage::{LoopStage, LoopStageFlag};
 pub use self::entity::Entity;
-pub use self::system::{DispatchEvents, SystemTrait};
+pub use self::access::Access;
+pub use self::aux_part::{AuxPart, SplitAux};
+pub use self::system::{DispatchEvents, DowncastSystem, SystemHandle, SystemTrait};
 pub use self::component_group::{ComponentGroup, ComponentTrait};
+pub use self::storage::StorageKind;
+pub use self::query::QueryExt;
 pub use self::assembly::Assembly;
+pub use self::command::{CommandBuffer, EntityBuilder};
+pub use self::schedule::ExecutionMode;
+pub use self::timer::{TimerHandle, Timers};
+pub use self::scene::{ComponentRegistry, Scene};
+pub use self::prefab::Prefab;
+pub use self::snapshot::{Snapshot, SnapshotChange};
 pub use self::world::World;
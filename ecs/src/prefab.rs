@@ -0,0 +1,134 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use serde_json::{self, Value};
+
+use assembly::Assembly;
+use entity::Entity;
+use error::EcsError;
+use scene::{read_json, ComponentRegistry, Scene};
+
+/// Describes a reusable bundle of serialized components, from which any number of entities may
+/// be instantiated. The components use the same representation as in a `Scene`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Prefab {
+    pub components: BTreeMap<String, Value>,
+}
+
+impl Prefab {
+    /// Creates an empty prefab.
+    pub fn new() -> Self {
+        Default::default()
+    }
+    /// Parses a prefab from its JSON representation.
+    pub fn from_json(json: &str) -> Result<Self, EcsError> {
+        Ok(serde_json::from_str(json)?)
+    }
+    /// Reads a prefab from the specified file.
+    pub fn read(path: &Path) -> Result<Self, EcsError> {
+        read_json(path)
+    }
+    /// Returns a copy of the prefab with the specified overrides applied. Objects are merged
+    /// field by field, any other value replaces the original one, and `null` removes the
+    /// component (or field) altogether.
+    pub fn with_overrides(&self, overrides: &BTreeMap<String, Value>) -> Prefab {
+        let mut components = self.components.clone();
+        for (name, value) in overrides {
+            if value.is_null() {
+                components.remove(name);
+            } else {
+                merge(components.entry(name.clone()).or_insert(Value::Null), value);
+            }
+        }
+        Prefab { components }
+    }
+    /// Creates an `Entity` from the prefab.
+    pub fn instantiate<X>(
+        &self,
+        assembly: &mut Assembly,
+        registry: &ComponentRegistry<X>,
+        context: &mut X,
+    ) -> Result<Entity, EcsError> {
+        let scene = Scene {
+            entities: vec![self.components.clone()],
+        };
+        let entities = registry.load(assembly, &scene, context)?;
+        Ok(entities[0])
+    }
+}
+
+/// Recursively merges the patch into the target value.
+fn merge(target: &mut Value, patch: &Value) {
+    match *patch {
+        Value::Object(ref p) if target.is_object() => if let Value::Object(ref mut t) = *target {
+            for (key, value) in p {
+                if value.is_null() {
+                    t.remove(key);
+                } else {
+                    merge(t.entry(key.clone()).or_insert(Value::Null), value);
+                }
+            }
+        },
+        _ => *target = patch.clone(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use component_group::ComponentTrait;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Label {
+        text: String,
+        size: u32,
+    }
+    impl ComponentTrait for Label {
+        fn name() -> &'static str {
+            "label"
+        }
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Marker;
+    impl ComponentTrait for Marker {
+        fn name() -> &'static str {
+            "marker"
+        }
+    }
+
+    #[test]
+    fn test_instantiate_with_overrides() {
+        let mut r = ComponentRegistry::new();
+        r.register::<Label>();
+        r.register::<Marker>();
+        let json = r#"{"components": {"label": {"text": "a", "size": 2}, "marker": null}}"#;
+        let prefab = Prefab::from_json(json).unwrap();
+
+        let mut overrides = BTreeMap::new();
+        overrides.insert("label".to_string(), json_value(r#"{"text": "b"}"#));
+        overrides.insert("marker".to_string(), Value::Null);
+
+        let mut a = Assembly::new();
+        let e = prefab.instantiate(&mut a, &r, &mut ()).unwrap();
+        assert!(a.has_component::<Marker>(&e));
+
+        let f = prefab
+            .with_overrides(&overrides)
+            .instantiate(&mut a, &r, &mut ())
+            .unwrap();
+        assert_eq!(
+            a.borrow_component::<Label>(&f).unwrap(),
+            &Label {
+                text: "b".into(),
+                size: 2,
+            }
+        );
+        assert!(!a.has_component::<Marker>(&f));
+        assert_eq!(a.borrow_component::<Label>(&e).unwrap().text, "a");
+    }
+
+    fn json_value(json: &str) -> Value {
+        serde_json::from_str(json).unwrap()
+    }
+}
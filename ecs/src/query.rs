@@ -0,0 +1,48 @@
+use error::EcsError;
+
+/// Provides adapters for the iterators returned by the `Assembly` queries (e.g. `r2_iter`), such
+/// that hot paths may inspect query results without allocating. Counting is covered by
+/// `Iterator::count`.
+pub trait QueryExt: Iterator + Sized {
+    /// Returns the first result of the query. Errors with `EcsError::ComponentNotFound` if the
+    /// query yields nothing.
+    fn first(mut self) -> Result<Self::Item, EcsError> {
+        self.next().ok_or(EcsError::ComponentNotFound)
+    }
+    /// Returns the only result of the query. Errors with `EcsError::ComponentNotFound` if the
+    /// query yields nothing, and with `EcsError::MultipleComponentsFound` if it yields more than
+    /// one result.
+    fn single(mut self) -> Result<Self::Item, EcsError> {
+        let item = self.next().ok_or(EcsError::ComponentNotFound)?;
+        match self.next() {
+            Some(_) => Err(EcsError::MultipleComponentsFound),
+            None => Ok(item),
+        }
+    }
+}
+
+impl<I: Iterator> QueryExt for I {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_first() {
+        assert!((0..0).first().is_err());
+        assert_eq!((3..5).first().unwrap(), 3);
+    }
+
+    #[test]
+    fn test_single() {
+        match (0..0).single() {
+            Err(EcsError::ComponentNotFound) => (),
+            r => panic!("Expected a missing component error, got {:?} instead", r),
+        }
+        match (0..2).single() {
+            Err(EcsError::MultipleComponentsFound) => (),
+            r => panic!("Expected a multiple components error, got {:?} instead", r),
+        }
+        assert_eq!((1..2).single().unwrap(), 1);
+    }
+}
@@ -0,0 +1,349 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::Path;
+
+use failure;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::{self, Value};
+
+use assembly::Assembly;
+use entity::Entity;
+use component_group::ComponentTrait;
+use error::EcsError;
+use snapshot::Snapshot;
+
+/// Serializes the component of the specified type of an `Entity`, if present.
+type SaveFn = Box<dyn Fn(&Assembly, &Entity) -> Option<Result<Value, failure::Error>>>;
+
+/// Deserializes a component and adds it to the specified `Entity`.
+type LoadFn<X> = Box<dyn Fn(&mut Assembly, &Entity, Value, &mut X) -> Result<(), failure::Error>>;
+
+/// Removes the component of the specified type from an `Entity`, if present.
+type RemoveFn = Box<dyn Fn(&mut Assembly, &Entity)>;
+
+/// Holds a single registered component type.
+struct Entry<X> {
+    name: &'static str,
+    save: SaveFn,
+    load: LoadFn<X>,
+    remove: RemoveFn,
+}
+
+/// Maps the stable names of component types (see `ComponentTrait::name`) to functions that
+/// serialize and deserialize them. Components that cannot be serialized directly, such as those
+/// backed by GPU resources, may be registered with custom functions that store a reference to
+/// their assets instead, and rebuild them from a loading context of type `X`.
+///
+/// Components are loaded in the order of registration, such that required components must be
+/// registered before the components that require them.
+pub struct ComponentRegistry<X = ()> {
+    entries: Vec<Entry<X>>,
+}
+
+impl<X> Default for ComponentRegistry<X> {
+    fn default() -> Self {
+        ComponentRegistry {
+            entries: Vec::new(),
+        }
+    }
+}
+
+impl<X> ComponentRegistry<X> {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Default::default()
+    }
+    /// Registers a component type that implements `Serialize` and `Deserialize`.
+    pub fn register<C>(&mut self)
+    where
+        C: ComponentTrait + Serialize + DeserializeOwned,
+    {
+        self.register_with::<C, _, _>(
+            |c| Ok(serde_json::to_value(c)?),
+            |v, _| Ok(serde_json::from_value(v)?),
+        )
+    }
+    /// Registers a component type with custom functions to convert it to and from its
+    /// serialized representation. Any previous registration of the same name is replaced.
+    pub fn register_with<C, S, L>(&mut self, save: S, load: L)
+    where
+        C: ComponentTrait,
+        S: Fn(&C) -> Result<Value, failure::Error> + 'static,
+        L: Fn(Value, &mut X) -> Result<C, failure::Error> + 'static,
+    {
+        self.register_dependent::<C, _, _>(save, move |value, _, _, context| load(value, context))
+    }
+    /// Registers a component type whose loading function may also inspect the components that
+    /// were loaded before it (in the order of registration), e.g. to derive a bounding volume
+    /// from a mesh.
+    pub fn register_dependent<C, S, L>(&mut self, save: S, load: L)
+    where
+        C: ComponentTrait,
+        S: Fn(&C) -> Result<Value, failure::Error> + 'static,
+        L: Fn(Value, &Assembly, &Entity, &mut X) -> Result<C, failure::Error> + 'static,
+    {
+        let entry = Entry {
+            name: C::name(),
+            save: Box::new(move |assembly, entity| {
+                assembly.borrow_component::<C>(entity).ok().map(&save)
+            }),
+            load: Box::new(move |assembly, entity, value, context| {
+                let component = load(value, assembly, entity, context)?;
+                assembly.add_component(entity, component)?;
+                Ok(())
+            }),
+            remove: Box::new(|assembly, entity| {
+                assembly.remove_component::<C>(entity).ok();
+            }),
+        };
+
+        self.entries.retain(|e| e.name != C::name());
+        self.entries.push(entry);
+    }
+    /// Returns the names of all registered component types in the order of registration.
+    pub fn names(&self) -> Vec<&'static str> {
+        self.entries.iter().map(|e| e.name).collect()
+    }
+    /// Serializes the registered components of all entities. Entities without any registered
+    /// components are omitted.
+    pub fn save(&self, assembly: &Assembly) -> Result<Scene, EcsError> {
+        let mut scene = Scene::new();
+        for entity in assembly.entities() {
+            let components = self.save_entity(assembly, &entity)?;
+            if !components.is_empty() {
+                scene.entities.push(components);
+            }
+        }
+        Ok(scene)
+    }
+    /// Creates an `Entity` for each entry of the scene and adds the deserialized components.
+    /// If any component cannot be loaded, the entities created so far are destroyed again.
+    pub fn load(
+        &self,
+        assembly: &mut Assembly,
+        scene: &Scene,
+        context: &mut X,
+    ) -> Result<Vec<Entity>, EcsError> {
+        for name in scene.entities.iter().flat_map(|c| c.keys()) {
+            if !self.entries.iter().any(|e| e.name == name) {
+                return Err(EcsError::UnregisteredComponent(name.clone()));
+            }
+        }
+
+        let mut entities = Vec::new();
+        for components in &scene.entities {
+            let entity = assembly.create_entity();
+            entities.push(entity);
+
+            for entry in &self.entries {
+                if let Some(value) = components.get(entry.name) {
+                    let loaded = (entry.load)(assembly, &entity, value.clone(), context);
+                    if let Err(e) = loaded {
+                        for entity in &entities {
+                            assembly.destroy_entity(entity);
+                        }
+                        return Err(serialization_error(entry.name, &e));
+                    }
+                }
+            }
+        }
+        Ok(entities)
+    }
+    /// Captures the registered components of all entities, including those without any
+    /// registered components, at the current update tick.
+    pub fn snapshot(&self, assembly: &Assembly) -> Result<Snapshot, EcsError> {
+        let mut entities = BTreeMap::new();
+        for entity in assembly.entities() {
+            entities.insert(entity, self.save_entity(assembly, &entity)?);
+        }
+        Ok(Snapshot::new(assembly.timers().ticks(), entities))
+    }
+    /// Rolls the `Assembly` back to the state of the snapshot: Entities created since are
+    /// destroyed, destroyed ones are revived with their original handles, and registered
+    /// components that differ from the snapshot are reloaded or removed. Unregistered components
+    /// of surviving entities are left untouched. If a component cannot be loaded, the restore
+    /// stops with an error and the `Assembly` is left partially restored.
+    pub fn restore(
+        &self,
+        assembly: &mut Assembly,
+        snapshot: &Snapshot,
+        context: &mut X,
+    ) -> Result<(), EcsError> {
+        let created: Vec<Entity> = assembly
+            .entities()
+            .filter(|e| snapshot.components(e).is_none())
+            .collect();
+        for entity in &created {
+            assembly.destroy_entity(entity);
+        }
+
+        for (entity, components) in snapshot.iter() {
+            assembly.revive_entity(entity)?;
+            for entry in &self.entries {
+                let current = match (entry.save)(assembly, entity) {
+                    Some(Ok(value)) => Some(value),
+                    Some(Err(e)) => return Err(serialization_error(entry.name, &e)),
+                    None => None,
+                };
+                match components.get(entry.name) {
+                    Some(value) if current.as_ref() != Some(value) => {
+                        (entry.load)(assembly, entity, value.clone(), context)
+                            .map_err(|e| serialization_error(entry.name, &e))?;
+                    }
+                    None if current.is_some() => (entry.remove)(assembly, entity),
+                    _ => (),
+                }
+            }
+        }
+        Ok(())
+    }
+    /// Serializes the registered components of the specified `Entity`.
+    fn save_entity(
+        &self,
+        assembly: &Assembly,
+        entity: &Entity,
+    ) -> Result<BTreeMap<String, Value>, EcsError> {
+        let mut components = BTreeMap::new();
+        for entry in &self.entries {
+            if let Some(value) = (entry.save)(assembly, entity) {
+                let value = value.map_err(|e| serialization_error(entry.name, &e))?;
+                components.insert(entry.name.to_string(), value);
+            }
+        }
+        Ok(components)
+    }
+}
+
+/// Reads and parses the specified JSON file.
+pub(crate) fn read_json<T: DeserializeOwned>(path: &Path) -> Result<T, EcsError> {
+    let mut json = String::new();
+    File::open(path).and_then(|mut f| f.read_to_string(&mut json))?;
+    Ok(serde_json::from_str(&json)?)
+}
+
+/// Describes a failure to convert the named component.
+fn serialization_error(name: &str, error: &failure::Error) -> EcsError {
+    EcsError::ComponentSerialization(name.to_string(), error.to_string())
+}
+
+/// Holds the serialized components of a set of entities. Each entity is represented by a map
+/// from component names to component data. Scenes are stored as human-editable JSON files.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Scene {
+    pub entities: Vec<BTreeMap<String, Value>>,
+}
+
+impl Scene {
+    /// Creates an empty scene.
+    pub fn new() -> Self {
+        Default::default()
+    }
+    /// Parses a scene from its JSON representation.
+    pub fn from_json(json: &str) -> Result<Self, EcsError> {
+        Ok(serde_json::from_str(json)?)
+    }
+    /// Returns the (pretty-printed) JSON representation of the scene.
+    pub fn to_json(&self) -> Result<String, EcsError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+    /// Reads a scene from the specified file.
+    pub fn read(path: &Path) -> Result<Self, EcsError> {
+        read_json(path)
+    }
+    /// Writes the scene to the specified file.
+    pub fn write(&self, path: &Path) -> Result<(), EcsError> {
+        let json = self.to_json()?;
+        File::create(path).and_then(|mut f| f.write_all(json.as_bytes()))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Name(String);
+    impl ComponentTrait for Name {
+        fn name() -> &'static str {
+            "name"
+        }
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Position(i32, i32);
+    impl ComponentTrait for Position {
+        fn name() -> &'static str {
+            "position"
+        }
+    }
+
+    /// Stands in for a component that is backed by an external resource.
+    #[derive(Debug, PartialEq)]
+    struct Handle(u32);
+    impl ComponentTrait for Handle {
+        fn name() -> &'static str {
+            "handle"
+        }
+    }
+
+    fn registry() -> ComponentRegistry<u32> {
+        let mut r = ComponentRegistry::new();
+        r.register::<Name>();
+        r.register::<Position>();
+        r.register_with::<Handle, _, _>(
+            |_| Ok(Value::String("asset".into())),
+            |_, next: &mut u32| {
+                *next += 1;
+                Ok(Handle(*next))
+            },
+        );
+        r
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let r = registry();
+        let mut a = Assembly::new();
+        let e = a.create_entity();
+        a.add_component(&e, Name("a".into())).unwrap();
+        a.add_component(&e, Position(1, 2)).unwrap();
+        a.add_component(&e, Handle(7)).unwrap();
+        a.create_entity();
+
+        let scene = r.save(&a).unwrap();
+        assert_eq!(scene.entities.len(), 1);
+        let json = scene.to_json().unwrap();
+        assert_eq!(Scene::from_json(&json).unwrap(), scene);
+
+        let mut b = Assembly::new();
+        let mut next = 0;
+        let entities = r.load(&mut b, &scene, &mut next).unwrap();
+        assert_eq!(entities.len(), 1);
+        assert_eq!(b.borrow_component::<Name>(&entities[0]).unwrap(), &Name("a".into()));
+        assert_eq!(b.borrow_component::<Position>(&entities[0]).unwrap(), &Position(1, 2));
+        assert_eq!(b.borrow_component::<Handle>(&entities[0]).unwrap(), &Handle(1));
+    }
+
+    #[test]
+    fn test_load_errors() {
+        let r = registry();
+        let mut a = Assembly::new();
+
+        let scene = Scene::from_json(r#"{"entities": [{"velocity": [1, 2]}]}"#).unwrap();
+        match r.load(&mut a, &scene, &mut 0) {
+            Err(EcsError::UnregisteredComponent(ref n)) if n == "velocity" => (),
+            r => panic!("Unexpected result {:?}", r),
+        }
+
+        let json = r#"{"entities": [{"name": "a"}, {"name": "b", "position": "c"}]}"#;
+        let scene = Scene::from_json(json).unwrap();
+        match r.load(&mut a, &scene, &mut 0) {
+            Err(EcsError::ComponentSerialization(ref n, _)) if n == "position" => (),
+            r => panic!("Unexpected result {:?}", r),
+        }
+        assert_eq!(a.entity_count(), 0);
+    }
+}
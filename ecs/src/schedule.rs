@@ -0,0 +1,216 @@
+use std::collections::BTreeSet;
+
+use loop_stage::LoopStage;
+use system::SystemTrait;
+use error::EcsError;
+
+/// Selects how the `World` executes the systems of the update stages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExecutionMode {
+    /// Calls all systems one after another on the current thread, in the resolved order.
+    #[default]
+    SingleThreaded,
+    /// Calls systems without conflicting `Access` concurrently on a thread pool.
+    Parallel,
+}
+
+/// Sorts the supplied systems topologically according to their labels and `before`/`after`
+/// constraints, and returns the resulting order as indices into `systems`. Systems that are not
+/// constrained relative to each other retain their insertion order. Constraints that refer to
+/// unknown labels are ignored.
+///
+/// Because the order is valid for the whole set of systems, it is also valid for each subset of
+/// systems that participates in a particular `LoopStage`.
+pub fn resolve_order<A, S>(systems: &[S]) -> Result<Vec<usize>, EcsError>
+where
+    S: SystemTrait<A>,
+{
+    let n = systems.len();
+    let mut successors = vec![Vec::new(); n];
+    let mut in_degree = vec![0usize; n];
+
+    for (a, sa) in systems.iter().enumerate() {
+        for (b, sb) in systems.iter().enumerate() {
+            if a == b {
+                continue;
+            }
+            let a_before_b = refers_to(sa.get_before(), sb.get_label())
+                || refers_to(sb.get_after(), sa.get_label());
+
+            if a_before_b {
+                successors[a].push(b);
+                in_degree[b] += 1;
+            }
+        }
+    }
+
+    // Kahn's algorithm, where the lowest ready index is always picked first.
+    let mut ready = (0..n).filter(|&i| in_degree[i] == 0).collect::<BTreeSet<_>>();
+    let mut order = Vec::with_capacity(n);
+    while let Some(&i) = ready.iter().next() {
+        ready.remove(&i);
+        order.push(i);
+        for &j in &successors[i] {
+            in_degree[j] -= 1;
+            if in_degree[j] == 0 {
+                ready.insert(j);
+            }
+        }
+    }
+
+    if order.len() < n {
+        let names = (0..n)
+            .filter(|&i| in_degree[i] > 0)
+            .map(|i| match systems[i].get_label() {
+                Some(l) => l.to_string(),
+                None => format!("#{}", i),
+            })
+            .collect();
+        return Err(EcsError::CyclicSystemOrder(names));
+    }
+
+    Ok(order)
+}
+
+/// Groups the systems that participate in the specified `LoopStage` into consecutive batches,
+/// following the supplied order. A batch contains no two systems with conflicting `Access`, or
+/// with an ordering constraint between them, thus its systems may run concurrently.
+pub fn batches<A, S>(systems: &[S], order: &[usize], stage: &LoopStage) -> Vec<Vec<usize>>
+where
+    S: SystemTrait<A>,
+{
+    let mut batches: Vec<Vec<usize>> = Vec::new();
+    let mut accesses = Vec::new();
+
+    for &i in order {
+        let s = &systems[i];
+        if !stage.match_filter(s.get_loop_stage_filter()) {
+            continue;
+        }
+
+        let access = s.get_access();
+        let fits = match batches.last() {
+            Some(b) => b.iter().zip(&accesses).all(|(&j, a)| {
+                let t = &systems[j];
+                !access.conflicts_with(a) && !refers_to(s.get_before(), t.get_label())
+                    && !refers_to(s.get_after(), t.get_label())
+                    && !refers_to(t.get_before(), s.get_label())
+                    && !refers_to(t.get_after(), s.get_label())
+            }),
+            None => false,
+        };
+
+        if fits {
+            batches.last_mut().unwrap().push(i);
+            accesses.push(access);
+        } else {
+            batches.push(vec![i]);
+            accesses = vec![access];
+        }
+    }
+
+    batches
+}
+
+/// Returns `true` if the list of labels contains the specified label.
+fn refers_to(labels: &[&str], label: Option<&str>) -> bool {
+    match label {
+        Some(l) => labels.contains(&l),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use loop_stage::LoopStageFlag;
+    use assembly::Assembly;
+    use access::Access;
+    use component_group::ComponentTrait;
+
+    struct Position;
+    impl ComponentTrait for Position {}
+
+    struct Velocity;
+    impl ComponentTrait for Velocity {}
+
+    struct Node(
+        Option<&'static str>,
+        &'static [&'static str],
+        &'static [&'static str],
+        Access,
+    );
+
+    impl SystemTrait<()> for Node {
+        fn verify_requirements(&self, _: &Assembly) -> bool {
+            true
+        }
+        fn get_loop_stage_filter(&self) -> LoopStageFlag {
+            LoopStageFlag::ALL_STAGES
+        }
+        fn get_label(&self) -> Option<&'static str> {
+            self.0
+        }
+        fn get_before(&self) -> &'static [&'static str] {
+            self.1
+        }
+        fn get_after(&self) -> &'static [&'static str] {
+            self.2
+        }
+        fn get_access(&self) -> Access {
+            self.3.clone()
+        }
+    }
+
+    #[test]
+    fn test_resolve_order() {
+        let systems = vec![
+            Node(Some("render"), &[], &["physics"], Access::new()),
+            Node(None, &[], &[], Access::new()),
+            Node(Some("physics"), &[], &["input"], Access::new()),
+            Node(Some("input"), &["render"], &[], Access::new()),
+            Node(Some("audio"), &[], &["unknown"], Access::new()),
+        ];
+
+        assert_eq!(
+            resolve_order::<(), _>(&systems).unwrap(),
+            vec![1, 3, 2, 0, 4]
+        );
+    }
+
+    #[test]
+    fn test_resolve_order_cycle() {
+        let systems = vec![
+            Node(Some("a"), &["b"], &[], Access::new()),
+            Node(Some("b"), &[], &[], Access::new()),
+            Node(Some("c"), &[], &[], Access::new()),
+            Node(None, &["a"], &["b"], Access::new()),
+        ];
+
+        match resolve_order::<(), _>(&systems) {
+            Err(EcsError::CyclicSystemOrder(names)) => {
+                assert_eq!(names, vec!["a".to_string(), "b".to_string(), "#3".to_string()])
+            }
+            r => panic!("Expected a cycle, got {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_batches() {
+        let systems = vec![
+            Node(Some("a"), &[], &[], Access::new().write::<Position>()),
+            Node(Some("b"), &[], &[], Access::new().read::<Velocity>()),
+            Node(Some("c"), &[], &[], Access::new().read::<Position>()),
+            Node(Some("d"), &[], &["c"], Access::new().read::<Velocity>()),
+            Node(None, &[], &[], Access::exclusive()),
+            Node(None, &[], &[], Access::new()),
+            Node(None, &[], &[], Access::new().write::<Velocity>()),
+        ];
+        let order = resolve_order::<(), _>(&systems).unwrap();
+
+        assert_eq!(
+            batches::<(), _>(&systems, &order, &LoopStage::Update),
+            vec![vec![0, 1], vec![2], vec![3], vec![4], vec![5, 6]]
+        );
+    }
+}
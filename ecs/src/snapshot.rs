@@ -0,0 +1,210 @@
+use std::collections::BTreeMap;
+use std::collections::btree_map::Iter;
+use std::fmt;
+
+use serde_json::Value;
+
+use entity::Entity;
+
+/// Captures the serialized components of all entities at a given update tick, such that the
+/// `Assembly` may later be rolled back to that state (see `ComponentRegistry::restore`). Only
+/// components known to the `ComponentRegistry` are captured.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Snapshot {
+    /// Holds the update tick at which the snapshot was taken.
+    tick: u64,
+    /// Holds the serialized components of each entity.
+    entities: BTreeMap<Entity, BTreeMap<String, Value>>,
+}
+
+impl Snapshot {
+    /// Creates a new snapshot from the serialized components of each entity.
+    pub(crate) fn new(tick: u64, entities: BTreeMap<Entity, BTreeMap<String, Value>>) -> Self {
+        Snapshot { tick, entities }
+    }
+    /// Returns the update tick at which the snapshot was taken.
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+    /// Returns the number of captured entities.
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+    /// Returns `true` if no entities were captured.
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+    /// Returns the serialized components of the specified `Entity`, if it was captured.
+    pub fn components(&self, entity: &Entity) -> Option<&BTreeMap<String, Value>> {
+        self.entities.get(entity)
+    }
+    /// Returns an iterator over all captured entities and their serialized components.
+    pub fn iter(&self) -> Iter<'_, Entity, BTreeMap<String, Value>> {
+        self.entities.iter()
+    }
+    /// Lists the changes that lead from this snapshot to the other one, ordered by `Entity`.
+    pub fn diff(&self, other: &Snapshot) -> Vec<SnapshotChange> {
+        let mut changes = Vec::new();
+        for (&entity, before) in &self.entities {
+            let after = match other.entities.get(&entity) {
+                Some(after) => after,
+                None => {
+                    changes.push(SnapshotChange::EntityDestroyed(entity));
+                    continue;
+                }
+            };
+            for (name, value) in before {
+                match after.get(name) {
+                    Some(v) if v == value => (),
+                    Some(_) => changes.push(SnapshotChange::ComponentChanged(entity, name.clone())),
+                    None => changes.push(SnapshotChange::ComponentRemoved(entity, name.clone())),
+                }
+            }
+            for name in after.keys().filter(|n| !before.contains_key(*n)) {
+                changes.push(SnapshotChange::ComponentAdded(entity, name.clone()));
+            }
+        }
+        for &entity in other.entities.keys() {
+            if !self.entities.contains_key(&entity) {
+                changes.push(SnapshotChange::EntityCreated(entity));
+            }
+        }
+        changes.sort_by_key(|c| c.entity());
+        changes
+    }
+}
+
+/// Describes a single difference between two snapshots.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SnapshotChange {
+    EntityCreated(Entity),
+    EntityDestroyed(Entity),
+    ComponentAdded(Entity, String),
+    ComponentRemoved(Entity, String),
+    ComponentChanged(Entity, String),
+}
+
+impl SnapshotChange {
+    /// Returns the `Entity` affected by the change.
+    pub fn entity(&self) -> Entity {
+        match *self {
+            SnapshotChange::EntityCreated(e)
+            | SnapshotChange::EntityDestroyed(e)
+            | SnapshotChange::ComponentAdded(e, _)
+            | SnapshotChange::ComponentRemoved(e, _)
+            | SnapshotChange::ComponentChanged(e, _) => e,
+        }
+    }
+}
+
+impl fmt::Display for SnapshotChange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SnapshotChange::EntityCreated(e) => write!(f, "+ {}", e),
+            SnapshotChange::EntityDestroyed(e) => write!(f, "- {}", e),
+            SnapshotChange::ComponentAdded(e, ref n) => write!(f, "+ {}: {}", e, n),
+            SnapshotChange::ComponentRemoved(e, ref n) => write!(f, "- {}: {}", e, n),
+            SnapshotChange::ComponentChanged(e, ref n) => write!(f, "~ {}: {}", e, n),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use assembly::Assembly;
+    use component_group::ComponentTrait;
+    use scene::ComponentRegistry;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Health(u32);
+    impl ComponentTrait for Health {
+        fn name() -> &'static str {
+            "health"
+        }
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Shield(u32);
+    impl ComponentTrait for Shield {
+        fn name() -> &'static str {
+            "shield"
+        }
+    }
+
+    /// Is not registered for serialization.
+    #[derive(Debug, PartialEq)]
+    struct Transient;
+    impl ComponentTrait for Transient {}
+
+    fn registry() -> ComponentRegistry {
+        let mut r = ComponentRegistry::new();
+        r.register::<Health>();
+        r.register::<Shield>();
+        r
+    }
+
+    #[test]
+    fn test_diff_and_restore() {
+        let r = registry();
+        let mut a = Assembly::new();
+        let e = a.create_entity();
+        let f = a.create_entity();
+        let g = a.create_entity();
+        a.add_component(&e, Health(10)).unwrap();
+        a.add_component(&e, Transient).unwrap();
+        a.add_component(&f, Health(5)).unwrap();
+        a.add_component(&f, Shield(1)).unwrap();
+        let before = a.snapshot(&r).unwrap();
+        assert_eq!(before.len(), 3);
+
+        a.add_component(&e, Health(3)).unwrap();
+        a.add_component(&e, Shield(2)).unwrap();
+        a.remove_component::<Shield>(&f).unwrap();
+        a.destroy_entity(&g);
+        let h = a.create_entity();
+        let after = a.snapshot(&r).unwrap();
+
+        assert_eq!(
+            before.diff(&after),
+            vec![
+                SnapshotChange::ComponentChanged(e, "health".into()),
+                SnapshotChange::ComponentAdded(e, "shield".into()),
+                SnapshotChange::ComponentRemoved(f, "shield".into()),
+                SnapshotChange::EntityDestroyed(g),
+                SnapshotChange::EntityCreated(h),
+            ]
+        );
+
+        a.restore(&r, &before, &mut ()).unwrap();
+        assert_eq!(a.snapshot(&r).unwrap(), before);
+        assert!(a.has_entity(&g));
+        assert!(!a.has_entity(&h));
+        assert!(a.has_component::<Transient>(&e));
+        assert_eq!(a.borrow_component::<Shield>(&f).unwrap(), &Shield(1));
+        assert!(before.diff(&before).is_empty());
+    }
+
+    #[test]
+    fn test_restore_keeps_stale_handles() {
+        let r = registry();
+        let mut a = Assembly::new();
+        let e = a.create_entity();
+        let before = a.snapshot(&r).unwrap();
+
+        a.destroy_entity(&e);
+        let f = a.create_entity();
+        assert_eq!(f.index(), e.index());
+
+        a.restore(&r, &before, &mut ()).unwrap();
+        assert!(a.has_entity(&e));
+        assert!(!a.has_entity(&f));
+
+        // Reusing the index of the revived entity must not bring the newer handle back to life.
+        a.destroy_entity(&e);
+        let g = a.create_entity();
+        assert_eq!(g.index(), e.index());
+        assert!(g.generation() > f.generation());
+        assert!(!a.has_entity(&f));
+    }
+}
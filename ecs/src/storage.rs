@@ -0,0 +1,454 @@
+use std::any::Any;
+use std::fmt;
+use std::iter::Zip;
+use std::marker::PhantomData;
+use std::slice;
+
+use component_group::ComponentTrait;
+
+/// Selects how the components of a single type are laid out in memory.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StorageKind {
+    /// Stores components in a vector that is indexed directly by the entity index. Lookups are
+    /// fast, but memory usage is proportional to the highest entity index. Suited for components
+    /// that most entities have.
+    #[default]
+    Dense,
+    /// Stores components in a packed vector alongside a sparse index. The components themselves
+    /// take up memory proportional to their number, while the index takes up a few bytes per
+    /// entity up to the highest entity index. Suited for rare or large components.
+    Sparse,
+}
+
+/// Holds all components of a single type, indexed by the entity index. Regardless of the
+/// `StorageKind`, the storage maintains a packed list of its member indices, such that iteration
+/// is proportional to the number of components and not to the number of entities.
+pub struct Storage<C> {
+    /// Maps entity indices to positions within `members`. Its length is bounded by the highest
+    /// entity index that was ever added, regardless of the `StorageKind`.
+    positions: Vec<Option<u32>>,
+    /// Holds the entity indices of all components in packed form.
+    members: Vec<u32>,
+    /// Holds the tick at which each component was added, parallel to `members`.
+    added: Vec<u64>,
+    /// Holds the tick at which each component was last mutably accessed, parallel to `members`.
+    changed: Vec<u64>,
+    /// Holds the actual components.
+    data: Data<C>,
+}
+
+/// Describes the layout of the component data according to `StorageKind`.
+enum Data<C> {
+    /// Indexed by the entity index.
+    Dense(Vec<Option<C>>),
+    /// Indexed by the position within `Storage::members`.
+    Sparse(Vec<C>),
+}
+
+impl<C: ComponentTrait> Storage<C> {
+    /// Creates a new, empty `Storage` of the specified kind.
+    pub fn new(kind: StorageKind) -> Self {
+        Storage {
+            positions: Vec::new(),
+            members: Vec::new(),
+            added: Vec::new(),
+            changed: Vec::new(),
+            data: match kind {
+                StorageKind::Dense => Data::Dense(Vec::new()),
+                StorageKind::Sparse => Data::Sparse(Vec::new()),
+            },
+        }
+    }
+    /// Returns the number of components in the `Storage`.
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+    /// Returns `true` if the `Storage` holds a component for the specified entity index.
+    pub fn contains(&self, index: usize) -> bool {
+        self.position(index).is_some()
+    }
+    /// Borrows the component of the specified entity index.
+    pub fn get(&self, index: usize) -> Option<&C> {
+        match self.data {
+            Data::Dense(ref d) => d.get(index).and_then(|c| c.as_ref()),
+            Data::Sparse(ref d) => self.position(index).map(|p| &d[p]),
+        }
+    }
+    /// Mutably borrows the component of the specified entity index and marks it as changed at
+    /// the specified tick.
+    pub fn get_mut(&mut self, index: usize, tick: u64) -> Option<&mut C> {
+        let position = self.position(index)?;
+        self.changed[position] = tick;
+        match self.data {
+            Data::Dense(ref mut d) => d[index].as_mut(),
+            Data::Sparse(ref mut d) => Some(&mut d[position]),
+        }
+    }
+    /// Inserts a component for the specified entity index at the specified tick. If available,
+    /// returns the previous component, in which case the component counts as changed rather than
+    /// added.
+    pub fn insert(&mut self, index: usize, component: C, tick: u64) -> Option<C> {
+        if let Some(position) = self.position(index) {
+            self.changed[position] = tick;
+            return match self.data {
+                Data::Dense(ref mut d) => d[index].replace(component),
+                Data::Sparse(ref mut d) => Some(::std::mem::replace(&mut d[position], component)),
+            };
+        }
+
+        if self.positions.len() <= index {
+            self.positions.resize(index + 1, None);
+        }
+        self.positions[index] = Some(self.members.len() as u32);
+        self.members.push(index as u32);
+        self.added.push(tick);
+        self.changed.push(tick);
+
+        match self.data {
+            Data::Dense(ref mut d) => {
+                if d.len() <= index {
+                    d.resize_with(index + 1, || None);
+                }
+                d[index] = Some(component);
+            }
+            Data::Sparse(ref mut d) => d.push(component),
+        }
+        None
+    }
+    /// Removes the component of the specified entity index and returns it.
+    pub fn remove(&mut self, index: usize) -> Option<C> {
+        let position = self.position(index)?;
+        self.positions[index] = None;
+        self.members.swap_remove(position);
+        self.added.swap_remove(position);
+        self.changed.swap_remove(position);
+        if let Some(&moved) = self.members.get(position) {
+            self.positions[moved as usize] = Some(position as u32);
+        }
+
+        match self.data {
+            Data::Dense(ref mut d) => d[index].take(),
+            Data::Sparse(ref mut d) => Some(d.swap_remove(position)),
+        }
+    }
+    /// Returns an iterator over all entity indices and their components.
+    pub fn iter(&self) -> Iter<'_, C> {
+        match self.data {
+            Data::Dense(ref d) => Iter::Dense(self.members.iter(), d),
+            Data::Sparse(ref d) => Iter::Sparse(self.members.iter().zip(d.iter())),
+        }
+    }
+    /// Returns an iterator over all entity indices and their mutable components. Each yielded
+    /// component is marked as changed at the specified tick.
+    pub fn iter_mut(&mut self, tick: u64) -> IterMut<'_, C> {
+        let inner = match self.data {
+            Data::Dense(ref mut d) => IterMutInner::Dense(self.members.iter(), d.as_mut_ptr(), PhantomData),
+            Data::Sparse(ref mut d) => IterMutInner::Sparse(self.members.iter().zip(d.iter_mut())),
+        };
+        IterMut {
+            inner,
+            changed: self.changed.iter_mut(),
+            tick,
+        }
+    }
+    /// Splits the storage into a view that hands out mutable references to components of
+    /// distinct entity indices, such that several storages may be joined mutably. Each borrowed
+    /// component is marked as changed at the specified tick.
+    pub fn join_mut(&mut self, tick: u64) -> JoinMut<'_, C> {
+        JoinMut {
+            positions: &self.positions,
+            members: &self.members,
+            changed: self.changed.as_mut_ptr(),
+            tick,
+            data: match self.data {
+                Data::Dense(ref mut d) => DataPtr::Dense(d.as_mut_ptr()),
+                Data::Sparse(ref mut d) => DataPtr::Sparse(d.as_mut_ptr()),
+            },
+            _marker: PhantomData,
+        }
+    }
+    /// Returns the entity indices of all components that were added after the specified tick.
+    pub fn added_since(&self, since: u64) -> impl Iterator<Item = usize> + '_ {
+        self.members
+            .iter()
+            .zip(self.added.iter())
+            .filter(move |&(_, &t)| t > since)
+            .map(|(&i, _)| i as usize)
+    }
+    /// Returns the entity indices of all components that were added or mutably accessed after
+    /// the specified tick.
+    pub fn changed_since(&self, since: u64) -> impl Iterator<Item = usize> + '_ {
+        self.members
+            .iter()
+            .zip(self.changed.iter())
+            .filter(move |&(_, &t)| t > since)
+            .map(|(&i, _)| i as usize)
+    }
+    /// Returns the position of the specified entity index within the packed member list.
+    fn position(&self, index: usize) -> Option<usize> {
+        self.positions.get(index).and_then(|p| p.map(|p| p as usize))
+    }
+}
+
+impl<C> fmt::Debug for Storage<C> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let kind = match self.data {
+            Data::Dense(_) => StorageKind::Dense,
+            Data::Sparse(_) => StorageKind::Sparse,
+        };
+        f.debug_struct("Storage")
+            .field("kind", &kind)
+            .field("len", &self.members.len())
+            .finish()
+    }
+}
+
+/// Iterates over the entity indices and components of a `Storage`.
+pub enum Iter<'a, C: 'a> {
+    Dense(slice::Iter<'a, u32>, &'a [Option<C>]),
+    Sparse(Zip<slice::Iter<'a, u32>, slice::Iter<'a, C>>),
+}
+
+impl<'a, C: 'a> Iterator for Iter<'a, C> {
+    type Item = (usize, &'a C);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match *self {
+            Iter::Dense(ref mut members, data) => {
+                let index = *members.next()? as usize;
+                data[index].as_ref().map(|c| (index, c))
+            }
+            Iter::Sparse(ref mut inner) => inner.next().map(|(&i, c)| (i as usize, c)),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match *self {
+            Iter::Dense(ref members, _) => members.size_hint(),
+            Iter::Sparse(ref inner) => inner.size_hint(),
+        }
+    }
+}
+
+/// Iterates over the entity indices and mutable components of a `Storage`.
+pub struct IterMut<'a, C: 'a> {
+    inner: IterMutInner<'a, C>,
+    changed: slice::IterMut<'a, u64>,
+    tick: u64,
+}
+
+impl<'a, C: 'a> Iterator for IterMut<'a, C> {
+    type Item = (usize, &'a mut C);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = self.inner.next()?;
+        if let Some(t) = self.changed.next() {
+            *t = self.tick;
+        }
+        Some(item)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+/// Iterates over the entity indices and mutable components of a `Storage`, in the order of the
+/// packed member list.
+enum IterMutInner<'a, C: 'a> {
+    Dense(slice::Iter<'a, u32>, *mut Option<C>, PhantomData<&'a mut C>),
+    Sparse(Zip<slice::Iter<'a, u32>, slice::IterMut<'a, C>>),
+}
+
+impl<'a, C: 'a> Iterator for IterMutInner<'a, C> {
+    type Item = (usize, &'a mut C);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match *self {
+            IterMutInner::Dense(ref mut members, data, _) => {
+                let index = *members.next()? as usize;
+                // The member list holds each index at most once, and only indices that are in
+                // bounds of the dense data vector. Thus, no two returned references alias.
+                let component = unsafe { (*data.add(index)).as_mut() };
+                component.map(|c| (index, c))
+            }
+            IterMutInner::Sparse(ref mut inner) => inner.next().map(|(&i, c)| (i as usize, c)),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match *self {
+            IterMutInner::Dense(ref members, _, _) => members.size_hint(),
+            IterMutInner::Sparse(ref inner) => inner.size_hint(),
+        }
+    }
+}
+
+/// Mutably borrows a `Storage` for the duration of a join over several component types.
+pub struct JoinMut<'a, C: 'a> {
+    positions: &'a [Option<u32>],
+    members: &'a [u32],
+    changed: *mut u64,
+    tick: u64,
+    data: DataPtr<C>,
+    _marker: PhantomData<&'a mut C>,
+}
+
+/// Points to the component data of a mutably borrowed `Storage`.
+enum DataPtr<C> {
+    Dense(*mut Option<C>),
+    Sparse(*mut C),
+}
+
+impl<'a, C: 'a> JoinMut<'a, C> {
+    /// Returns the entity indices of all components within the storage.
+    pub fn members(&self) -> &'a [u32] {
+        self.members
+    }
+    /// Mutably borrows the component of the specified entity index.
+    ///
+    /// # Safety
+    ///
+    /// The caller must not request the same index more than once during the lifetime of the
+    /// `JoinMut`, otherwise the returned references alias.
+    pub unsafe fn get(&self, index: usize) -> Option<&'a mut C> {
+        let position = self.positions.get(index).and_then(|p| *p)? as usize;
+        // All pointers stem from the mutable borrow of the storage. The dense data vector is at
+        // least as long as the sparse index, and the sparse data vector as well as the change
+        // ticks are as long as the member list, thus all offsets are in bounds.
+        *self.changed.add(position) = self.tick;
+        match self.data {
+            DataPtr::Dense(d) => (*d.add(index)).as_mut(),
+            DataPtr::Sparse(d) => Some(&mut *d.add(position)),
+        }
+    }
+}
+
+/// Provides type-erased access to a `Storage`, such that the `Assembly` may hold storages of
+/// arbitrary component types.
+pub trait AnyStorage: Any + fmt::Debug {
+    /// Casts the storage to `Any` for downcasting.
+    fn as_any(&self) -> &dyn Any;
+    /// Casts the storage to `Any` for mutable downcasting.
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+    /// Returns the name of the component type held by the storage.
+    fn component_name(&self) -> &'static str;
+    /// Returns the entity indices of all components within the storage.
+    fn members(&self) -> &[u32];
+    /// Returns `true` if the storage holds a component for the specified entity index.
+    fn contains_index(&self, index: usize) -> bool;
+    /// Removes the component of the specified entity index and returns it as boxed `Any`.
+    fn remove_any(&mut self, index: usize) -> Option<Box<dyn Any>>;
+}
+
+impl<C: ComponentTrait> AnyStorage for Storage<C> {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+    fn component_name(&self) -> &'static str {
+        C::name()
+    }
+    fn members(&self) -> &[u32] {
+        &self.members
+    }
+    fn contains_index(&self, index: usize) -> bool {
+        self.contains(index)
+    }
+    fn remove_any(&mut self, index: usize) -> Option<Box<dyn Any>> {
+        self.remove(index).map(|c| Box::new(c) as Box<dyn Any>)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[derive(Debug, PartialEq, Eq)]
+    struct Component(u32);
+
+    impl ComponentTrait for Component {}
+
+    fn kinds() -> [StorageKind; 2] {
+        [StorageKind::Dense, StorageKind::Sparse]
+    }
+
+    #[test]
+    fn test_insert_and_get() {
+        for &kind in &kinds() {
+            let mut s = Storage::new(kind);
+
+            assert!(s.insert(5, Component(5), 0).is_none());
+            assert!(s.insert(2, Component(2), 0).is_none());
+            assert_eq!(s.insert(5, Component(6), 0), Some(Component(5)));
+            assert_eq!(s.len(), 2);
+            assert_eq!(s.get(5), Some(&Component(6)));
+            assert_eq!(s.get(2), Some(&Component(2)));
+            assert!(s.get(3).is_none());
+            assert!(s.get(100).is_none());
+        }
+    }
+
+    #[test]
+    fn test_remove() {
+        for &kind in &kinds() {
+            let mut s = Storage::new(kind);
+
+            s.insert(0, Component(0), 0);
+            s.insert(1, Component(1), 0);
+            s.insert(2, Component(2), 0);
+
+            assert_eq!(s.remove(0), Some(Component(0)));
+            assert!(s.remove(0).is_none());
+            assert!(!s.contains(0));
+            assert_eq!(s.get(2), Some(&Component(2)));
+            assert_eq!(s.get(1), Some(&Component(1)));
+            assert_eq!(s.len(), 2);
+        }
+    }
+
+    #[test]
+    fn test_iter() {
+        for &kind in &kinds() {
+            let mut s = Storage::new(kind);
+
+            s.insert(3, Component(3), 0);
+            s.insert(7, Component(7), 0);
+            s.insert(1, Component(1), 0);
+            s.remove(3);
+
+            for (i, c) in s.iter_mut(0) {
+                assert_eq!(i as u32, c.0);
+                c.0 += 1;
+            }
+
+            let mut items = s.iter().map(|(i, c)| (i, c.0)).collect::<Vec<_>>();
+            items.sort();
+            assert_eq!(items, vec![(1, 2), (7, 8)]);
+        }
+    }
+
+    #[test]
+    fn test_change_ticks() {
+        for &kind in &kinds() {
+            let mut s = Storage::new(kind);
+
+            s.insert(0, Component(0), 1);
+            s.insert(1, Component(1), 2);
+            s.insert(2, Component(2), 3);
+            s.insert(0, Component(3), 4);
+            s.get_mut(1, 5);
+            s.remove(2);
+
+            assert_eq!(s.added_since(1).collect::<Vec<_>>(), vec![1]);
+            let mut changed = s.changed_since(3).collect::<Vec<_>>();
+            changed.sort();
+            assert_eq!(changed, vec![0, 1]);
+
+            for _ in s.iter_mut(6) {}
+            assert_eq!(s.changed_since(5).count(), 2);
+        }
+    }
+}
@@ -1,26 +1,84 @@
+use std::fmt;
 use std::time::Duration;
 
-use event::EventTrait;
+use event::{Event, EventFilter};
 use loop_stage::LoopStageFlag;
 use assembly::Assembly;
+use access::Access;
 
 /// Describes the return signature of functions that may dispatch new events. The first element
 /// contains events that shall be dispatched immediately, and events in the second element
 /// shall be dispatched in the next loop iteration.
-pub type DispatchEvents<E> = (Option<Vec<E>>, Option<Vec<E>>);
+pub type DispatchEvents = (Option<Vec<Event>>, Option<Vec<Event>>);
+
+/// Identifies a system within a `World`. Handles remain valid until the system is removed, and
+/// are never reused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SystemHandle(pub(crate) u64);
+
+impl fmt::Display for SystemHandle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "#{}", self.0)
+    }
+}
+
+/// Provides typed access to a concrete system, if the current system is or contains one. This
+/// is implemented by `impl_system_group!` for each variant of the group.
+pub trait DowncastSystem<T> {
+    /// Borrows the concrete system.
+    fn downcast_ref(&self) -> Option<&T>;
+    /// Mutably borrows the concrete system.
+    fn downcast_mut(&mut self) -> Option<&mut T>;
+}
+
+impl<T> DowncastSystem<T> for T {
+    fn downcast_ref(&self) -> Option<&T> {
+        Some(self)
+    }
+    fn downcast_mut(&mut self) -> Option<&mut T> {
+        Some(self)
+    }
+}
 
 /// Every system encodes behaviour and every system must supply at least one of the methods
 /// defined in the trait `SystemTrait`.
-pub trait SystemTrait<E: EventTrait, A> {
+pub trait SystemTrait<A> {
     /// Returns `true` if the supplied assembly satisfies the requirements of the current system.
     /// Can be used to require components or specific sets of them.
     fn verify_requirements(&self, _entities: &Assembly) -> bool;
     /// Returns a bitmask that corresponds to a combination of `LoopStage`s. Based on that value,
     /// `World` will thus regularly call the other methods.
     fn get_loop_stage_filter(&self) -> LoopStageFlag;
-    /// Returns a bitmask that corresponds to a combination of events. Based on that value, `World`
-    /// will call `handle_event` only with the selected events.
-    fn get_event_filter(&self) -> E::EventFlag {
+    /// Returns the label of the system, which other systems may refer to in their ordering
+    /// constraints. Multiple systems may share the same label.
+    fn get_label(&self) -> Option<&'static str> {
+        None
+    }
+    /// Returns the labels of the systems that must run after the current system within each
+    /// `LoopStage`.
+    fn get_before(&self) -> &'static [&'static str] {
+        &[]
+    }
+    /// Returns the labels of the systems that must run before the current system within each
+    /// `LoopStage`.
+    fn get_after(&self) -> &'static [&'static str] {
+        &[]
+    }
+    /// Returns the component types and auxiliary parts that the system accesses during the
+    /// update calls. Systems without conflicting access may run concurrently. By default,
+    /// systems claim exclusive access.
+    fn get_access(&self) -> Access {
+        Access::exclusive()
+    }
+    /// Returns the system as a `Send` trait object, if it may be called on another thread. Other
+    /// systems are called on the current thread, though still concurrently with the remaining
+    /// systems of their batch. By default, systems are called on the current thread.
+    fn as_send(&mut self) -> Option<&mut (dyn SystemTrait<A> + Send)> {
+        None
+    }
+    /// Returns the event types that the system receives. Based on that value, `World` will call
+    /// `handle_event` only with the selected events.
+    fn get_event_filter(&self) -> EventFilter {
         unimplemented!("Did you forget to implement the get_event_filter method for your system?");
     }
     /// Processes events received by the `World`, and in turn, by the engine. May optionally return
@@ -30,8 +88,8 @@ pub trait SystemTrait<E: EventTrait, A> {
         &mut self,
         _entities: &mut Assembly,
         _aux: &mut A,
-        _event: &E,
-    ) -> DispatchEvents<E> {
+        _event: &Event,
+    ) -> DispatchEvents {
         unimplemented!("Did you forget to implement the handle_event method for your system?");
     }
     /// Updates the game simulation. May optionally return two vectors of events; the first of
@@ -43,7 +101,7 @@ pub trait SystemTrait<E: EventTrait, A> {
         _aux: &mut A,
         _time: &Duration,
         _delta_time: &Duration,
-    ) -> DispatchEvents<E> {
+    ) -> DispatchEvents {
         unimplemented!("Did you forget to implement the update method for your system?");
     }
     fn dynamic_update(
@@ -52,16 +110,18 @@ pub trait SystemTrait<E: EventTrait, A> {
         _aux: &mut A,
         _time: &Duration,
         _delta_time: &Duration,
-    ) -> DispatchEvents<E> {
+    ) -> DispatchEvents {
         unimplemented!("Did you forget to implement the dynamic_update method for your system?");
     }
-    /// Renders the `World` state.
+    /// Renders the `World` state. The interpolation factor `alpha` describes how far the frame
+    /// lies between the previous and the current fixed update, ranging from zero to one.
     fn render(
         &mut self,
         _entities: &Assembly,
         _aux: &mut A,
         _time: &Duration,
         _delta_time: &Duration,
+        _alpha: f32,
     ) {
         unimplemented!("Did you forget to implement the render method for your system?");
     }
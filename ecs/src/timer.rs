@@ -0,0 +1,160 @@
+use std::fmt;
+use std::time::Duration;
+
+use event::Event;
+
+/// Identifies a scheduled event, such that it may be cancelled before it is dispatched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TimerHandle(u64);
+
+impl fmt::Display for TimerHandle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "timer #{}", self.0)
+    }
+}
+
+/// Describes when a scheduled event becomes due.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Due {
+    Time(Duration),
+    Tick(u64),
+}
+
+/// Holds events that are to be dispatched at a future game time or after a number of update
+/// ticks. The game time is the one passed to `World::update`, such that timers pause along with
+/// the simulation.
+#[derive(Debug, Default)]
+pub struct Timers {
+    /// Holds the game time of the most recent update.
+    time: Duration,
+    /// Holds the number of updates so far.
+    tick: u64,
+    /// Holds the value of the next timer handle.
+    next_handle: u64,
+    /// Holds the scheduled events in the order of scheduling.
+    pending: Vec<(TimerHandle, Due, Event)>,
+}
+
+impl Timers {
+    /// Creates an empty set of timers.
+    pub fn new() -> Self {
+        Default::default()
+    }
+    /// Returns the game time of the most recent update.
+    pub fn time(&self) -> Duration {
+        self.time
+    }
+    /// Returns the number of updates so far.
+    pub fn ticks(&self) -> u64 {
+        self.tick
+    }
+    /// Returns the number of scheduled events.
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+    /// Returns `true` if no events are scheduled.
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+    /// Schedules the event for the first update at or after the specified game time.
+    pub fn schedule_at<T: Into<Event>>(&mut self, time: Duration, event: T) -> TimerHandle {
+        self.push(Due::Time(time), event.into())
+    }
+    /// Schedules the event for the first update once the specified game time has passed.
+    pub fn schedule_after<T: Into<Event>>(&mut self, delay: Duration, event: T) -> TimerHandle {
+        let time = self.time + delay;
+        self.push(Due::Time(time), event.into())
+    }
+    /// Schedules the event for the update that follows the specified number of further updates.
+    /// Zero ticks refer to the next update, one tick to the update after that, and so on.
+    pub fn schedule_after_ticks<T: Into<Event>>(&mut self, ticks: u64, event: T) -> TimerHandle {
+        let tick = self.tick + ticks + 1;
+        self.push(Due::Tick(tick), event.into())
+    }
+    /// Cancels the specified timer and returns its event, if it was still pending.
+    pub fn cancel(&mut self, handle: TimerHandle) -> Option<Event> {
+        self.pending
+            .iter()
+            .position(|&(h, _, _)| h == handle)
+            .map(|i| self.pending.remove(i).2)
+    }
+    /// Returns `true` if the specified timer is still pending.
+    pub fn is_pending(&self, handle: TimerHandle) -> bool {
+        self.pending.iter().any(|&(h, _, _)| h == handle)
+    }
+    /// Advances to the specified game time and one tick further, and returns the events that
+    /// have become due, in the order of scheduling.
+    pub(crate) fn advance(&mut self, time: Duration) -> Vec<Event> {
+        self.time = time;
+        self.tick += 1;
+
+        let (time, tick) = (self.time, self.tick);
+        let (due, pending) = self.pending.drain(..).partition(|&(_, d, _)| match d {
+            Due::Time(t) => t <= time,
+            Due::Tick(t) => t <= tick,
+        });
+        self.pending = pending;
+        due.into_iter().map(|(_, _, e)| e).collect()
+    }
+    /// Creates empty timers at the same game time and tick, for use by a restricted `Assembly`.
+    pub(crate) fn empty_copy(&self) -> Timers {
+        Timers {
+            time: self.time,
+            tick: self.tick,
+            ..Default::default()
+        }
+    }
+    /// Adds a new pending event.
+    fn push(&mut self, due: Due, event: Event) -> TimerHandle {
+        let handle = TimerHandle(self.next_handle);
+        self.next_handle += 1;
+        self.pending.push((handle, due, event));
+        handle
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use event::EventTrait;
+
+    #[derive(Debug, PartialEq)]
+    struct Alarm(u32);
+    impl EventTrait for Alarm {}
+
+    fn alarms(events: Vec<Event>) -> Vec<u32> {
+        events
+            .iter()
+            .filter_map(|e| e.downcast_ref::<Alarm>().map(|a| a.0))
+            .collect()
+    }
+
+    #[test]
+    fn test_schedule() {
+        let mut t = Timers::new();
+        t.schedule_after(Duration::from_secs(2), Alarm(0));
+        t.schedule_at(Duration::from_secs(1), Alarm(1));
+        t.schedule_after_ticks(2, Alarm(2));
+        let h = t.schedule_after_ticks(0, Alarm(3));
+        t.schedule_after_ticks(1, Alarm(4));
+        assert_eq!(t.len(), 5);
+        assert!(t.is_pending(h));
+
+        assert_eq!(alarms(t.advance(Duration::from_secs(0))), vec![3]);
+        assert!(!t.is_pending(h));
+        assert_eq!(alarms(t.advance(Duration::from_secs(1))), vec![1, 4]);
+        assert_eq!(alarms(t.advance(Duration::from_millis(1500))), vec![2]);
+        assert_eq!(alarms(t.advance(Duration::from_secs(2))), vec![0]);
+        assert!(t.is_empty());
+        assert_eq!(t.ticks(), 4);
+    }
+
+    #[test]
+    fn test_cancel() {
+        let mut t = Timers::new();
+        let h = t.schedule_after(Duration::from_secs(1), Alarm(0));
+        assert!(t.cancel(h).is_some());
+        assert!(t.cancel(h).is_none());
+        assert_eq!(alarms(t.advance(Duration::from_secs(1))), Vec::<u32>::new());
+    }
+}
@@ -1,16 +1,54 @@
-use std::collections::VecDeque;
+use std::any::TypeId;
+use std::collections::{HashMap, VecDeque};
+use std::mem;
 use std::ops::{Deref, DerefMut};
+use std::panic::{self, AssertUnwindSafe};
 use std::time::Duration;
 
-use event::{EcsEvent, EventTrait};
+use rayon;
+
+use event::{EcsEvent, Event, EventStatistics};
 use loop_stage::LoopStage;
-use assembly::Assembly;
-use system::SystemTrait;
+use assembly::{Assembly, Lifecycle};
+use entity::Entity;
+use component_group::ComponentTrait;
+use system::{DispatchEvents, DowncastSystem, SystemHandle, SystemTrait};
+use schedule::{batches, resolve_order, ExecutionMode};
+use timer::{TimerHandle, Timers};
+use aux_part::SplitAux;
 use error::EcsError;
 
+/// Describes a callback that is invoked after a component was added to or removed from an
+/// `Entity`. The callback receives the `Assembly`, the auxiliary object of the `World` and the
+/// affected `Entity`.
+pub type Hook<A> = Box<dyn Fn(&Assembly, &mut A, &Entity)>;
+
+/// Describes a function that calls all systems of an update stage and returns their events in
+/// the resolved order.
+type Executor<A, S> = fn(&mut World<A, S>, &LoopStage, &Duration, &Duration)
+    -> Vec<DispatchEvents>;
+
+/// Specifies the default maximum nesting depth of priority events.
+const DEFAULT_RECURSION_LIMIT: usize = 64;
+
+/// Holds the lifecycle hooks of a single component type.
+struct Hooks<A> {
+    on_add: Vec<Hook<A>>,
+    on_remove: Vec<Hook<A>>,
+}
+
+impl<A> Default for Hooks<A> {
+    fn default() -> Self {
+        Hooks {
+            on_add: Vec::new(),
+            on_remove: Vec::new(),
+        }
+    }
+}
+
 /// Encapsulates a set of systems, entities and components that describe an abstract universe of
 /// data and behaviour.
-pub struct World<E: EventTrait, A: Default, S: SystemTrait<E, A>> {
+pub struct World<A: Default, S: SystemTrait<A>> {
     /// This field stores an arbitrary auxiliary object, that is passed to systems during the
     /// update and event-handling calls. Consider using this for caching, file-system persistence,
     /// global state, singleton objects, etc.
@@ -19,15 +57,36 @@ pub struct World<E: EventTrait, A: Default, S: SystemTrait<E, A>> {
     pub rendering_suspended: bool,
     /// Stores any currently queued events. These will be passed on to the relevant systems in
     /// event-handling calls.
-    event_queue: VecDeque<E>,
+    event_queue: VecDeque<Event>,
     /// Stores all systems as boxed trait objects. Systems primarily encode behaviour.
     systems: Vec<S>,
+    /// Stores the handle of each system.
+    handles: Vec<SystemHandle>,
+    /// Marks the systems that are currently enabled.
+    enabled: Vec<bool>,
+    /// Holds the value of the next system handle.
+    next_handle: u64,
+    /// Stores the order in which the enabled systems are called, as indices into `systems`.
+    order: Vec<usize>,
     /// The `Assembly` stores entities and their components. A reference is passed to systems
     /// during the update, event-handling and render calls.
     assembly: Assembly,
+    /// Stores the lifecycle hooks per component type.
+    hooks: HashMap<TypeId, Hooks<A>>,
+    /// Stores the tick at which the previous call to `handle_events` ended. Removal records
+    /// older than that are discarded.
+    frame_tick: u64,
+    /// Selects how the systems of the update stages are executed.
+    mode: ExecutionMode,
+    /// Holds the parallel executor, if selected.
+    executor: Option<Executor<A, S>>,
+    /// Specifies the maximum nesting depth of priority events.
+    recursion_limit: usize,
+    /// Counts the events dispatched during the current frame.
+    statistics: EventStatistics,
 }
 
-impl<E: EventTrait, A: Default, S: SystemTrait<E, A>> Default for World<E, A, S> {
+impl<A: Default, S: SystemTrait<A>> Default for World<A, S> {
     /// Creates a default instance of `World`.
     fn default() -> Self {
         World {
@@ -35,84 +94,271 @@ impl<E: EventTrait, A: Default, S: SystemTrait<E, A>> Default for World<E, A, S>
             rendering_suspended: Default::default(),
             event_queue: Default::default(),
             systems: Default::default(),
+            handles: Default::default(),
+            enabled: Default::default(),
+            next_handle: Default::default(),
+            order: Default::default(),
             assembly: Default::default(),
+            hooks: Default::default(),
+            frame_tick: Default::default(),
+            mode: Default::default(),
+            executor: None,
+            recursion_limit: DEFAULT_RECURSION_LIMIT,
+            statistics: EventStatistics::new(),
         }
     }
 }
 
-impl<E: EventTrait, A: Default, S: SystemTrait<E, A>> World<E, A, S> {
+impl<A: Default, S: SystemTrait<A>> World<A, S> {
     /// Creates a new, empty instance of `World`.
     pub fn new() -> Self {
         Default::default()
     }
-    /// Adds a new system to the `World`.
-    pub fn add_system(&mut self, system: S) -> Result<(), EcsError> {
-        if system.verify_requirements(&self.assembly) {
-            self.systems.push(system);
-            Ok(())
-        } else {
-            Err(EcsError::UnsatisfiedRequirements)
+    /// Adds a new system to the `World` and returns its handle. Within each `LoopStage`,
+    /// systems are called in the order of insertion, unless their labels and `before`/`after`
+    /// constraints require otherwise. If the constraints cannot be satisfied, the system is not
+    /// added.
+    pub fn add_system(&mut self, system: S) -> Result<SystemHandle, EcsError> {
+        if !system.verify_requirements(&self.assembly) {
+            return Err(EcsError::UnsatisfiedRequirements);
+        }
+
+        let handle = SystemHandle(self.next_handle);
+        self.systems.push(system);
+        self.handles.push(handle);
+        self.enabled.push(true);
+        match self.reschedule() {
+            Ok(()) => {
+                self.next_handle += 1;
+                Ok(handle)
+            }
+            Err(e) => {
+                self.systems.pop();
+                self.handles.pop();
+                self.enabled.pop();
+                Err(e)
+            }
         }
     }
+    /// Removes the specified system from the `World` and returns it.
+    pub fn remove_system(&mut self, handle: SystemHandle) -> Result<S, EcsError> {
+        let i = self.position(handle)?;
+        let system = self.systems.remove(i);
+        self.handles.remove(i);
+        self.enabled.remove(i);
+        self.reschedule()
+            .expect("Removing a system cannot introduce an ordering cycle");
+        Ok(system)
+    }
+    /// Enables or disables the specified system. Disabled systems are not called, but retain
+    /// their state and position in the order.
+    pub fn set_enabled(&mut self, handle: SystemHandle, enabled: bool) -> Result<(), EcsError> {
+        let i = self.position(handle)?;
+        self.enabled[i] = enabled;
+        self.reschedule()
+    }
+    /// Returns `true` if the specified system is enabled.
+    pub fn is_enabled(&self, handle: SystemHandle) -> Result<bool, EcsError> {
+        self.position(handle).map(|i| self.enabled[i])
+    }
+    /// Returns the handle of the first system with the specified label.
+    pub fn find_system(&self, label: &str) -> Option<SystemHandle> {
+        self.systems
+            .iter()
+            .position(|s| s.get_label() == Some(label))
+            .map(|i| self.handles[i])
+    }
+    /// Borrows the specified system.
+    pub fn system(&self, handle: SystemHandle) -> Result<&S, EcsError> {
+        self.position(handle).map(move |i| &self.systems[i])
+    }
+    /// Mutably borrows the specified system.
+    pub fn system_mut(&mut self, handle: SystemHandle) -> Result<&mut S, EcsError> {
+        let i = self.position(handle)?;
+        Ok(&mut self.systems[i])
+    }
+    /// Borrows the first system of the specified concrete type.
+    pub fn get_system<T>(&self) -> Option<&T>
+    where
+        S: DowncastSystem<T>,
+    {
+        self.systems.iter().filter_map(|s| s.downcast_ref()).next()
+    }
+    /// Mutably borrows the first system of the specified concrete type.
+    pub fn get_system_mut<T>(&mut self) -> Option<&mut T>
+    where
+        S: DowncastSystem<T>,
+    {
+        self.systems.iter_mut().filter_map(|s| s.downcast_mut()).next()
+    }
+    /// Returns the labels of the enabled systems that participate in the specified `LoopStage`,
+    /// in the order in which they are called. Unlabeled systems are represented by `None`.
+    pub fn system_order(&self, stage: LoopStage) -> Vec<Option<&'static str>> {
+        self.order
+            .iter()
+            .map(|&i| &self.systems[i])
+            .filter(|s| stage.match_filter(s.get_loop_stage_filter()))
+            .map(|s| s.get_label())
+            .collect()
+    }
+    /// Registers a hook that is called whenever a component of type `C` is added to an
+    /// `Entity`. Hooks are called at the next sync point, that is after the current system
+    /// returns or at the beginning of the next loop stage. Replacing an existing component does
+    /// not count as an addition.
+    pub fn on_add<C, F>(&mut self, hook: F)
+    where
+        C: ComponentTrait,
+        F: Fn(&Assembly, &mut A, &Entity) + 'static,
+    {
+        self.assembly.observe(TypeId::of::<C>());
+        self.hooks
+            .entry(TypeId::of::<C>())
+            .or_default()
+            .on_add
+            .push(Box::new(hook));
+    }
+    /// Registers a hook that is called whenever a component of type `C` is removed from an
+    /// `Entity`, including when the `Entity` is destroyed. Hooks are called at the next sync
+    /// point, thus the component is no longer accessible.
+    pub fn on_remove<C, F>(&mut self, hook: F)
+    where
+        C: ComponentTrait,
+        F: Fn(&Assembly, &mut A, &Entity) + 'static,
+    {
+        self.assembly.observe(TypeId::of::<C>());
+        self.hooks
+            .entry(TypeId::of::<C>())
+            .or_default()
+            .on_remove
+            .push(Box::new(hook));
+    }
     /// Iterates over all queued events and dispatches them to the relevant systems.
     pub fn handle_events(&mut self) -> bool {
-        let events = self.event_queue.iter().cloned().collect::<Vec<_>>();
-        self.event_queue.clear();
+        Self::sync(&mut self.assembly, &mut self.aux, &self.hooks);
+
+        let events = self.event_queue.drain(..).collect::<Vec<_>>();
 
         for e in events {
             match e.as_ecs_event() {
-                Some(EcsEvent::ImmediateShutdown) => {
+                Some(&EcsEvent::ImmediateShutdown) => {
                     return false;
                 }
-                Some(EcsEvent::Shutdown) => {
-                    self.dispatch_immediate(&e);
-                    self.dispatch(EcsEvent::ImmediateShutdown.into())
+                Some(&EcsEvent::Shutdown) => {
+                    self.propagate(&e);
+                    self.dispatch(EcsEvent::ImmediateShutdown)
                 }
-                _ => self.dispatch_immediate(&e),
+                _ => self.propagate(&e),
             }
         }
 
+        self.apply_deferred();
+        let statistics = mem::take(&mut self.statistics);
+        self.assembly.set_event_statistics(statistics);
+
+        // Removals are retained for one full loop iteration, such that every system has a chance
+        // to observe them.
+        self.assembly.clear_removed(self.frame_tick);
+        self.frame_tick = self.assembly.tick();
+
         true
     }
     /// Updates the current simulation of the `World` by iterating through all systems that
     /// subscribe to the update call. This update call should be performed at fixed time steps.
+    /// Beforehand, scheduled events that have become due at the specified game time are queued.
     pub fn update(&mut self, time: &Duration, delta_time: &Duration) {
-        let mut priority_events = Vec::new();
-        let mut events = Vec::new();
-
-        for system in &mut self.systems {
-            if LoopStage::Update.match_filter(system.get_loop_stage_filter()) {
-                let (pe, e) = system.update(&mut self.assembly, &mut self.aux, time, delta_time);
-
-                if let Some(mut pe) = pe {
-                    priority_events.append(&mut pe);
-                }
-                if let Some(mut e) = e {
-                    events.append(&mut e);
-                }
-            }
-        }
-
-        for pe in priority_events {
-            self.dispatch_immediate(&pe);
-        }
-        for e in events {
+        for e in self.own_timers().advance(*time) {
             self.dispatch(e);
         }
+        self.run_stage(LoopStage::Update, time, delta_time);
     }
     /// Updates the current simulation of the `World` by iterating through all systems that
     /// subscribe to the update call. This update call should be performed at variable time steps.
     pub fn dynamic_update(&mut self, time: &Duration, delta_time: &Duration) {
+        self.run_stage(LoopStage::DynamicUpdate, time, delta_time);
+    }
+    /// Renders the current state of the `World` by iterating through all systems that subscribe to
+    /// the render call. The interpolation factor `alpha` is the fraction of a fixed update step
+    /// that has elapsed since the most recent call to `update`.
+    pub fn render(&mut self, time: &Duration, delta_time: &Duration, alpha: f32) {
+        if !self.rendering_suspended {
+            Self::sync(&mut self.assembly, &mut self.aux, &self.hooks);
+            for &i in &self.order {
+                let system = &mut self.systems[i];
+                if LoopStage::Render.match_filter(system.get_loop_stage_filter()) {
+                    system.render(&self.assembly, &mut self.aux, time, delta_time, alpha);
+                    self.assembly.advance_tick();
+                }
+            }
+        }
+    }
+    /// Sends an event of any type to the queue for later processing.
+    pub fn dispatch<T: Into<Event>>(&mut self, event: T) {
+        let event = event.into();
+        self.statistics.record(&event);
+        self.event_queue.push_back(event);
+    }
+    /// Schedules an event of any type for the first update at or after the specified game time.
+    pub fn dispatch_at<T: Into<Event>>(&mut self, time: Duration, event: T) -> TimerHandle {
+        self.own_timers().schedule_at(time, event)
+    }
+    /// Schedules an event of any type for the first update once the specified amount of game
+    /// time has passed.
+    pub fn dispatch_after<T: Into<Event>>(&mut self, delay: Duration, event: T) -> TimerHandle {
+        self.own_timers().schedule_after(delay, event)
+    }
+    /// Schedules an event of any type for the update that follows the specified number of
+    /// further updates.
+    pub fn dispatch_after_ticks<T: Into<Event>>(&mut self, ticks: u64, event: T) -> TimerHandle {
+        self.own_timers().schedule_after_ticks(ticks, event)
+    }
+    /// Cancels a scheduled event. Returns `true` if the event was still pending.
+    pub fn cancel(&mut self, handle: TimerHandle) -> bool {
+        self.own_timers().cancel(handle).is_some()
+    }
+    /// Sets the maximum nesting depth of priority events, that is how often priority events may
+    /// cause further priority events before the chain is aborted.
+    pub fn set_event_recursion_limit(&mut self, limit: usize) {
+        self.recursion_limit = limit;
+    }
+    /// Returns the maximum nesting depth of priority events.
+    pub fn event_recursion_limit(&self) -> usize {
+        self.recursion_limit
+    }
+    /// Returns the number of events dispatched during the previous frame per event type, as well
+    /// as the errors that occurred while dispatching them.
+    pub fn event_statistics(&self) -> &EventStatistics {
+        self.assembly.event_statistics()
+    }
+    /// Processes the current event and all priority events that result from it. If the chain of
+    /// priority events exceeds the recursion limit, it is aborted and the error is logged.
+    fn propagate(&mut self, event: &Event) {
+        if let Err(e) = self.dispatch_immediate(event, &mut Vec::new()) {
+            error!("{}", e);
+            self.statistics.record_error(&e);
+        }
+    }
+    /// Processes the current event by iterating over all applicable systems (e.g. they subscribe
+    /// to the event handling call and also to the current event). The chain holds the emitting
+    /// system and the type name of each enclosing priority event.
+    fn dispatch_immediate(
+        &mut self,
+        event: &Event,
+        chain: &mut Vec<(usize, &'static str)>,
+    ) -> Result<(), EcsError> {
         let mut priority_events = Vec::new();
         let mut events = Vec::new();
 
-        for system in &mut self.systems {
-            if LoopStage::DynamicUpdate.match_filter(system.get_loop_stage_filter()) {
-                let (pe, e) =
-                    system.dynamic_update(&mut self.assembly, &mut self.aux, time, delta_time);
+        for &i in &self.order {
+            let system = &mut self.systems[i];
+            if LoopStage::HandleEvent.match_filter(system.get_loop_stage_filter())
+                && system.get_event_filter().matches(event)
+            {
+                let (pe, e) = system.handle_event(&mut self.assembly, &mut self.aux, event);
+                self.assembly.advance_tick();
+                Self::sync(&mut self.assembly, &mut self.aux, &self.hooks);
 
-                if let Some(mut pe) = pe {
-                    priority_events.append(&mut pe);
+                if let Some(pe) = pe {
+                    priority_events.extend(pe.into_iter().map(|e| (i, e)));
                 }
                 if let Some(mut e) = e {
                     events.append(&mut e);
@@ -120,59 +366,287 @@ impl<E: EventTrait, A: Default, S: SystemTrait<E, A>> World<E, A, S> {
             }
         }
 
-        for pe in priority_events {
-            self.dispatch_immediate(&pe);
+        for (i, pe) in priority_events {
+            self.statistics.record(&pe);
+            chain.push((i, pe.name()));
+            let result = if chain.len() > self.recursion_limit {
+                Err(self.recursion_error(chain))
+            } else {
+                self.dispatch_immediate(&pe, chain)
+            };
+            chain.pop();
+            result?;
         }
         for e in events {
             self.dispatch(e);
         }
+        Ok(())
     }
-    /// Renders the current state of the `World` by iterating through all systems that subscribe to
-    /// the render call.
-    pub fn render(&mut self, time: &Duration, delta_time: &Duration) {
-        if !self.rendering_suspended {
-            for system in &mut self.systems {
-                if LoopStage::Render.match_filter(system.get_loop_stage_filter()) {
-                    system.render(&self.assembly, &mut self.aux, time, delta_time);
+    /// Describes why a chain of priority events exceeded the recursion limit. If the last system
+    /// emitted the same event type before, the systems involved in the cycle are named.
+    fn recursion_error(&self, chain: &[(usize, &'static str)]) -> EcsError {
+        let (last, rest) = chain.split_last().expect("The chain must not be empty");
+        match rest.iter().rposition(|link| link == last) {
+            Some(start) => {
+                let mut names: Vec<String> = Vec::new();
+                for &(i, _) in &chain[start + 1..] {
+                    let name = match self.systems[i].get_label() {
+                        Some(l) => l.to_string(),
+                        None => self.handles[i].to_string(),
+                    };
+                    if !names.contains(&name) {
+                        names.push(name);
+                    }
                 }
+                EcsError::EventCycle(names)
             }
+            None => EcsError::EventRecursionLimit(self.recursion_limit),
         }
     }
-    /// Sends an event to the queue for later processing.
-    pub fn dispatch(&mut self, event: E) {
-        self.event_queue.push_back(event);
+    /// Returns the index of the specified system.
+    fn position(&self, handle: SystemHandle) -> Result<usize, EcsError> {
+        self.handles
+            .iter()
+            .position(|&h| h == handle)
+            .ok_or(EcsError::SystemNotFound(handle))
     }
-    /// Processes the current event by iterating over all applicable systems (e.g. they subscribe
-    /// to the event handling call and also to the current event).
-    fn dispatch_immediate(&mut self, event: &E) {
+    /// Resolves the order of all systems and retains the enabled ones.
+    fn reschedule(&mut self) -> Result<(), EcsError> {
+        let order = resolve_order(&self.systems)?;
+        let enabled = &self.enabled;
+        self.order = order.into_iter().filter(|&i| enabled[i]).collect();
+        Ok(())
+    }
+    /// Returns the current `ExecutionMode`.
+    pub fn execution_mode(&self) -> ExecutionMode {
+        self.mode
+    }
+    /// Calls all systems of the specified update stage and dispatches the resulting events.
+    fn run_stage(&mut self, stage: LoopStage, time: &Duration, delta_time: &Duration) {
         let mut priority_events = Vec::new();
         let mut events = Vec::new();
 
-        for system in &mut self.systems {
-            if LoopStage::HandleEvent.match_filter(system.get_loop_stage_filter())
-                && event.match_filter(system.get_event_filter())
-            {
-                let (pe, e) = system.handle_event(&mut self.assembly, &mut self.aux, event);
-
-                if let Some(mut pe) = pe {
-                    priority_events.append(&mut pe);
-                }
-                if let Some(mut e) = e {
-                    events.append(&mut e);
-                }
+        Self::sync(&mut self.assembly, &mut self.aux, &self.hooks);
+        let results = match self.executor {
+            Some(executor) => executor(self, &stage, time, delta_time),
+            None => self.run_sequential(&stage, time, delta_time),
+        };
+        for (pe, e) in results {
+            if let Some(mut pe) = pe {
+                priority_events.append(&mut pe);
+            }
+            if let Some(mut e) = e {
+                events.append(&mut e);
             }
         }
 
         for pe in priority_events {
-            self.dispatch_immediate(&pe);
+            self.statistics.record(&pe);
+            self.propagate(&pe);
         }
         for e in events {
             self.dispatch(e);
         }
+        self.apply_deferred();
+    }
+    /// Calls all systems of the specified update stage one after another.
+    fn run_sequential(
+        &mut self,
+        stage: &LoopStage,
+        time: &Duration,
+        delta_time: &Duration,
+    ) -> Vec<DispatchEvents> {
+        let mut results = Vec::new();
+        for &i in &self.order {
+            let system = &mut self.systems[i];
+            if stage.match_filter(system.get_loop_stage_filter()) {
+                results.push(Self::call(
+                    system,
+                    stage,
+                    &mut self.assembly,
+                    &mut self.aux,
+                    time,
+                    delta_time,
+                ));
+                self.assembly.advance_tick();
+                Self::sync(&mut self.assembly, &mut self.aux, &self.hooks);
+            }
+        }
+        results
+    }
+    /// Calls the method of the system that corresponds to the specified update stage.
+    fn call<T: SystemTrait<A> + ?Sized>(
+        system: &mut T,
+        stage: &LoopStage,
+        entities: &mut Assembly,
+        aux: &mut A,
+        time: &Duration,
+        delta_time: &Duration,
+    ) -> DispatchEvents {
+        match *stage {
+            LoopStage::Update => system.update(entities, aux, time, delta_time),
+            LoopStage::DynamicUpdate => system.dynamic_update(entities, aux, time, delta_time),
+            _ => unreachable!("Only the update stages are executed by run_stage"),
+        }
+    }
+    /// Applies the command buffers deferred by systems during the current loop stage, and invokes
+    /// the resulting lifecycle hooks. Commands that fail do not abort the loop stage, instead the
+    /// error is logged and reported with the event statistics.
+    fn apply_deferred(&mut self) {
+        if let Err(e) = self.assembly.apply_deferred() {
+            error!("Unable to apply the deferred commands: {}", e);
+            self.statistics.record_error(&e);
+        }
+        Self::sync(&mut self.assembly, &mut self.aux, &self.hooks);
+    }
+    /// Returns the timers of the `Assembly`, which is never restricted outside of `run_parallel`.
+    fn own_timers(&mut self) -> &mut Timers {
+        self.assembly
+            .timers_mut()
+            .expect("The Assembly of the World must not be restricted")
+    }
+    /// Invokes the lifecycle hooks for all structural changes recorded since the last sync point.
+    fn sync(assembly: &mut Assembly, aux: &mut A, hooks: &HashMap<TypeId, Hooks<A>>) {
+        for l in assembly.take_lifecycle() {
+            let (type_id, entity, added) = match l {
+                Lifecycle::Added(t, e) => (t, e, true),
+                Lifecycle::Removed(t, e) => (t, e, false),
+            };
+            if let Some(h) = hooks.get(&type_id) {
+                let hooks = if added { &h.on_add } else { &h.on_remove };
+                for hook in hooks {
+                    hook(assembly, aux, &entity);
+                }
+            }
+        }
     }
 }
 
-impl<E: EventTrait, A: Default, S: SystemTrait<E, A>> Deref for World<E, A, S> {
+impl<A, S> World<A, S>
+where
+    A: Default + SplitAux,
+    S: SystemTrait<A>,
+{
+    /// Selects how the systems of the update stages are executed. In parallel mode, systems
+    /// without conflicting `Access` (see `SystemTrait::get_access`) are called concurrently, and
+    /// each of them receives only the auxiliary parts it declared (see `SplitAux`). Systems that
+    /// are not `Send` (see `SystemTrait::as_send`) are called on the current thread.
+    pub fn set_execution_mode(&mut self, mode: ExecutionMode) {
+        self.mode = mode;
+        self.executor = match mode {
+            ExecutionMode::SingleThreaded => None,
+            ExecutionMode::Parallel => Some(Self::run_parallel),
+        };
+    }
+    /// Calls the systems of the specified update stage in batches of systems without conflicts.
+    /// The results are identical to those of `run_sequential`, as long as the systems access
+    /// only what they declared. If a system panics, the storages and auxiliary parts are returned
+    /// to the `World` before the panic resumes.
+    fn run_parallel(
+        &mut self,
+        stage: &LoopStage,
+        time: &Duration,
+        delta_time: &Duration,
+    ) -> Vec<DispatchEvents> {
+        let mut results = Vec::new();
+        for batch in batches(&self.systems, &self.order, stage) {
+            if batch.len() == 1 {
+                let system = &mut self.systems[batch[0]];
+                results.push(Self::call(
+                    system,
+                    stage,
+                    &mut self.assembly,
+                    &mut self.aux,
+                    time,
+                    delta_time,
+                ));
+                self.assembly.advance_tick();
+                Self::sync(&mut self.assembly, &mut self.aux, &self.hooks);
+                continue;
+            }
+
+            // Each system receives its own restricted assembly, its own change tick and its own
+            // auxiliary parts.
+            let tick = self.assembly.tick();
+            let mut subs = Vec::with_capacity(batch.len());
+            let mut parts = Vec::with_capacity(batch.len());
+            for (k, &i) in batch.iter().enumerate() {
+                let access = self.systems[i].get_access();
+                subs.push(self.assembly.split(&access, tick + k as u64));
+                parts.push(self.aux.split(&access));
+            }
+
+            let mut systems = self.systems.iter_mut().map(Some).collect::<Vec<_>>();
+            let mut outcomes = batch.iter().map(|_| None).collect::<Vec<_>>();
+            let called = {
+                let tasks = batch
+                    .iter()
+                    .map(|&i| systems[i].take().expect("The batch contains a system twice"))
+                    .zip(subs.iter_mut())
+                    .zip(parts.iter_mut())
+                    .zip(outcomes.iter_mut());
+
+                // Systems that are `Send` are spawned on the thread pool, while the others are
+                // called on the current thread.
+                panic::catch_unwind(AssertUnwindSafe(|| {
+                    rayon::in_place_scope(|scope| {
+                        let mut local = Vec::new();
+                        for (((system, sub), aux), outcome) in tasks {
+                            if system.as_send().is_none() {
+                                local.push((system, sub, aux, outcome));
+                                continue;
+                            }
+                            let system = system.as_send().unwrap_or_else(|| unreachable!());
+                            let sub = Restricted(sub);
+                            let aux = Restricted(aux);
+                            scope.spawn(move |_| {
+                                let (sub, aux) = (sub.into_inner(), aux.into_inner());
+                                let o = Self::call(system, stage, sub, aux, time, delta_time);
+                                *outcome = Some(o);
+                            });
+                        }
+                        for (system, sub, aux, outcome) in local {
+                            *outcome = Some(Self::call(system, stage, sub, aux, time, delta_time));
+                        }
+                    })
+                }))
+            };
+
+            for sub in subs {
+                self.assembly.merge(sub);
+            }
+            for part in parts {
+                self.aux.merge(part);
+            }
+            if let Err(payload) = called {
+                panic::resume_unwind(payload);
+            }
+            for _ in &batch {
+                self.assembly.advance_tick();
+            }
+            Self::sync(&mut self.assembly, &mut self.aux, &self.hooks);
+            results.extend(outcomes.into_iter().map(|o| o.expect("A system was not called")));
+        }
+        results
+    }
+}
+
+/// Allows to hand a restricted `Assembly` or the auxiliary parts of a system to another thread.
+struct Restricted<'a, T: 'a>(&'a mut T);
+
+impl<'a, T> Restricted<'a, T> {
+    fn into_inner(self) -> &'a mut T {
+        self.0
+    }
+}
+
+// A restricted `Assembly` only provides access to the storages of component types declared by an
+// `Access`, which requires these types to be `Send` and `Sync`. Its shared storages are not
+// mutated by any other system of the same batch. Likewise, auxiliary objects created by
+// `SplitAux::split` only hold the declared parts, which must be `Send`.
+unsafe impl<'a, T> Send for Restricted<'a, T> {}
+
+impl<A: Default, S: SystemTrait<A>> Deref for World<A, S> {
     type Target = Assembly;
 
     fn deref(&self) -> &Self::Target {
@@ -180,8 +654,543 @@ impl<E: EventTrait, A: Default, S: SystemTrait<E, A>> Deref for World<E, A, S> {
     }
 }
 
-impl<E: EventTrait, A: Default, S: SystemTrait<E, A>> DerefMut for World<E, A, S> {
+impl<A: Default, S: SystemTrait<A>> DerefMut for World<A, S> {
     fn deref_mut(&mut self) -> &mut Self::Target {
         &mut self.assembly
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use loop_stage::LoopStageFlag;
+    use system::DispatchEvents;
+    use event::{EventFilter, EventTrait};
+    use command::CommandBuffer;
+    use access::Access;
+    use aux_part::AuxPart;
+
+    #[derive(Debug)]
+    struct Ping;
+    impl EventTrait for Ping {}
+
+    #[derive(Debug, PartialEq)]
+    struct Position(u32);
+    impl ComponentTrait for Position {}
+
+    /// Records the entities whose `Position` was added or changed since its last run.
+    #[derive(Default)]
+    struct Tracker {
+        last_tick: u64,
+        added: Vec<Entity>,
+        changed: Vec<Entity>,
+        removed: Vec<Entity>,
+    }
+
+    impl SystemTrait<Vec<String>> for Tracker {
+        fn verify_requirements(&self, _: &Assembly) -> bool {
+            true
+        }
+        fn get_loop_stage_filter(&self) -> LoopStageFlag {
+            LoopStageFlag::UPDATE
+        }
+        fn update(
+            &mut self,
+            entities: &mut Assembly,
+            _: &mut Vec<String>,
+            _: &Duration,
+            _: &Duration,
+        ) -> DispatchEvents {
+            self.added = entities.added::<Position>(self.last_tick).collect();
+            self.changed = entities.changed::<Position>(self.last_tick).collect();
+            self.removed = entities.removed::<Position>(self.last_tick).collect();
+            self.last_tick = entities.tick();
+            (None, None)
+        }
+    }
+
+    #[test]
+    fn test_change_detection() {
+        let mut w: World<Vec<String>, Tracker> = World::new();
+        w.add_system(Tracker::default()).unwrap();
+
+        let e = w.create_entity();
+        w.add_component(&e, Position(0)).unwrap();
+        let f = w.create_entity();
+        w.add_component(&f, Position(1)).unwrap();
+
+        let t = Duration::default();
+        w.update(&t, &t);
+        assert_eq!(w.systems[0].added.len(), 2);
+
+        w.update(&t, &t);
+        assert!(w.systems[0].added.is_empty());
+        assert!(w.systems[0].changed.is_empty());
+
+        w.borrow_component_mut::<Position>(&f).unwrap().0 += 1;
+        w.update(&t, &t);
+        assert!(w.systems[0].added.is_empty());
+        assert_eq!(w.systems[0].changed, vec![f]);
+
+        w.destroy_entity(&e);
+        w.update(&t, &t);
+        assert_eq!(w.systems[0].removed, vec![e]);
+        w.update(&t, &t);
+        assert!(w.systems[0].removed.is_empty());
+
+        w.handle_events();
+        assert_eq!(w.removed::<Position>(0).count(), 1);
+        w.handle_events();
+        assert_eq!(w.removed::<Position>(0).count(), 0);
+    }
+
+    /// Spawns one entity per update call via a `CommandBuffer`.
+    #[derive(Default)]
+    struct Spawner;
+
+    impl SystemTrait<Vec<String>> for Spawner {
+        fn verify_requirements(&self, _: &Assembly) -> bool {
+            true
+        }
+        fn get_loop_stage_filter(&self) -> LoopStageFlag {
+            LoopStageFlag::UPDATE
+        }
+        fn update(
+            &mut self,
+            entities: &mut Assembly,
+            _: &mut Vec<String>,
+            _: &Duration,
+            _: &Duration,
+        ) -> DispatchEvents {
+            let before = entities.count1::<Position>();
+            let mut buffer = CommandBuffer::new();
+            for (e, _) in entities.r1_iter::<Position>() {
+                buffer.destroy_entity(&e);
+            }
+            buffer.create_entity().with(Position(0)).build();
+            entities.defer(buffer);
+
+            // Nothing is applied until the end of the loop stage.
+            assert_eq!(entities.count1::<Position>(), before);
+            (None, None)
+        }
+    }
+
+    #[test]
+    fn test_deferred_commands() {
+        let mut w: World<Vec<String>, Spawner> = World::new();
+        w.add_system(Spawner).unwrap();
+        w.add_system(Spawner).unwrap();
+        w.on_add::<Position, _>(|_, log, _| log.push("added".into()));
+
+        let t = Duration::default();
+        w.update(&t, &t);
+        assert_eq!(w.count1::<Position>(), 2);
+        assert_eq!(w.aux.len(), 2);
+
+        w.update(&t, &t);
+        assert_eq!(w.count1::<Position>(), 2);
+        assert_eq!(w.entity_count(), 2);
+    }
+
+    #[test]
+    fn test_deferred_command_error() {
+        #[derive(Debug)]
+        struct Dependent;
+        impl ComponentTrait for Dependent {
+            fn required_components() -> Vec<(TypeId, &'static str)> {
+                vec![(TypeId::of::<Position>(), "position")]
+            }
+        }
+
+        let mut w: World<Vec<String>, Spawner> = World::new();
+        let e = w.create_entity();
+        let f = w.create_entity();
+        w.destroy_entity(&f);
+
+        let mut buffer = CommandBuffer::new();
+        buffer.add_component(&e, Dependent);
+        buffer.destroy_entity(&f);
+        buffer.add_component(&e, Position(1));
+        w.defer(buffer);
+
+        let t = Duration::default();
+        w.update(&t, &t);
+        assert!(w.has_component::<Position>(&e));
+        assert!(!w.has_component::<Dependent>(&e));
+
+        w.handle_events();
+        assert_eq!(w.event_statistics().errors().len(), 1);
+    }
+
+    /// Logs its label on each update call and runs after the systems with the specified labels.
+    struct Ordered(&'static str, &'static [&'static str]);
+
+    impl SystemTrait<Vec<String>> for Ordered {
+        fn verify_requirements(&self, _: &Assembly) -> bool {
+            true
+        }
+        fn get_loop_stage_filter(&self) -> LoopStageFlag {
+            LoopStageFlag::UPDATE
+        }
+        fn get_label(&self) -> Option<&'static str> {
+            Some(self.0)
+        }
+        fn get_after(&self) -> &'static [&'static str] {
+            self.1
+        }
+        fn update(
+            &mut self,
+            _: &mut Assembly,
+            log: &mut Vec<String>,
+            _: &Duration,
+            _: &Duration,
+        ) -> DispatchEvents {
+            log.push(self.0.into());
+            (None, None)
+        }
+    }
+
+    #[test]
+    fn test_system_order() {
+        let mut w: World<Vec<String>, Ordered> = World::new();
+        w.add_system(Ordered("render", &["physics"])).unwrap();
+        w.add_system(Ordered("physics", &["input"])).unwrap();
+        w.add_system(Ordered("input", &[])).unwrap();
+        assert!(w.add_system(Ordered("input", &["render"])).is_err());
+
+        assert_eq!(
+            w.system_order(LoopStage::Update),
+            vec![Some("input"), Some("physics"), Some("render")]
+        );
+        assert!(w.system_order(LoopStage::Render).is_empty());
+
+        let t = Duration::default();
+        w.update(&t, &t);
+        assert_eq!(w.aux, vec!["input", "physics", "render"]);
+    }
+
+    #[test]
+    fn test_system_management() {
+        let mut w: World<Vec<String>, Ordered> = World::new();
+        let render = w.add_system(Ordered("render", &["physics"])).unwrap();
+        let physics = w.add_system(Ordered("physics", &[])).unwrap();
+        let audio = w.add_system(Ordered("audio", &[])).unwrap();
+        assert_eq!(w.find_system("physics"), Some(physics));
+        assert_eq!(w.find_system("input"), None);
+        assert_eq!(w.system(audio).unwrap().0, "audio");
+        assert_eq!(w.get_system::<Ordered>().map(|s| s.0), Some("render"));
+
+        let t = Duration::default();
+        w.set_enabled(physics, false).unwrap();
+        assert!(!w.is_enabled(physics).unwrap());
+        w.update(&t, &t);
+        assert_eq!(w.aux, vec!["render", "audio"]);
+
+        w.aux.clear();
+        w.set_enabled(physics, true).unwrap();
+        w.update(&t, &t);
+        assert_eq!(w.aux, vec!["physics", "render", "audio"]);
+
+        w.aux.clear();
+        assert_eq!(w.remove_system(render).unwrap().0, "render");
+        assert!(w.remove_system(render).is_err());
+        assert!(w.set_enabled(render, false).is_err());
+        w.system_mut(audio).unwrap().0 = "music";
+        w.update(&t, &t);
+        assert_eq!(w.aux, vec!["physics", "music"]);
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Velocity(u32);
+    impl ComponentTrait for Velocity {}
+
+    /// Holds the log of the `Worker`s as a part that may be split off.
+    #[derive(Default)]
+    struct Journal {
+        log: AuxPart<Vec<String>>,
+    }
+
+    unsafe impl SplitAux for Journal {
+        fn split(&mut self, access: &Access) -> Self {
+            Journal {
+                log: self.log.split(access),
+            }
+        }
+        fn merge(&mut self, parts: Self) {
+            self.log.merge(parts.log);
+        }
+    }
+
+    /// Performs one of several tasks, each with a different `Access`.
+    struct Worker(&'static str);
+
+    impl SystemTrait<Journal> for Worker {
+        fn verify_requirements(&self, _: &Assembly) -> bool {
+            true
+        }
+        fn get_loop_stage_filter(&self) -> LoopStageFlag {
+            LoopStageFlag::UPDATE
+        }
+        fn get_access(&self) -> Access {
+            match self.0 {
+                "integrate" => Access::new().write::<Position>().read::<Velocity>(),
+                "accelerate" => Access::new().write::<Velocity>(),
+                "count" => Access::new().read::<Velocity>().aux::<Vec<String>>(),
+                "fail" => Access::new().aux::<Vec<String>>(),
+                _ => Access::new(),
+            }
+        }
+        fn as_send(&mut self) -> Option<&mut (dyn SystemTrait<Journal> + Send)> {
+            // The counter is called on the current thread, as if it was not `Send`.
+            if self.0 == "count" {
+                None
+            } else {
+                Some(self)
+            }
+        }
+        fn update(
+            &mut self,
+            entities: &mut Assembly,
+            journal: &mut Journal,
+            _: &Duration,
+            _: &Duration,
+        ) -> DispatchEvents {
+            let log = &mut journal.log;
+            match self.0 {
+                "integrate" => for (_, p, v) in entities.w1r1_iter::<Position, Velocity>() {
+                    p.0 += v.0;
+                },
+                "accelerate" => for (_, v) in entities.w1_iter::<Velocity>() {
+                    v.0 += 1;
+                },
+                "count" => log.push(format!("{}", entities.count1::<Velocity>())),
+                "log" => log.push("log".into()),
+                "fail" => panic!("The worker failed"),
+                _ => {
+                    entities.count1::<Position>();
+                }
+            }
+            (None, Some(vec![Ping.into()]))
+        }
+    }
+
+    #[test]
+    fn test_parallel_execution() {
+        let run = |mode| {
+            let mut w: World<Journal, Worker> = World::new();
+            w.set_execution_mode(mode);
+            w.add_system(Worker("integrate")).unwrap();
+            w.add_system(Worker("count")).unwrap();
+            w.add_system(Worker("accelerate")).unwrap();
+            for i in 0..3 {
+                let e = w.create_entity();
+                w.add_component(&e, Position(0)).unwrap();
+                w.add_component(&e, Velocity(i)).unwrap();
+            }
+
+            let t = Duration::default();
+            for _ in 0..3 {
+                w.update(&t, &t);
+            }
+
+            let positions = w.r1::<Position>()
+                .into_iter()
+                .map(|(_, p)| p.0)
+                .collect::<Vec<_>>();
+            (positions, w.aux.log.clone(), w.event_queue.len())
+        };
+
+        let sequential = run(ExecutionMode::SingleThreaded);
+        assert_eq!(sequential.0, vec![3, 6, 9]);
+        assert_eq!(run(ExecutionMode::Parallel), sequential);
+    }
+
+    #[test]
+    #[should_panic(expected = "without being declared")]
+    fn test_parallel_execution_undeclared_access() {
+        let mut w: World<Journal, Worker> = World::new();
+        w.set_execution_mode(ExecutionMode::Parallel);
+        w.add_system(Worker("accelerate")).unwrap();
+        w.add_system(Worker("undeclared")).unwrap();
+
+        let t = Duration::default();
+        w.update(&t, &t);
+    }
+
+    #[test]
+    #[should_panic(expected = "auxiliary part")]
+    fn test_parallel_execution_undeclared_aux_access() {
+        let mut w: World<Journal, Worker> = World::new();
+        w.set_execution_mode(ExecutionMode::Parallel);
+        w.add_system(Worker("accelerate")).unwrap();
+        w.add_system(Worker("log")).unwrap();
+
+        let t = Duration::default();
+        w.update(&t, &t);
+    }
+
+    #[test]
+    fn test_parallel_execution_panic() {
+        let mut w: World<Journal, Worker> = World::new();
+        w.set_execution_mode(ExecutionMode::Parallel);
+        w.add_system(Worker("accelerate")).unwrap();
+        w.add_system(Worker("fail")).unwrap();
+        let e = w.create_entity();
+        w.add_component(&e, Velocity(0)).unwrap();
+
+        let t = Duration::default();
+        let r = panic::catch_unwind(AssertUnwindSafe(|| w.update(&t, &t)));
+        assert!(r.is_err());
+
+        // The storages and auxiliary parts of the failed batch are returned to the world.
+        assert_eq!(w.borrow_component::<Velocity>(&e).unwrap(), &Velocity(1));
+        w.aux.log.push("recovered".into());
+        assert_eq!(*w.aux.log, vec![String::from("recovered")]);
+    }
+
+    #[derive(Debug)]
+    struct Pong(u32);
+    impl EventTrait for Pong {}
+
+    /// Answers each `Ping` with a `Pong` and logs all received events.
+    struct Listener;
+
+    impl SystemTrait<Vec<String>> for Listener {
+        fn verify_requirements(&self, _: &Assembly) -> bool {
+            true
+        }
+        fn get_loop_stage_filter(&self) -> LoopStageFlag {
+            LoopStageFlag::HANDLE_EVENT
+        }
+        fn get_event_filter(&self) -> EventFilter {
+            EventFilter::new().with::<Ping>().with::<Pong>()
+        }
+        fn handle_event(
+            &mut self,
+            _: &mut Assembly,
+            log: &mut Vec<String>,
+            event: &Event,
+        ) -> DispatchEvents {
+            match event.downcast_ref::<Pong>() {
+                Some(p) => {
+                    log.push(format!("Pong {}", p.0));
+                    (None, None)
+                }
+                None => {
+                    log.push(format!("{:?}", event));
+                    (Some(vec![Pong(0).into()]), Some(vec![Pong(1).into()]))
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_event_dispatch() {
+        let mut w: World<Vec<String>, Listener> = World::new();
+        w.add_system(Listener).unwrap();
+
+        w.dispatch(Ping);
+        w.dispatch(EcsEvent::Shutdown);
+        assert!(w.handle_events());
+        assert_eq!(w.aux, vec!["Ping", "Pong 0"]);
+
+        assert!(!w.handle_events());
+        assert_eq!(w.aux, vec!["Ping", "Pong 0", "Pong 1"]);
+    }
+
+    #[test]
+    fn test_timed_events() {
+        let mut w: World<Vec<String>, Listener> = World::new();
+        w.add_system(Listener).unwrap();
+
+        w.dispatch_after(Duration::from_secs(2), Ping);
+        let h = w.dispatch_after_ticks(0, Pong(2));
+        w.dispatch_after_ticks(0, Pong(3));
+        w.dispatch_after_ticks(1, Pong(4));
+        assert!(w.cancel(h));
+        assert!(!w.cancel(h));
+
+        let dt = Duration::from_secs(1);
+        let mut log = Vec::new();
+        for i in 0..3 {
+            w.update(&(dt * i), &dt);
+            w.handle_events();
+            log.push(w.aux.drain(..).collect::<Vec<_>>());
+        }
+        assert_eq!(log, vec![vec!["Pong 3"], vec!["Pong 4"], vec!["Ping", "Pong 0"]]);
+        assert!(w.timers().is_empty());
+    }
+
+    /// Answers each event of the first type with a priority event of the second type.
+    struct Echo(&'static str, bool);
+
+    impl SystemTrait<()> for Echo {
+        fn verify_requirements(&self, _: &Assembly) -> bool {
+            true
+        }
+        fn get_loop_stage_filter(&self) -> LoopStageFlag {
+            LoopStageFlag::HANDLE_EVENT
+        }
+        fn get_label(&self) -> Option<&'static str> {
+            Some(self.0)
+        }
+        fn get_event_filter(&self) -> EventFilter {
+            if self.1 {
+                EventFilter::new().with::<Ping>()
+            } else {
+                EventFilter::new().with::<Pong>()
+            }
+        }
+        fn handle_event(&mut self, _: &mut Assembly, _: &mut (), _: &Event) -> DispatchEvents {
+            if self.1 {
+                (Some(vec![Pong(0).into()]), None)
+            } else {
+                (Some(vec![Ping.into()]), None)
+            }
+        }
+    }
+
+    #[test]
+    fn test_event_cycle() {
+        let mut w: World<(), Echo> = World::new();
+        w.add_system(Echo("pinger", true)).unwrap();
+        w.add_system(Echo("ponger", false)).unwrap();
+        w.set_event_recursion_limit(10);
+
+        w.dispatch(Ping);
+        assert!(w.handle_events());
+
+        let s = w.event_statistics();
+        assert_eq!(s.count::<Ping>(), 6);
+        assert_eq!(s.count::<Pong>(), 6);
+        assert_eq!(s.errors().len(), 1);
+        assert!(s.errors()[0].contains("[\"ponger\", \"pinger\"]"));
+
+        assert!(w.handle_events());
+        assert_eq!(w.event_statistics().total(), 0);
+    }
+
+    #[test]
+    fn test_lifecycle_hooks() {
+        let mut w: World<Vec<String>, Tracker> = World::new();
+        w.on_add::<Position, _>(|entities, log, entity| {
+            let p = entities.borrow_component::<Position>(entity).unwrap();
+            log.push(format!("added {}", p.0));
+        });
+        w.on_remove::<Position, _>(|_, log, _| log.push("removed".into()));
+
+        let e = w.create_entity();
+        w.add_component(&e, Position(3)).unwrap();
+        w.add_component(&e, Position(4)).unwrap();
+        assert!(w.aux.is_empty());
+
+        let t = Duration::default();
+        w.update(&t, &t);
+        assert_eq!(w.aux, vec![String::from("added 4")]);
+
+        w.destroy_entity(&e);
+        w.handle_events();
+        assert_eq!(w.aux, vec![String::from("added 4"), String::from("removed")]);
+    }
+}
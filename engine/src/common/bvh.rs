@@ -0,0 +1,471 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::f32;
+use std::hash::Hash;
+use std::mem;
+use nalgebra::Point3;
+use common::affine_transform::AffineTransform;
+use common::ray::Ray;
+
+/// Specifies by how much the bounds of each leaf of a `Bvh` are enlarged, such that small
+/// movements do not require changes to the tree.
+const DEFAULT_MARGIN: f32 = 0.1;
+
+/// `Bounds` describe an axis-aligned box in terms of its minimum and maximum corners.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bounds {
+    pub min: Point3<f32>,
+    pub max: Point3<f32>,
+}
+
+impl Bounds {
+    /// Creates new `Bounds` from their minimum and maximum corners.
+    pub fn new(min: Point3<f32>, max: Point3<f32>) -> Self {
+        Bounds { min, max }
+    }
+    /// Creates the smallest `Bounds` that contain all of the specified points. Without any
+    /// points, the `Bounds` are empty and intersect nothing.
+    pub fn from_points(points: &[Point3<f32>]) -> Self {
+        let init = Bounds::new(
+            Point3::new(f32::INFINITY, f32::INFINITY, f32::INFINITY),
+            Point3::new(-f32::INFINITY, -f32::INFINITY, -f32::INFINITY),
+        );
+
+        points
+            .iter()
+            .fold(init, |b, p| b.merge(&Bounds::new(*p, *p)))
+    }
+    /// Returns the eight corners of the `Bounds`.
+    pub fn corners(&self) -> [Point3<f32>; 8] {
+        let (a, b) = (&self.min, &self.max);
+        [
+            Point3::new(a.x, a.y, a.z),
+            Point3::new(b.x, a.y, a.z),
+            Point3::new(a.x, b.y, a.z),
+            Point3::new(b.x, b.y, a.z),
+            Point3::new(a.x, a.y, b.z),
+            Point3::new(b.x, a.y, b.z),
+            Point3::new(a.x, b.y, b.z),
+            Point3::new(b.x, b.y, b.z),
+        ]
+    }
+    /// Returns the smallest `Bounds` that contain both these and the other `Bounds`.
+    pub fn merge(&self, other: &Bounds) -> Bounds {
+        Bounds::new(
+            Point3::new(
+                self.min.x.min(other.min.x),
+                self.min.y.min(other.min.y),
+                self.min.z.min(other.min.z),
+            ),
+            Point3::new(
+                self.max.x.max(other.max.x),
+                self.max.y.max(other.max.y),
+                self.max.z.max(other.max.z),
+            ),
+        )
+    }
+    /// Returns `true` if the other `Bounds` lie entirely within these.
+    pub fn contains(&self, other: &Bounds) -> bool {
+        (0..3).all(|i| {
+            self.min.coords[i] <= other.min.coords[i] && other.max.coords[i] <= self.max.coords[i]
+        })
+    }
+    /// Returns the `Bounds` enlarged by the specified margin in all directions.
+    pub fn expand(&self, margin: f32) -> Bounds {
+        Bounds::new(
+            Point3::new(self.min.x - margin, self.min.y - margin, self.min.z - margin),
+            Point3::new(self.max.x + margin, self.max.y + margin, self.max.z + margin),
+        )
+    }
+    /// Returns the surface area of the `Bounds`.
+    pub fn surface_area(&self) -> f32 {
+        let d = self.max - self.min;
+        2.0 * (d.x * d.y + d.y * d.z + d.z * d.x)
+    }
+    /// Returns the smallest `Bounds` that contain these `Bounds` after the transformation.
+    pub fn transform(&self, transform: &AffineTransform<f32>) -> Bounds {
+        let corners = self.corners()
+            .iter()
+            .map(|c| transform.transform_point(c))
+            .collect::<Vec<_>>();
+        Bounds::from_points(&corners)
+    }
+    /// Performs an intersection test of the `Bounds` against the supplied `Ray` and optionally
+    /// returns the `Ray` position at which it enters the `Bounds` (zero, if the `Ray` originates
+    /// within).
+    pub fn intersect_ray(&self, ray: &Ray<f32>) -> Option<f32> {
+        let mut t_min = 0.0;
+        let mut t_max = f32::INFINITY;
+        for i in 0..3 {
+            let (min, max) = (self.min.coords[i], self.max.coords[i]);
+            let o = ray.origin.coords[i];
+            let d = ray.direction[i];
+            if min > max {
+                return None;
+            }
+
+            if d.abs() > f32::EPSILON {
+                let mut t_1 = (min - o) / d;
+                let mut t_2 = (max - o) / d;
+
+                if t_1 > t_2 {
+                    mem::swap(&mut t_1, &mut t_2);
+                }
+
+                t_min = t_1.max(t_min);
+                t_max = t_2.min(t_max);
+
+                if t_min > t_max {
+                    return None;
+                }
+            } else if o < min || o > max {
+                return None;
+            }
+        }
+
+        Some(t_min)
+    }
+}
+
+/// Distinguishes the leaves of a `Bvh` from its inner nodes.
+#[derive(Debug, Clone)]
+enum BvhNodeKind<K> {
+    Leaf(K),
+    Branch(usize, usize),
+}
+
+/// Each node of a `Bvh` holds the bounds of its entire subtree.
+#[derive(Debug, Clone)]
+struct BvhNode<K> {
+    bounds: Bounds,
+    parent: Option<usize>,
+    kind: BvhNodeKind<K>,
+}
+
+/// The bounding volume hierarchy `Bvh` is a binary tree of `Bounds`, where each leaf corresponds
+/// to a key. The leaves are enlarged by a margin, such that they only need to be reinserted once
+/// the `Bounds` of a key move beyond it. Insertion follows the surface area heuristic.
+#[derive(Debug, Clone)]
+pub struct Bvh<K: Clone + Eq + Hash> {
+    /// Holds all nodes of the tree, including vacant ones.
+    nodes: Vec<BvhNode<K>>,
+    /// Holds the indices of vacant nodes.
+    free: Vec<usize>,
+    /// Holds the index of the root node, if any.
+    root: Option<usize>,
+    /// Provides the index of the leaf for each key.
+    leaves: HashMap<K, usize>,
+    /// Specifies by how much the bounds of each leaf are enlarged.
+    margin: f32,
+}
+
+impl<K: Clone + Eq + Hash> Default for Bvh<K> {
+    fn default() -> Self {
+        Bvh::with_margin(DEFAULT_MARGIN)
+    }
+}
+
+impl<K: Clone + Eq + Hash> Bvh<K> {
+    /// Creates a new, empty `Bvh`.
+    pub fn new() -> Self {
+        Default::default()
+    }
+    /// Creates a new, empty `Bvh`, whose leaves are enlarged by the specified margin.
+    pub fn with_margin(margin: f32) -> Self {
+        Bvh {
+            nodes: Vec::new(),
+            free: Vec::new(),
+            root: None,
+            leaves: HashMap::new(),
+            margin: margin,
+        }
+    }
+    /// Returns the number of keys in the `Bvh`.
+    pub fn len(&self) -> usize {
+        self.leaves.len()
+    }
+    /// Returns `true` if the `Bvh` holds no keys.
+    pub fn is_empty(&self) -> bool {
+        self.leaves.is_empty()
+    }
+    /// Returns `true` if the specified key is represented within the `Bvh`.
+    pub fn contains(&self, key: &K) -> bool {
+        self.leaves.contains_key(key)
+    }
+    /// Inserts the key with the specified `Bounds`, or updates the `Bounds` of a key that is
+    /// already present. Returns `true` if the tree had to be changed, which is not the case if
+    /// the `Bounds` still lie within the enlarged leaf.
+    pub fn insert(&mut self, key: K, bounds: Bounds) -> bool {
+        if let Some(&leaf) = self.leaves.get(&key) {
+            if self.nodes[leaf].bounds.contains(&bounds) {
+                return false;
+            }
+            self.detach(leaf);
+            self.nodes[leaf].bounds = bounds.expand(self.margin);
+            self.attach(leaf);
+            return true;
+        }
+
+        let leaf = self.allocate(BvhNode {
+            bounds: bounds.expand(self.margin),
+            parent: None,
+            kind: BvhNodeKind::Leaf(key.clone()),
+        });
+        self.leaves.insert(key, leaf);
+        self.attach(leaf);
+        true
+    }
+    /// Removes the specified key and returns `true` if it was present.
+    pub fn remove(&mut self, key: &K) -> bool {
+        match self.leaves.remove(key) {
+            Some(leaf) => {
+                self.detach(leaf);
+                self.free.push(leaf);
+                true
+            }
+            None => false,
+        }
+    }
+    /// Casts the `Ray` through the `Bvh` and returns the closest hit. The supplied closure
+    /// performs the exact intersection test for the key of a leaf whose `Bounds` are hit, and
+    /// optionally returns the distance along the `Ray` and the result of the test. Subtrees that
+    /// lie beyond the closest hit found so far are skipped.
+    pub fn raycast<R, F>(&self, ray: &Ray<f32>, mut test: F) -> Option<(f32, R)>
+    where
+        F: FnMut(&K) -> Option<(f32, R)>,
+    {
+        let mut closest: Option<(f32, R)> = None;
+        let mut stack = self.root
+            .and_then(|r| self.entry(r, ray))
+            .into_iter()
+            .collect::<Vec<_>>();
+
+        while let Some((t, idx)) = stack.pop() {
+            if let Some((d, _)) = closest {
+                if t > d {
+                    continue;
+                }
+            }
+            match self.nodes[idx].kind {
+                BvhNodeKind::Leaf(ref key) => if let Some(hit) = test(key) {
+                    let nearer = match closest {
+                        Some((d, _)) => hit.0 < d,
+                        None => true,
+                    };
+                    if nearer {
+                        closest = Some(hit);
+                    }
+                },
+                BvhNodeKind::Branch(a, b) => {
+                    // Push the nearer child last, such that it is visited first.
+                    let mut children = [a, b]
+                        .iter()
+                        .filter_map(|&c| self.entry(c, ray))
+                        .collect::<Vec<_>>();
+                    children.sort_by(|x, y| y.0.partial_cmp(&x.0).unwrap_or(Ordering::Equal));
+                    stack.extend(children);
+                }
+            }
+        }
+        closest
+    }
+    /// Casts the `Ray` through the `Bvh` and returns all hits sorted by their distance along the
+    /// `Ray`. Refer to `Bvh::raycast` for more information.
+    pub fn raycast_all<R, F>(&self, ray: &Ray<f32>, mut test: F) -> Vec<(f32, R)>
+    where
+        F: FnMut(&K) -> Option<(f32, R)>,
+    {
+        let mut hits = Vec::new();
+        let mut stack = self.root.into_iter().collect::<Vec<_>>();
+        while let Some(idx) = stack.pop() {
+            if self.entry(idx, ray).is_none() {
+                continue;
+            }
+            match self.nodes[idx].kind {
+                BvhNodeKind::Leaf(ref key) => hits.extend(test(key)),
+                BvhNodeKind::Branch(a, b) => stack.extend(&[a, b]),
+            }
+        }
+        hits.sort_by(|x, y| x.0.partial_cmp(&y.0).unwrap_or(Ordering::Equal));
+        hits
+    }
+    /// Returns the `Ray` position at which it enters the specified node, along with the node.
+    fn entry(&self, idx: usize, ray: &Ray<f32>) -> Option<(f32, usize)> {
+        self.nodes[idx].bounds.intersect_ray(ray).map(|t| (t, idx))
+    }
+    /// Stores the node in a vacant slot, if possible, and returns its index.
+    fn allocate(&mut self, node: BvhNode<K>) -> usize {
+        match self.free.pop() {
+            Some(idx) => {
+                self.nodes[idx] = node;
+                idx
+            }
+            None => {
+                self.nodes.push(node);
+                self.nodes.len() - 1
+            }
+        }
+    }
+    /// Attaches the leaf to the tree next to the sibling that least increases the surface area
+    /// of the tree, and refits the ancestors.
+    fn attach(&mut self, leaf: usize) {
+        let mut sibling = match self.root {
+            Some(r) => r,
+            None => {
+                self.nodes[leaf].parent = None;
+                self.root = Some(leaf);
+                return;
+            }
+        };
+
+        let bounds = self.nodes[leaf].bounds;
+        while let BvhNodeKind::Branch(a, b) = self.nodes[sibling].kind {
+            let cost = |idx: usize| {
+                let b = &self.nodes[idx].bounds;
+                b.merge(&bounds).surface_area() - b.surface_area()
+            };
+            sibling = if cost(a) <= cost(b) { a } else { b };
+        }
+
+        let parent = self.nodes[sibling].parent;
+        let branch = self.allocate(BvhNode {
+            bounds: bounds.merge(&self.nodes[sibling].bounds),
+            parent: parent,
+            kind: BvhNodeKind::Branch(sibling, leaf),
+        });
+        self.nodes[sibling].parent = Some(branch);
+        self.nodes[leaf].parent = Some(branch);
+        match parent {
+            Some(p) => {
+                self.replace_child(p, sibling, branch);
+                self.refit(p);
+            }
+            None => self.root = Some(branch),
+        }
+    }
+    /// Detaches the leaf from the tree, such that its sibling takes the place of their parent,
+    /// and refits the ancestors. The leaf itself remains allocated.
+    fn detach(&mut self, leaf: usize) {
+        let parent = match self.nodes[leaf].parent.take() {
+            Some(p) => p,
+            None => {
+                self.root = None;
+                return;
+            }
+        };
+
+        let sibling = match self.nodes[parent].kind {
+            BvhNodeKind::Branch(a, b) if a == leaf => b,
+            BvhNodeKind::Branch(a, _) => a,
+            BvhNodeKind::Leaf(_) => unreachable!(),
+        };
+        let grandparent = self.nodes[parent].parent;
+        self.nodes[sibling].parent = grandparent;
+        match grandparent {
+            Some(g) => {
+                self.replace_child(g, parent, sibling);
+                self.refit(g);
+            }
+            None => self.root = Some(sibling),
+        }
+        self.free.push(parent);
+    }
+    /// Replaces a child of the specified branch.
+    fn replace_child(&mut self, branch: usize, old: usize, new: usize) {
+        if let BvhNodeKind::Branch(ref mut a, ref mut b) = self.nodes[branch].kind {
+            if *a == old {
+                *a = new;
+            } else {
+                *b = new;
+            }
+        }
+    }
+    /// Recalculates the bounds of the specified branch and of all its ancestors.
+    fn refit(&mut self, branch: usize) {
+        let mut current = Some(branch);
+        while let Some(idx) = current {
+            if let BvhNodeKind::Branch(a, b) = self.nodes[idx].kind {
+                self.nodes[idx].bounds = self.nodes[a].bounds.merge(&self.nodes[b].bounds);
+            }
+            current = self.nodes[idx].parent;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use nalgebra::{Unit, Vector3};
+
+    fn cube(x: f32, y: f32) -> Bounds {
+        Bounds::new(Point3::new(x - 0.5, y - 0.5, -0.5), Point3::new(x + 0.5, y + 0.5, 0.5))
+    }
+
+    fn ray() -> Ray<f32> {
+        Ray::new(Point3::origin(), Unit::new_normalize(Vector3::new(1.0, 0.0, 0.0)))
+    }
+
+    /// Intersects the ray with the exact bounds of the cube with the specified key.
+    fn test_cubes<'a>(cubes: &'a HashMap<u32, Bounds>) -> impl Fn(&u32) -> Option<(f32, u32)> + 'a {
+        move |k| cubes[k].intersect_ray(&ray()).map(|t| (t, *k))
+    }
+
+    #[test]
+    fn test_bounds() {
+        let b = cube(2.0, 0.0);
+        assert_eq!(b.intersect_ray(&ray()), Some(1.5));
+        assert_eq!(cube(0.0, 0.0).intersect_ray(&ray()), Some(0.0));
+        assert_eq!(cube(-2.0, 0.0).intersect_ray(&ray()), None);
+        assert_eq!(cube(2.0, 2.0).intersect_ray(&ray()), None);
+        assert_eq!(Bounds::from_points(&[]).intersect_ray(&ray()), None);
+
+        assert!(b.expand(0.1).contains(&b));
+        assert!(!b.contains(&b.expand(0.1)));
+        assert_eq!(b.merge(&cube(0.0, 0.0)).surface_area(), 2.0 * (3.0 + 1.0 + 3.0));
+        assert_eq!(b.transform(&AffineTransform::identity()), b);
+    }
+
+    #[test]
+    fn test_raycast() {
+        let mut bvh = Bvh::new();
+        let mut cubes = HashMap::new();
+        for k in 0..20 {
+            // Place the cubes alternately on and above the ray, in descending order.
+            let c = cube(40.0 - k as f32 * 2.0, (k % 2) as f32 * 2.0);
+            assert!(bvh.insert(k, c));
+            cubes.insert(k, c);
+        }
+        assert_eq!(bvh.len(), 20);
+
+        let closest = bvh.raycast(&ray(), test_cubes(&cubes));
+        assert_eq!(closest, Some((3.5, 18)));
+        let all = bvh.raycast_all(&ray(), test_cubes(&cubes));
+        assert_eq!(all.len(), 10);
+        let keys = all.iter().map(|h| h.1).collect::<Vec<_>>();
+        assert_eq!(keys, (0..10).map(|k| 18 - 2 * k).collect::<Vec<_>>());
+
+        // Small movements stay within the margin, larger ones require reinsertion.
+        let c = cube(4.05, 0.0);
+        assert!(!bvh.insert(18, c));
+        cubes.insert(18, c);
+        let c = cube(4.0, 5.0);
+        assert!(bvh.insert(18, c));
+        cubes.insert(18, c);
+        assert_eq!(bvh.raycast(&ray(), test_cubes(&cubes)), Some((7.5, 16)));
+
+        let c = cube(1.0, 0.0);
+        assert!(bvh.insert(7, c));
+        cubes.insert(7, c);
+        assert_eq!(bvh.raycast(&ray(), test_cubes(&cubes)), Some((0.5, 7)));
+
+        assert!(bvh.remove(&7));
+        assert!(!bvh.remove(&7));
+        assert!(!bvh.contains(&7));
+        assert_eq!(bvh.raycast(&ray(), test_cubes(&cubes)), Some((7.5, 16)));
+        for k in 0..20 {
+            bvh.remove(&k);
+        }
+        assert!(bvh.is_empty());
+        assert_eq!(bvh.raycast(&ray(), test_cubes(&cubes)), None);
+    }
+}
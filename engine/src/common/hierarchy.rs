@@ -1,11 +1,16 @@
 use std::collections::HashMap;
 use std::hash::Hash;
+use std::iter;
+use std::mem;
 use daggy::{Dag, NodeIndex};
 use daggy::petgraph::graph::{DefaultIx, Node};
 use daggy::petgraph::visit::{Bfs, Walker};
 
 /// Given a set of identifying keys and corresponding data, `Hierarchy` allows users to establish
-/// hierarchical relationships between individual instances of the data type.
+/// hierarchical relationships between individual instances of the data type. Nodes are marked
+/// dirty when their local data or their position within the graph changes, such that updates
+/// only visit the dirty subtrees.
+#[derive(Clone)]
 pub struct Hierarchy<K: Clone + Default + Eq + Hash, V: Clone + Default> {
     /// Holds the key of the root node.
     root_key: K,
@@ -14,6 +19,8 @@ pub struct Hierarchy<K: Clone + Default + Eq + Hash, V: Clone + Default> {
     index: HashMap<K, NodeIndex>,
     /// Holds the directed acyclic graph of `HierNode`s.
     graph: Dag<HierNode<K, V>, ()>,
+    /// Holds the keys of the dirty nodes, in the order in which they were marked.
+    dirty: Vec<K>,
 }
 
 impl<K: Clone + Default + Eq + Hash, V: Clone + Default> Default for Hierarchy<K, V> {
@@ -32,6 +39,7 @@ impl<K: Clone + Default + Eq + Hash, V: Clone + Default> Default for Hierarchy<K
             root_key: root_key,
             index: index,
             graph: dag,
+            dirty: Vec::new(),
         }
     }
 }
@@ -41,15 +49,72 @@ impl<K: Clone + Default + Eq + Hash, V: Clone + Default> Hierarchy<K, V> {
     pub fn new() -> Self {
         Default::default()
     }
-    /// Deletes the `HierNode` defined by the specified key.
-    pub fn remove(&mut self, key: &K) -> Result<(), GraphError> {
+    /// Deletes the `HierNode` defined by the specified key along with all of its descendants,
+    /// and returns the keys of all deleted nodes.
+    pub fn remove(&mut self, key: &K) -> Result<Vec<K>, GraphError> {
         if key == &self.root_key {
             return Err(GraphError::CannotRemoveRootNode);
         }
 
         let node_idx = self.get_index(key)?;
-        self.graph.remove_node(node_idx);
-        self.rebuild_index();
+        let keys = Bfs::new(self.graph.graph(), node_idx)
+            .iter(self.graph.graph())
+            .filter_map(|idx| self.graph.node_weight(idx).map(|n| n.key.clone()))
+            .collect::<Vec<_>>();
+        for k in &keys {
+            self.remove_node(k)?;
+        }
+        Ok(keys)
+    }
+    /// Deletes the `HierNode` defined by the specified key, while its children are attached to
+    /// its parent instead and marked dirty.
+    pub fn remove_and_promote(&mut self, key: &K) -> Result<(), GraphError> {
+        if key == &self.root_key {
+            return Err(GraphError::CannotRemoveRootNode);
+        }
+
+        let node_idx = self.get_index(key)?;
+        let parent_idx = self.get_parent_index(node_idx)?;
+        let children = self.graph
+            .children(node_idx)
+            .iter(&self.graph)
+            .map(|(_, c)| c)
+            .collect::<Vec<_>>();
+        for child_idx in children {
+            self.graph
+                .add_edge(parent_idx, child_idx, ())
+                .map_err(|_| GraphError::WouldCycle)?;
+            self.mark_dirty(child_idx);
+        }
+        self.remove_node(key)
+    }
+    /// Moves the `HierNode` defined by the specified key (along with its descendants) to become
+    /// a child of another `HierNode`, and marks it dirty. Errors with `GraphError::WouldCycle` if
+    /// the new parent is the node itself or one of its descendants.
+    pub fn reparent(&mut self, key: &K, new_parent: &K) -> Result<(), GraphError> {
+        if key == &self.root_key {
+            return Err(GraphError::CannotReparentRootNode);
+        }
+
+        let node_idx = self.get_index(key)?;
+        let new_parent_idx = self.get_index(new_parent)?;
+
+        // Walk up from the new parent to make sure that the node is not among its ancestors.
+        let mut ancestor_idx = Some(new_parent_idx);
+        while let Some(idx) = ancestor_idx {
+            if idx == node_idx {
+                return Err(GraphError::WouldCycle);
+            }
+            ancestor_idx = self.graph.parents(idx).walk_next(&self.graph).map(|(_, p)| p);
+        }
+
+        if let Some((edge_idx, _)) = self.graph.parents(node_idx).walk_next(&self.graph) {
+            self.graph.remove_edge(edge_idx);
+        }
+        self.graph
+            .add_edge(new_parent_idx, node_idx, ())
+            .map_err(|_| GraphError::WouldCycle)?;
+        self.mark_dirty(node_idx);
         Ok(())
     }
     /// Inserts a `HierNode` as child of the root `HierNode`.
@@ -57,47 +122,98 @@ impl<K: Clone + Default + Eq + Hash, V: Clone + Default> Hierarchy<K, V> {
         let parent = self.root_key.clone();
         self.insert_child(&parent, child, data).unwrap_or_else(|_| unreachable!())
     }
-    /// Inserts a `HierNode` as child of another `HierNode` identified by its key.
+    /// Inserts a `HierNode` as child of another `HierNode` identified by its key. The new node is
+    /// marked dirty.
     pub fn insert_child(&mut self, parent: &K, child: K, data: V) -> Result<(), GraphError> {
         let parent_idx = self.get_index(parent)?;
         let child_node = HierNode::new(child.clone(), data);
         let (_, child_idx) = self.graph.add_child(parent_idx, (), child_node);
         self.index.insert(child, child_idx);
+        self.mark_dirty(child_idx);
         Ok(())
     }
+    /// Returns the key of the parent of the `HierNode` defined by the specified key, or `None`
+    /// for the root node.
+    pub fn parent(&self, key: &K) -> Result<Option<&K>, GraphError> {
+        let node_idx = self.get_index(key)?;
+        match self.graph.parents(node_idx).walk_next(&self.graph) {
+            Some((_, parent_idx)) => self.graph
+                .node_weight(parent_idx)
+                .map(|n| Some(&n.key))
+                .ok_or(GraphError::NodeNotFound),
+            None => Ok(None),
+        }
+    }
+    /// Returns the keys of the children of the `HierNode` defined by the specified key, in no
+    /// particular order.
+    pub fn children(&self, key: &K) -> Result<Vec<&K>, GraphError> {
+        let node_idx = self.get_index(key)?;
+        Ok(self.graph
+            .children(node_idx)
+            .iter(&self.graph)
+            .filter_map(|(_, c)| self.graph.node_weight(c).map(|n| &n.key))
+            .collect())
+    }
+    /// Returns `true` if the `HierNode` defined by the specified key awaits an update.
+    pub fn is_dirty(&self, key: &K) -> Result<bool, GraphError> {
+        let node_idx = self.get_index(key)?;
+        self.graph
+            .node_weight(node_idx)
+            .map(|n| n.dirty)
+            .ok_or(GraphError::NodeNotFound)
+    }
     /// Returns `true` if the specified key is represented within the `Hierarchy`.
     pub fn has(&self, key: &K) -> bool {
         self.index.contains_key(key)
     }
-    /// Updates each node in breadth first search order. Refer to `HierNode.update` for more
-    /// information.
-    pub fn update<F>(&mut self, merge_fn: &F) -> Result<(), GraphError>
+    /// Updates the global data of all dirty nodes and their descendants in breadth first search
+    /// order, and returns the keys of the updated nodes. If no node is dirty, nothing is visited.
+    /// If the update fails, the nodes that were not yet updated remain dirty and are retried by
+    /// the next update. Refer to `HierNode.update` for more information.
+    pub fn update<F>(&mut self, merge_fn: &F) -> Result<Vec<K>, GraphError>
     where
-        for<'r> F: Fn(&'r K, &'r V) -> Option<V>,
+        F: Fn(&V, &V) -> V,
     {
-        // Obtain the index of the root node.
-        let root_idx = self.get_index(&self.root_key)?;
-
-        // Traverse the tree in breadth-first search order and update each node.
-        let mut bfs = Bfs::new(self.graph.graph(), root_idx);
-        while let Some(nidx) = bfs.next(self.graph.graph()) {
-            let mut parents = self.graph.parents(nidx);
-            if let Some((_, parent_idx)) = parents.walk_next(&self.graph) {
-                let parent_data = self.graph
-                    .node_weight(parent_idx)
-                    .map(|n| n.data.clone())
-                    .ok_or(GraphError::NodeNotFound)?;
-
-                self.graph
-                    .node_weight_mut(nidx)
-                    .map(|n| n.update(&parent_data, merge_fn))
-                    .ok_or(GraphError::NodeNotFound)?;
+        let mut keys = Vec::new();
+        let mut dirty = mem::replace(&mut self.dirty, Vec::new()).into_iter();
+        while let Some(key) = dirty.next() {
+            // Skip nodes that were removed or already updated along with an ancestor. Nodes
+            // with a dirty ancestor are updated along with that ancestor later on.
+            let node_idx = match self.index.get(&key) {
+                Some(&idx) => idx,
+                None => continue,
+            };
+            if self.graph[node_idx].dirty && !self.has_dirty_ancestor(node_idx) {
+                match self.update_from(node_idx, merge_fn) {
+                    Ok(k) => keys.extend(k),
+                    Err(e) => {
+                        // Requeue the current subtree and the unprocessed keys.
+                        self.graph[node_idx].dirty = true;
+                        self.dirty = iter::once(key).chain(dirty).collect();
+                        return Err(e);
+                    }
+                }
             }
         }
-
+        Ok(keys)
+    }
+    /// Updates the global data of the `HierNode` defined by the specified key and of all its
+    /// descendants in breadth first search order, regardless of whether they are dirty, and
+    /// returns the keys of the updated nodes.
+    pub fn update_subtree<F>(&mut self, key: &K, merge_fn: &F) -> Result<Vec<K>, GraphError>
+    where
+        F: Fn(&V, &V) -> V,
+    {
+        let node_idx = self.get_index(key)?;
+        self.update_from(node_idx, merge_fn)
+    }
+    /// Replaces the local data of the `HierNode` defined by the specified key and marks it dirty.
+    /// The global data remains unchanged until the next update.
+    pub fn set(&mut self, key: &K, data: V) -> Result<(), GraphError> {
+        *self.borrow_mut(key)? = data;
         Ok(())
     }
-    /// Borrows the data from the `HierNode` identified by the specified key.
+    /// Borrows the local data from the `HierNode` identified by the specified key.
     pub fn borrow(&self, key: &K) -> Result<&V, GraphError> {
         let node_idx = self.get_index(key)?;
         self.graph
@@ -105,9 +221,20 @@ impl<K: Clone + Default + Eq + Hash, V: Clone + Default> Hierarchy<K, V> {
             .map(|n| &n.data)
             .ok_or(GraphError::KeyNotFound)
     }
-    /// Mutably borrows the data from the `HierNode` defined by the specified key.
+    /// Borrows the global data from the `HierNode` identified by the specified key, as computed
+    /// by the most recent update.
+    pub fn borrow_global(&self, key: &K) -> Result<&V, GraphError> {
+        let node_idx = self.get_index(key)?;
+        self.graph
+            .node_weight(node_idx)
+            .map(|n| &n.global)
+            .ok_or(GraphError::KeyNotFound)
+    }
+    /// Mutably borrows the local data from the `HierNode` defined by the specified key, which is
+    /// marked dirty.
     pub fn borrow_mut(&mut self, key: &K) -> Result<&mut V, GraphError> {
         let node_idx = self.get_index(key)?;
+        self.mark_dirty(node_idx);
         self.graph
             .node_weight_mut(node_idx)
             .map(|n| &mut n.data)
@@ -121,46 +248,120 @@ impl<K: Clone + Default + Eq + Hash, V: Clone + Default> Hierarchy<K, V> {
     fn get_index(&self, key: &K) -> Result<NodeIndex, GraphError> {
         self.index.get(key).cloned().ok_or(GraphError::KeyNotFound)
     }
-    /// Rebuilds the `Key`-`HierNode` index from the underlying `Graph`.
-    fn rebuild_index(&mut self) {
-        self.index.clear();
-        for idx in self.graph.graph().node_indices() {
+    /// Marks the specified node as dirty, unless it already is.
+    fn mark_dirty(&mut self, node_idx: NodeIndex) {
+        if let Some(node) = self.graph.node_weight_mut(node_idx) {
+            if !node.dirty {
+                node.dirty = true;
+                self.dirty.push(node.key.clone());
+            }
+        }
+    }
+    /// Returns `true` if any ancestor of the specified node is dirty.
+    fn has_dirty_ancestor(&self, node_idx: NodeIndex) -> bool {
+        let mut ancestor_idx = self.graph.parents(node_idx).walk_next(&self.graph).map(|(_, p)| p);
+        while let Some(idx) = ancestor_idx {
+            if self.graph[idx].dirty {
+                return true;
+            }
+            ancestor_idx = self.graph.parents(idx).walk_next(&self.graph).map(|(_, p)| p);
+        }
+        false
+    }
+    /// Updates the global data of the specified node and of all its descendants in breadth first
+    /// search order, and marks them clean.
+    fn update_from<F>(&mut self, node_idx: NodeIndex, merge_fn: &F) -> Result<Vec<K>, GraphError>
+    where
+        F: Fn(&V, &V) -> V,
+    {
+        let mut keys = Vec::new();
+
+        // Traverse the subtree in breadth-first search order and update each node.
+        let mut bfs = Bfs::new(self.graph.graph(), node_idx);
+        while let Some(nidx) = bfs.next(self.graph.graph()) {
+            let parent_global = match self.graph.parents(nidx).walk_next(&self.graph) {
+                Some((_, parent_idx)) => self.graph
+                    .node_weight(parent_idx)
+                    .map(|n| Some(n.global.clone()))
+                    .ok_or(GraphError::NodeNotFound)?,
+                None => None,
+            };
+
             let node = self.graph
-                .node_weight(idx)
-                .unwrap_or_else(|| unreachable!());
-            self.index.insert(node.key.clone(), idx);
+                .node_weight_mut(nidx)
+                .ok_or(GraphError::NodeNotFound)?;
+            match parent_global {
+                Some(ref p) => node.update(p, merge_fn),
+                None => node.global = node.data.clone(),
+            }
+            node.dirty = false;
+            keys.push(node.key.clone());
         }
+
+        Ok(keys)
+    }
+    /// Returns the `NodeIndex` of the parent of the specified node.
+    fn get_parent_index(&self, node_idx: NodeIndex) -> Result<NodeIndex, GraphError> {
+        self.graph
+            .parents(node_idx)
+            .walk_next(&self.graph)
+            .map(|(_, p)| p)
+            .ok_or(GraphError::NodeNotFound)
+    }
+    /// Deletes a single `HierNode` and its edges. The graph moves its last node into the vacated
+    /// slot, such that only the index of that node needs to be updated.
+    fn remove_node(&mut self, key: &K) -> Result<(), GraphError> {
+        let node_idx = self.get_index(key)?;
+        let last_idx = NodeIndex::new(self.graph.node_count() - 1);
+        self.graph
+            .remove_node(node_idx)
+            .ok_or(GraphError::NodeNotFound)?;
+        self.index.remove(key);
+
+        if node_idx != last_idx {
+            let moved_key = self.graph
+                .node_weight(node_idx)
+                .map(|n| n.key.clone())
+                .ok_or(GraphError::NodeNotFound)?;
+            self.index.insert(moved_key, node_idx);
+        }
+        Ok(())
     }
 }
 
-/// Each `HierNode` consists of an identifying key and the associated data.
+/// Each `HierNode` consists of an identifying key, the associated local data, and the global
+/// data that results from combining the local data with that of all ancestors.
 #[derive(Default, Clone)]
 pub struct HierNode<K, V: Clone + Default> {
     /// Provides access to the identifying key.
     pub key: K,
-    /// Provides access to the hierarchical data.
+    /// Provides access to the local data, relative to the parent node.
     pub data: V,
+    /// Provides access to the global data, as computed by the most recent update.
+    pub global: V,
+    /// Is `true` if the global data must be updated.
+    dirty: bool,
 }
 
 impl<K, V: Clone + Default> HierNode<K, V> {
-    /// Creates a new `HierNode`.
+    /// Creates a new `HierNode`. Until the next update, the global data equals the local data.
     pub fn new(key: K, data: V) -> Self {
         HierNode {
             key: key,
+            global: data.clone(),
             data: data,
+            dirty: false,
         }
     }
-    /// Given the parent node's data, update the current node's data with the supplied closure.
-    /// This allows users to establish hierarchical relationships between instances of a type.
-    /// As arguments, the closure will receive the current node's key and a reference to its parent
-    /// node's data.
-    pub fn update<F>(&mut self, parent_data: &V, merge_fn: &F)
+    /// Given the parent node's global data, update the current node's global data with the
+    /// supplied closure. This allows users to establish hierarchical relationships between
+    /// instances of a type. As arguments, the closure will receive the parent node's global data
+    /// and the current node's local data. The local data is never modified.
+    pub fn update<F>(&mut self, parent_global: &V, merge_fn: &F)
     where
-        for<'r> F: Fn(&'r K, &'r V) -> Option<V>,
+        F: Fn(&V, &V) -> V,
     {
-        if let Some(data) = merge_fn(&self.key, parent_data) {
-            self.data = data;
-        }
+        self.global = merge_fn(parent_global, &self.data);
     }
 }
 
@@ -200,4 +401,129 @@ pub enum GraphError {
     #[fail(display = "The key was found more than once.")] MultipleKeysFound,
     #[fail(display = "The root node may not be removed.")] CannotRemoveRootNode,
     #[fail(display = "The specified node was not found.")] NodeNotFound,
+    #[fail(display = "The root node may not be moved.")] CannotReparentRootNode,
+    #[fail(display = "A node may not become a descendant of itself.")] WouldCycle,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::cell::Cell;
+
+    fn sorted(keys: Vec<&u32>) -> Vec<u32> {
+        let mut keys = keys.into_iter().cloned().collect::<Vec<_>>();
+        keys.sort();
+        keys
+    }
+
+    /// Creates the hierarchy 0 -> 1 -> (2 -> 4, 3).
+    fn hierarchy() -> Hierarchy<u32, u32> {
+        let mut h = Hierarchy::new();
+        h.insert(1, 10);
+        h.insert_child(&1, 2, 20).unwrap();
+        h.insert_child(&1, 3, 30).unwrap();
+        h.insert_child(&2, 4, 40).unwrap();
+        h
+    }
+
+    #[test]
+    fn reparent() {
+        let mut h = hierarchy();
+        h.update(&|p, l| p + l).unwrap();
+        assert_eq!(h.parent(&4).unwrap(), Some(&2));
+        assert_eq!(h.parent(&0).unwrap(), None);
+
+        h.reparent(&4, &3).unwrap();
+        assert!(h.is_dirty(&4).unwrap());
+        assert!(!h.is_dirty(&2).unwrap());
+        assert_eq!(h.update(&|p, l| p + l).unwrap(), vec![4]);
+        assert_eq!(h.borrow_global(&4).unwrap(), &80);
+        assert_eq!(h.parent(&4).unwrap(), Some(&3));
+        assert!(h.children(&2).unwrap().is_empty());
+        assert_eq!(sorted(h.children(&1).unwrap()), vec![2, 3]);
+
+        match h.reparent(&1, &4) {
+            Err(GraphError::WouldCycle) => (),
+            r => panic!("Unexpected result {:?}", r),
+        }
+        match h.reparent(&0, &1) {
+            Err(GraphError::CannotReparentRootNode) => (),
+            r => panic!("Unexpected result {:?}", r),
+        }
+        assert_eq!(h.parent(&1).unwrap(), Some(&0));
+    }
+
+    #[test]
+    fn remove_subtree() {
+        let mut h = hierarchy();
+        let mut removed = h.remove(&2).unwrap();
+        removed.sort();
+        assert_eq!(removed, vec![2, 4]);
+        assert!(!h.has(&4));
+        assert_eq!(h.borrow(&3).unwrap(), &30);
+        assert_eq!(h.borrow(&1).unwrap(), &10);
+        assert_eq!(h.iter().count(), 3);
+    }
+
+    #[test]
+    fn remove_and_promote() {
+        let mut h = hierarchy();
+        h.remove_and_promote(&1).unwrap();
+        assert!(!h.has(&1));
+        assert_eq!(sorted(h.children(&0).unwrap()), vec![2, 3]);
+        assert_eq!(h.parent(&4).unwrap(), Some(&2));
+        assert_eq!(h.borrow(&4).unwrap(), &40);
+    }
+
+    #[test]
+    fn update_globals() {
+        let mut h = hierarchy();
+        h.update(&|p, l| p + l).unwrap();
+        assert_eq!(h.borrow_global(&4).unwrap(), &70);
+        assert_eq!(h.borrow_global(&3).unwrap(), &40);
+
+        h.set(&4, 45).unwrap();
+        h.set(&2, 25).unwrap();
+        assert!(h.is_dirty(&2).unwrap() && !h.is_dirty(&3).unwrap());
+        let mut updated = h.update(&|p, l| p + l).unwrap();
+        updated.sort();
+        assert_eq!(updated, vec![2, 4]);
+        assert_eq!(h.borrow(&2).unwrap(), &25);
+        assert_eq!(h.borrow_global(&2).unwrap(), &35);
+        assert_eq!(h.borrow_global(&4).unwrap(), &80);
+        assert_eq!(h.borrow_global(&3).unwrap(), &40);
+        assert!(h.update(&|p, l| p + l).unwrap().is_empty());
+        assert_eq!(h.update_subtree(&1, &|p, l| p + l).unwrap().len(), 4);
+    }
+
+    #[test]
+    fn update_static_hierarchy() {
+        // Create 100 nodes with 99 children each.
+        let mut h = Hierarchy::new();
+        for i in 1..101 {
+            h.insert(i, 1);
+            for j in 1..100 {
+                h.insert_child(&i, i * 100 + j, 1).unwrap();
+            }
+        }
+        let calls = Cell::new(0);
+        let merge = |p: &u32, l: &u32| {
+            calls.set(calls.get() + 1);
+            p + l
+        };
+
+        assert_eq!(h.update(&merge).unwrap().len(), 10_000);
+        assert_eq!(calls.get(), 10_000);
+
+        // Updating the unchanged hierarchy visits no nodes at all.
+        for _ in 0..100 {
+            assert!(h.update(&merge).unwrap().is_empty());
+        }
+        assert_eq!(calls.get(), 10_000);
+
+        h.set(&1, 2).unwrap();
+        assert_eq!(h.update(&merge).unwrap().len(), 100);
+        assert_eq!(calls.get(), 10_100);
+        assert_eq!(h.borrow_global(&101).unwrap(), &3);
+    }
 }
@@ -0,0 +1,238 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Error, Write};
+use std::path::Path;
+use nalgebra::Point2;
+use glium::glutin::{ElementState, MouseButton, WindowEvent};
+use serde_json;
+use ecs::{EcsEvent, Event};
+use event::{CursorPosition, MouseInput, ResizeWindow};
+
+/// Mirrors `MouseButton` for serialization.
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "MouseButton")]
+enum MouseButtonDef {
+    Left,
+    Right,
+    Middle,
+    Other(u8),
+}
+
+/// Mirrors `ElementState` for serialization.
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "ElementState")]
+enum ElementStateDef {
+    Pressed,
+    Released,
+}
+
+/// Describes an input event that enters the `World` from the operating system.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum InputEvent {
+    Close,
+    Resize(u32, u32),
+    CursorMoved(u32, u32),
+    MouseInput(
+        #[serde(with = "MouseButtonDef")] MouseButton,
+        #[serde(with = "ElementStateDef")] ElementState,
+    ),
+}
+
+impl InputEvent {
+    /// Translates a window event to an input event, if it is of interest to the engine.
+    pub fn from_window_event(event: WindowEvent) -> Option<Self> {
+        match event {
+            WindowEvent::Closed => Some(InputEvent::Close),
+            WindowEvent::Resized(w, h) => Some(InputEvent::Resize(w, h)),
+            WindowEvent::CursorMoved {
+                position: (x, y), ..
+            } => {
+                // Convert the coordinates to pixels.
+                Some(InputEvent::CursorMoved(x.floor() as u32, y.floor() as u32))
+            }
+            WindowEvent::MouseInput {
+                state: s,
+                button: b,
+                ..
+            } => Some(InputEvent::MouseInput(b, s)),
+            _ => None,
+        }
+    }
+    /// Returns `true` if the event is to be dispatched as a priority event.
+    pub fn is_priority(&self) -> bool {
+        match *self {
+            InputEvent::CursorMoved(..) | InputEvent::MouseInput(..) => true,
+            InputEvent::Close | InputEvent::Resize(..) => false,
+        }
+    }
+    /// Converts the input event to the corresponding engine event.
+    pub fn into_event(self) -> Event {
+        match self {
+            InputEvent::Close => EcsEvent::Shutdown.into(),
+            InputEvent::Resize(w, h) => ResizeWindow(w, h).into(),
+            InputEvent::CursorMoved(x, y) => CursorPosition(Point2::new(x, y)).into(),
+            InputEvent::MouseInput(b, s) => MouseInput(b, s).into(),
+        }
+    }
+}
+
+/// Associates an input event with the tick of the fixed-step update at which it entered the
+/// `World`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InputRecord {
+    pub tick: u64,
+    pub event: InputEvent,
+}
+
+/// Writes input events to a file, one JSON-encoded `InputRecord` per line.
+pub struct InputRecorder {
+    writer: BufWriter<File>,
+}
+
+impl InputRecorder {
+    /// Creates (or truncates) the specified file for recording.
+    pub fn create(path: &Path) -> Result<Self, InputError> {
+        Ok(InputRecorder {
+            writer: BufWriter::new(File::create(path)?),
+        })
+    }
+    /// Appends the event to the recording. The file is flushed immediately, such that the
+    /// recording survives a crash.
+    pub fn record(&mut self, tick: u64, event: &InputEvent) -> Result<(), InputError> {
+        let record = InputRecord {
+            tick,
+            event: event.clone(),
+        };
+        serde_json::to_writer(&mut self.writer, &record)?;
+        self.writer.write_all(b"\n")?;
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Feeds recorded input events back in the order and at the ticks they were recorded.
+#[derive(Debug, Clone, Default)]
+pub struct InputReplay {
+    records: VecDeque<InputRecord>,
+}
+
+impl InputReplay {
+    /// Creates a replay from the specified records, which must be sorted by tick.
+    pub fn new(records: Vec<InputRecord>) -> Self {
+        InputReplay {
+            records: records.into(),
+        }
+    }
+    /// Loads a recording created by `InputRecorder`.
+    pub fn open(path: &Path) -> Result<Self, InputError> {
+        let mut records = Vec::new();
+        for line in BufReader::new(File::open(path)?).lines() {
+            let line = line?;
+            if !line.trim().is_empty() {
+                records.push(serde_json::from_str(&line)?);
+            }
+        }
+        Ok(InputReplay::new(records))
+    }
+    /// Returns all events that were recorded at exactly the specified tick. Events of earlier
+    /// ticks can no longer be replayed faithfully and are skipped.
+    pub fn poll(&mut self, tick: u64) -> Vec<InputEvent> {
+        let mut events = Vec::new();
+        while let Some(r) = self.records.pop_front() {
+            if r.tick > tick {
+                self.records.push_front(r);
+                break;
+            } else if r.tick == tick {
+                events.push(r.event);
+            } else {
+                warn!("Skipped the input event {:?} of the past tick {}", r.event, r.tick);
+            }
+        }
+        events
+    }
+    /// Returns `true` if all recorded events have been replayed.
+    pub fn is_finished(&self) -> bool {
+        self.records.is_empty()
+    }
+}
+
+#[derive(Debug, Fail)]
+pub enum InputError {
+    #[fail(display = "{}", _0)] IoError(#[cause] Error),
+    #[fail(display = "{}", _0)] SerializationError(#[cause] serde_json::Error),
+}
+
+impl From<Error> for InputError {
+    fn from(value: Error) -> Self {
+        InputError::IoError(value)
+    }
+}
+
+impl From<serde_json::Error> for InputError {
+    fn from(value: serde_json::Error) -> Self {
+        InputError::SerializationError(value)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn replay_by_tick() {
+        let mut replay = InputReplay::new(vec![
+            InputRecord {
+                tick: 0,
+                event: InputEvent::Resize(800, 600),
+            },
+            InputRecord {
+                tick: 2,
+                event: InputEvent::MouseInput(MouseButton::Left, ElementState::Pressed),
+            },
+            InputRecord {
+                tick: 2,
+                event: InputEvent::Close,
+            },
+        ]);
+
+        assert_eq!(replay.poll(0), vec![InputEvent::Resize(800, 600)]);
+        assert!(replay.poll(1).is_empty());
+        assert_eq!(
+            replay.poll(2),
+            vec![
+                InputEvent::MouseInput(MouseButton::Left, ElementState::Pressed),
+                InputEvent::Close,
+            ]
+        );
+        assert!(replay.is_finished());
+    }
+
+    #[test]
+    fn skip_past_ticks() {
+        let mut replay = InputReplay::new(vec![
+            InputRecord {
+                tick: 1,
+                event: InputEvent::CursorMoved(1, 1),
+            },
+            InputRecord {
+                tick: 3,
+                event: InputEvent::CursorMoved(3, 3),
+            },
+        ]);
+
+        assert_eq!(replay.poll(3), vec![InputEvent::CursorMoved(3, 3)]);
+        assert!(replay.is_finished());
+    }
+
+    #[test]
+    fn serialize_records() {
+        let record = InputRecord {
+            tick: 5,
+            event: InputEvent::MouseInput(MouseButton::Other(4), ElementState::Released),
+        };
+        let line = serde_json::to_string(&record).unwrap();
+        let parsed: InputRecord = serde_json::from_str(&line).unwrap();
+
+        assert_eq!(parsed, record);
+    }
+}
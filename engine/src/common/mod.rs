@@ -3,6 +3,7 @@ pub mod affine_transform;
 pub mod resource_group;
 pub mod layout_group;
 pub mod file_manipulation;
+pub mod input;
 pub mod text_rendering;
 pub mod text_manipulation;
 pub mod vertex;
@@ -12,5 +13,8 @@ pub mod ui_primitive;
 pub mod ui_element;
 pub mod ui_styles;
 pub mod hierarchy;
+pub mod bvh;
 pub mod physics;
+pub mod time_control;
 pub mod factory;
+pub mod scene;
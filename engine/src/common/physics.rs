@@ -1,34 +1,102 @@
 use std::time::Duration;
 use num_traits::float::Float;
 use alga::linear::Transformation;
-use ecs::Assembly;
+use ecs::{Assembly, Entity};
+use common::bvh::Bvh;
 use common::ray::{ObjectHit, Ray};
-use components::model::Model;
+use components::global_transform::GlobalTransform;
 use components::bounding_volume::BoundingVolume;
 
 /// The `PhysicsController` provides means to perform collision detection and other physics
-/// operations.
+/// operations. Ray casts are accelerated by a bounding volume hierarchy (`Bvh`) over the
+/// world-space bounds of all entities, which the `PhysicsUpdater` refits incrementally as
+/// entities change.
 #[derive(Default)]
-pub struct PhysicsController;
+pub struct PhysicsController {
+    bvh: Bvh<Entity>,
+    last_tick: u64,
+}
 
 impl PhysicsController {
-    /// Returns the first intersection of the ray with any object in the `Assembly` with a
-    /// `BoundingVolume` component. Currently no spatial partitioning is performed,
-    /// thus, this algorithm is likely to be very slow.
-    pub fn raycast(&mut self, entities: &Assembly, ray: &Ray<f32>) -> Option<ObjectHit<f32>> {
-        for (e, m, b) in entities.r2::<Model, BoundingVolume>() {
-            // Transform the ray to the local model coordinate system.
-            let transformed_ray = ray.inverse_transform(m.decomposed())?;
-
-            // Perform the intersection test.
-            if let Some((_, p)) = b.intersect_ray(&transformed_ray) {
-                return Some(ObjectHit {
-                    target: e,
-                    point: m.matrix().transform_point(&p),
-                });
+    /// Returns `true` if the specified entity is part of the bounding volume hierarchy.
+    pub fn contains(&self, entity: &Entity) -> bool {
+        self.bvh.contains(entity)
+    }
+    /// Brings the bounding volume hierarchy up to date with all entities whose `GlobalTransform`
+    /// or `BoundingVolume` changed or was removed since the previous refit. Removals are only
+    /// retained for one frame, thus the refit must happen at least once per frame.
+    pub fn refit(&mut self, entities: &Assembly) {
+        let removed = entities
+            .removed::<GlobalTransform>(self.last_tick)
+            .chain(entities.removed::<BoundingVolume>(self.last_tick))
+            .collect::<Vec<_>>();
+        for entity in removed {
+            if !entities.has_component::<GlobalTransform>(&entity)
+                || !entities.has_component::<BoundingVolume>(&entity)
+            {
+                self.bvh.remove(&entity);
+            }
+        }
+
+        let changed = entities
+            .changed::<GlobalTransform>(self.last_tick)
+            .chain(entities.changed::<BoundingVolume>(self.last_tick))
+            .collect::<Vec<_>>();
+        for entity in changed {
+            let g = entities.borrow_component::<GlobalTransform>(&entity);
+            let b = entities.borrow_component::<BoundingVolume>(&entity);
+            if let (Ok(g), Ok(b)) = (g, b) {
+                self.bvh.insert(entity, b.bounds().transform(g.0.decomposed()));
             }
         }
-        None
+
+        self.last_tick = entities.tick();
+    }
+
+    /// Returns the closest intersection of the ray with any object in the `Assembly` with a
+    /// `BoundingVolume` and a `GlobalTransform` component, as of the most recent refit.
+    pub fn raycast(&self, entities: &Assembly, ray: &Ray<f32>) -> Option<ObjectHit<f32>> {
+        self.bvh
+            .raycast(ray, |e| Self::intersect(entities, e, ray))
+            .map(|(_, h)| h)
+    }
+
+    /// Returns all intersections of the ray with objects in the `Assembly`, sorted by their
+    /// distance from the ray origin, as of the most recent refit.
+    pub fn raycast_all(&self, entities: &Assembly, ray: &Ray<f32>) -> Vec<ObjectHit<f32>> {
+        self.bvh
+            .raycast_all(ray, |e| Self::intersect(entities, e, ray))
+            .into_iter()
+            .map(|(_, h)| h)
+            .collect()
+    }
+
+    /// Intersects the ray with the `BoundingVolume` of the specified entity and returns the
+    /// world-space distance along with the hit.
+    fn intersect(
+        entities: &Assembly,
+        entity: &Entity,
+        ray: &Ray<f32>,
+    ) -> Option<(f32, ObjectHit<f32>)> {
+        let g = entities.borrow_component::<GlobalTransform>(entity).ok()?;
+        let b = entities.borrow_component::<BoundingVolume>(entity).ok()?;
+
+        // Transform the ray to the local model coordinate system.
+        let m = &g.0;
+        let transformed_ray = ray.inverse_transform(m.decomposed())?;
+
+        // Perform the intersection test.
+        let (_, p) = b.intersect_ray(&transformed_ray)?;
+        let point = m.matrix().transform_point(&p);
+        let distance = (point - ray.origin).norm();
+
+        Some((
+            distance,
+            ObjectHit {
+                target: *entity,
+                point,
+            },
+        ))
     }
 }
 
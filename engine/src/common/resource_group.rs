@@ -81,7 +81,7 @@ impl FontCacheGroup {
 }
 
 /// Encapsulates a group of shaders as a set of paths to the individual shader source files.
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct ShaderGroup {
     /// Holds the path to the vertex shader.
     pub vertex: PathBuf,
@@ -114,7 +114,7 @@ impl ShaderGroup {
 }
 
 /// Encapsulates a group of textures as a set of paths to the individual texture files.
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct TextureGroup {
     /// Optionally holds the path to the diffuse texture file.
     pub diffuse: Option<PathBuf>,
@@ -0,0 +1,220 @@
+use std::mem;
+use std::path::{Path, PathBuf};
+use glium::Display;
+use serde_json;
+use ecs::{ComponentRegistry, EcsError, Entity, Prefab, Snapshot, SnapshotChange};
+use common::factory::ComponentFactory;
+use common::hierarchy::Hierarchy;
+use common::resource_group::{ShaderGroup, TextureGroup};
+use components::bounding_volume::BoundingVolume;
+use components::camera::Camera;
+use components::description::Description;
+use components::material::Material;
+use components::mesh::{Mesh, MeshOrigin};
+use components::model::Model;
+use components::render_mode::RenderMode;
+use components::tooltip::TooltipData;
+
+/// Provides the resources required to rebuild GPU-backed components when loading a scene.
+pub struct SceneAssets {
+    /// Holds the display that owns the GPU resources.
+    pub display: Display,
+    /// Caches the materials created while loading.
+    pub factory: ComponentFactory,
+}
+
+impl SceneAssets {
+    /// Creates a new `SceneAssets` instance for the specified display.
+    pub fn new(display: &Display, factory: ComponentFactory) -> Self {
+        SceneAssets {
+            display: display.clone(),
+            factory: factory,
+        }
+    }
+    /// Creates a new `SceneAssets` instance that takes over the specified factory (usually the
+    /// one of the `Singletons`) until it is handed back with `restore`. Thus, loaded materials
+    /// are shared with the rest of the engine.
+    pub fn take(display: &Display, factory: &mut ComponentFactory) -> Self {
+        SceneAssets::new(display, mem::replace(factory, Default::default()))
+    }
+    /// Hands the factory back to its owner.
+    pub fn restore(self, factory: &mut ComponentFactory) {
+        *factory = self.factory;
+    }
+}
+
+/// Describes a `Material` in a scene file by the paths to its shaders and textures.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MaterialAsset {
+    pub shaders: ShaderGroup,
+    #[serde(default = "TextureGroup::empty")] pub textures: TextureGroup,
+}
+
+/// Describes a `BoundingVolume` in a scene file, either explicitly or by the kind of volume to
+/// derive from the `Mesh` of the same entity.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum BoundingVolumeAsset {
+    Derived(BoundingVolumeKind),
+    Explicit(BoundingVolume),
+}
+
+/// Enumerates the kinds of `BoundingVolume` that may be derived from a `Mesh`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BoundingVolumeKind {
+    Sphere,
+    Aabb,
+    KDop8,
+}
+
+/// Captures the serializable components of the `World` along with the scene graph of the
+/// `Singletons` at a given update tick.
+#[derive(Clone)]
+pub struct WorldSnapshot {
+    pub entities: Snapshot,
+    pub scene_graph: Hierarchy<Entity, Model>,
+}
+
+impl WorldSnapshot {
+    /// Returns the update tick at which the snapshot was taken.
+    pub fn tick(&self) -> u64 {
+        self.entities.tick()
+    }
+    /// Lists the entities and components that changed between this and the other snapshot.
+    pub fn diff(&self, other: &WorldSnapshot) -> Vec<SnapshotChange> {
+        self.entities.diff(&other.entities)
+    }
+}
+
+/// Creates a registry of all serializable engine components. `Mesh` and `Material` are stored as
+/// references to their assets, with paths relative to the specified resource directory. A
+/// `BoundingVolume` may also be specified by its kind only (see `BoundingVolumeAsset`).
+pub fn scene_registry(resource_path: &Path) -> ComponentRegistry<SceneAssets> {
+    let mut registry = ComponentRegistry::<SceneAssets>::new();
+    registry.register::<Description>();
+    registry.register::<TooltipData>();
+    registry.register::<Camera>();
+    registry.register::<Model>();
+    registry.register::<RenderMode>();
+    registry.register_with::<Mesh, _, _>(
+        |m| {
+            let origin = m.origin.ok_or(SceneError::CustomMesh)?;
+            Ok(serde_json::to_value(origin)?)
+        },
+        |v, assets| {
+            let origin: MeshOrigin = serde_json::from_value(v)?;
+            Ok(Mesh::from_origin(&assets.display, &origin)?)
+        },
+    );
+
+    let root = resource_path.to_path_buf();
+    let save_root = root.clone();
+    registry.register_with::<Material, _, _>(
+        move |m| {
+            let asset = MaterialAsset {
+                shaders: map_shaders(m.shaders(), |p| relative(&save_root, p)),
+                textures: map_textures(m.textures(), |p| relative(&save_root, p)),
+            };
+            Ok(serde_json::to_value(asset)?)
+        },
+        move |v, assets| {
+            let asset: MaterialAsset = serde_json::from_value(v)?;
+            let shaders = map_shaders(&asset.shaders, |p| root.join(p));
+            let textures = map_textures(&asset.textures, |p| root.join(p));
+            Ok(assets
+                .factory
+                .new_material(&assets.display, &shaders, &textures)?)
+        },
+    );
+    registry.register_dependent::<BoundingVolume, _, _>(
+        |b| Ok(serde_json::to_value(b)?),
+        |v, entities, entity, _| match serde_json::from_value(v)? {
+            BoundingVolumeAsset::Explicit(b) => Ok(b),
+            BoundingVolumeAsset::Derived(kind) => {
+                let mesh = entities.borrow_component::<Mesh>(entity)?;
+                let volume = match kind {
+                    BoundingVolumeKind::Sphere => BoundingVolume::from_mesh_sphere(mesh),
+                    BoundingVolumeKind::Aabb => BoundingVolume::from_mesh_aabb(mesh),
+                    BoundingVolumeKind::KDop8 => BoundingVolume::from_mesh_8dop(mesh),
+                };
+                Ok(volume?)
+            }
+        },
+    );
+    registry
+}
+
+/// Reads the prefab of the specified name from the `prefabs` directory of the resource tree.
+pub fn load_prefab(resource_path: &Path, name: &str) -> Result<Prefab, EcsError> {
+    Prefab::read(&resource_path.join("prefabs").join(format!("{}.json", name)))
+}
+
+/// Strips the resource directory from the specified path, if possible.
+fn relative(root: &Path, path: &Path) -> PathBuf {
+    path.strip_prefix(root).unwrap_or(path).to_path_buf()
+}
+
+/// Applies the function to all paths of the `ShaderGroup`.
+fn map_shaders<F: Fn(&Path) -> PathBuf>(shaders: &ShaderGroup, f: F) -> ShaderGroup {
+    ShaderGroup {
+        vertex: f(&shaders.vertex),
+        fragment: f(&shaders.fragment),
+        geometry: shaders.geometry.as_ref().map(|p| f(p)),
+    }
+}
+
+/// Applies the function to all paths of the `TextureGroup`.
+fn map_textures<F: Fn(&Path) -> PathBuf>(textures: &TextureGroup, f: F) -> TextureGroup {
+    TextureGroup {
+        diffuse: textures.diffuse.as_ref().map(|p| f(p)),
+        normal: textures.normal.as_ref().map(|p| f(p)),
+    }
+}
+
+/// Saving a scene may fail. `SceneError` describes those errors.
+#[derive(Debug, Fail)]
+pub enum SceneError {
+    #[fail(display = "Meshes with custom vertex data cannot be stored in a scene")] CustomMesh,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::Value;
+
+    #[test]
+    fn relative_asset_paths() {
+        let root = Path::new("/resources/rootspace");
+        let shaders = ShaderGroup {
+            vertex: root.join("shaders/test-vertex.glsl"),
+            fragment: PathBuf::from("/elsewhere/fragment.glsl"),
+            geometry: None,
+        };
+
+        let relative_shaders = map_shaders(&shaders, |p| relative(root, p));
+        assert_eq!(relative_shaders.vertex, Path::new("shaders/test-vertex.glsl"));
+        assert_eq!(relative_shaders.fragment, Path::new("/elsewhere/fragment.glsl"));
+        assert_eq!(map_shaders(&relative_shaders, |p| root.join(p)), shaders);
+
+        let value: Value = serde_json::to_value(MaterialAsset {
+            shaders: relative_shaders,
+            textures: TextureGroup::empty(),
+        }).unwrap();
+        assert_eq!(value["shaders"]["vertex"], "shaders/test-vertex.glsl");
+    }
+
+    #[test]
+    fn bounding_volume_assets() {
+        match serde_json::from_str(r#""Aabb""#).unwrap() {
+            BoundingVolumeAsset::Derived(BoundingVolumeKind::Aabb) => (),
+            b => panic!("Unexpected bounding volume {:?}", b),
+        }
+        let json = r#"{"Sphere": {"center": [0.0, 0.0, 0.0], "square_radius": 1.0}}"#;
+        match serde_json::from_str(json).unwrap() {
+            BoundingVolumeAsset::Explicit(BoundingVolume::Sphere { square_radius, .. }) => {
+                assert_eq!(square_radius, 1.0)
+            }
+            b => panic!("Unexpected bounding volume {:?}", b),
+        }
+    }
+}
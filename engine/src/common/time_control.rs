@@ -0,0 +1,142 @@
+use std::time::Duration;
+
+/// Specifies the largest factor by which the frame time may be scaled.
+pub const MAX_TIME_SCALE: f32 = 16.0;
+
+/// Controls how fast the simulation advances relative to the wall clock. The `Orchestrator`
+/// consults `TimeControl` once per frame: while paused, `World::update` is only called for
+/// explicitly requested steps, whereas dynamic updates and rendering continue regardless.
+#[derive(Debug, Clone)]
+pub struct TimeControl {
+    /// Holds the factor by which the frame time is scaled.
+    time_scale: f32,
+    /// If `true`, the simulation does not advance on its own.
+    paused: bool,
+    /// Holds the number of requested single steps that have not yet been performed.
+    pending_steps: u32,
+}
+
+impl Default for TimeControl {
+    fn default() -> Self {
+        TimeControl {
+            time_scale: 1.0,
+            paused: false,
+            pending_steps: 0,
+        }
+    }
+}
+
+impl TimeControl {
+    /// Creates a new `TimeControl` that runs the simulation at normal speed.
+    pub fn new() -> Self {
+        Default::default()
+    }
+    /// Returns the factor by which the frame time is scaled.
+    pub fn time_scale(&self) -> f32 {
+        self.time_scale
+    }
+    /// Sets the factor by which the frame time is scaled. The factor is clamped to the range
+    /// from zero to `MAX_TIME_SCALE`.
+    pub fn set_time_scale(&mut self, value: f32) {
+        self.time_scale = value.max(0.0).min(MAX_TIME_SCALE);
+    }
+    /// Returns `true` if the simulation is paused.
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+    /// Pauses or resumes the simulation. Resuming discards any pending steps.
+    pub fn set_paused(&mut self, value: bool) {
+        self.paused = value;
+        if !value {
+            self.pending_steps = 0;
+        }
+    }
+    /// Requests that the paused simulation advance by the specified number of fixed updates. The
+    /// number of pending steps saturates at `u32::MAX`.
+    pub fn request_steps(&mut self, steps: u32) {
+        self.pending_steps = self.pending_steps.saturating_add(steps);
+    }
+    /// Returns the amount of simulation time that elapses during a frame of the specified
+    /// duration, consuming any pending steps. At most `max_frame_time` elapses per frame (but at
+    /// least one step, if requested), such that the fixed updates always keep up. Steps beyond
+    /// that remain pending for the following frames.
+    pub fn simulated_time(
+        &mut self,
+        frame_time: Duration,
+        delta_time: Duration,
+        max_frame_time: Duration,
+    ) -> Duration {
+        if self.paused {
+            let max_steps = (nanos(&max_frame_time) / nanos(&delta_time).max(1)).max(1);
+            let steps = u64::from(self.pending_steps).min(max_steps) as u32;
+            self.pending_steps -= steps;
+            delta_time * steps
+        } else {
+            let scaled = (nanos(&frame_time) as f64 * f64::from(self.time_scale)).round() as u64;
+            let scaled = scaled.min(nanos(&max_frame_time));
+            Duration::new(scaled / 1_000_000_000, (scaled % 1_000_000_000) as u32)
+        }
+    }
+}
+
+/// Returns the specified `Duration` in nanoseconds.
+fn nanos(duration: &Duration) -> u64 {
+    duration.as_secs() * 1_000_000_000 + u64::from(duration.subsec_nanos())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn scaled_time() {
+        let dt = Duration::from_millis(50);
+        let max = Duration::from_secs(5);
+        let mut tc = TimeControl::new();
+        let frame = Duration::from_millis(40);
+        assert_eq!(tc.simulated_time(frame, dt, max), Duration::from_millis(40));
+
+        tc.set_time_scale(0.5);
+        let frame = Duration::from_secs(3);
+        assert_eq!(tc.simulated_time(frame, dt, max), Duration::from_millis(1500));
+
+        tc.set_time_scale(-1.0);
+        assert_eq!(tc.time_scale(), 0.0);
+    }
+
+    #[test]
+    fn bounded_time() {
+        let dt = Duration::from_millis(50);
+        let max = Duration::from_millis(250);
+        let frame = Duration::from_millis(40);
+        let mut tc = TimeControl::new();
+        tc.set_time_scale(1e9);
+        assert_eq!(tc.time_scale(), MAX_TIME_SCALE);
+        assert_eq!(tc.simulated_time(frame, dt, max), Duration::from_millis(250));
+    }
+
+    #[test]
+    fn paused_steps() {
+        let dt = Duration::from_millis(50);
+        let max = Duration::from_millis(250);
+        let frame = Duration::from_millis(40);
+        let mut tc = TimeControl::new();
+        tc.set_paused(true);
+        assert_eq!(tc.simulated_time(frame, dt, max), Duration::new(0, 0));
+
+        tc.request_steps(2);
+        assert_eq!(tc.simulated_time(frame, dt, max), Duration::from_millis(100));
+        assert_eq!(tc.simulated_time(frame, dt, max), Duration::new(0, 0));
+
+        tc.request_steps(7);
+        assert_eq!(tc.simulated_time(frame, dt, max), Duration::from_millis(250));
+        assert_eq!(tc.simulated_time(frame, dt, max), Duration::from_millis(100));
+
+        tc.request_steps(u32::MAX);
+        tc.request_steps(1);
+        assert_eq!(tc.pending_steps, u32::MAX);
+
+        tc.set_paused(false);
+        assert_eq!(tc.simulated_time(frame, dt, max), Duration::from_millis(40));
+    }
+}
@@ -1,16 +1,24 @@
 //! The `bounding_volume` module provides access to the `BoundingVolume` component.
 
+use std::cmp::Ordering;
 use std::f32;
 use std::mem;
-use nalgebra::{Point3, Vector3};
+use nalgebra::{Matrix3, Point3, Vector3};
 use glium::buffer::ReadError;
 use components::mesh::Mesh;
+use components::model::Model;
+use common::affine_transform::AffineTransform;
+use common::bvh::Bounds;
 use common::vertex::Vertex;
 use common::ray::Ray;
 
+/// Specifies the tolerance with which points are considered to lie within a k-DOP.
+const KDOP_TOLERANCE: f32 = 1.0e-4;
+
 /// The `BoundingVolume` component describes simplified volumes of entities or objects to use for
 /// collision detection.
-#[derive(Debug, Clone, Component)]
+#[derive(Debug, Clone, Serialize, Deserialize, Component)]
+#[component(name = "bounding_volume", requires(Model))]
 pub enum BoundingVolume {
     /// Defines a spherical bounding volume.
     Sphere {
@@ -166,6 +174,24 @@ impl BoundingVolume {
         let vertex_data = mesh.vertices.read()?;
         Ok(Self::new_8dop(&vertex_data))
     }
+    /// Returns the smallest axis-aligned `Bounds` that contain the `BoundingVolume`.
+    pub fn bounds(&self) -> Bounds {
+        match *self {
+            BoundingVolume::Sphere {
+                ref center,
+                square_radius,
+            } => {
+                let r = square_radius.sqrt();
+                let extents = Vector3::new(r, r, r);
+                Bounds::new(center - extents, center + extents)
+            }
+            BoundingVolume::Aabb {
+                ref center,
+                ref extents,
+            } => Bounds::new(center - extents, center + extents),
+            BoundingVolume::KDop(ref slabs) => Bounds::from_points(&kdop_vertices(slabs)),
+        }
+    }
     /// Performs an intersection test of the `BoundingVolume` against the supplied `Ray`.
     /// Optionally returns a tuple of `Ray` position and intersection point.
     pub fn intersect_ray(&self, ray: &Ray<f32>) -> Option<(f32, Point3<f32>)> {
@@ -239,14 +265,383 @@ impl BoundingVolume {
                     Some((t_max, ray.at(t_max)))
                 }
             }
-            _ => unimplemented!(),
+            BoundingVolume::KDop(ref slabs) => {
+                let epsilon = 0.001;
+                let mut t_min = -f32::INFINITY;
+                let mut t_max = f32::INFINITY;
+                for &(ref n, min, max) in slabs {
+                    let e = n.dot(&ray.origin.coords);
+                    let f = n.dot(ray.direction.as_ref());
+                    if f.abs() > epsilon {
+                        let mut t_1 = (min - e) / f;
+                        let mut t_2 = (max - e) / f;
+
+                        if t_1 > t_2 {
+                            mem::swap(&mut t_1, &mut t_2);
+                        }
+
+                        if t_1 > t_min {
+                            t_min = t_1;
+                        }
+
+                        if t_2 < t_max {
+                            t_max = t_2;
+                        }
+
+                        if t_min > t_max || t_max < 0.0 {
+                            return None;
+                        }
+                    } else if e < min || e > max {
+                        return None;
+                    }
+                }
+
+                if t_min > 0.0 {
+                    Some((t_min, ray.at(t_min)))
+                } else {
+                    Some((t_max, ray.at(t_max)))
+                }
+            }
+        }
+    }
+    /// Determines whether the `BoundingVolume` overlaps with another one. Volumes that merely
+    /// touch are considered overlapping.
+    pub fn intersects(&self, other: &BoundingVolume) -> bool {
+        match (self, other) {
+            (
+                &BoundingVolume::Sphere {
+                    center: ref c_1,
+                    square_radius: r_1,
+                },
+                &BoundingVolume::Sphere {
+                    center: ref c_2,
+                    square_radius: r_2,
+                },
+            ) => {
+                let d = c_2 - c_1;
+                let r = r_1.sqrt() + r_2.sqrt();
+                d.dot(&d) <= r * r
+            }
+            (
+                &BoundingVolume::Aabb {
+                    center: ref c_1,
+                    extents: ref e_1,
+                },
+                &BoundingVolume::Aabb {
+                    center: ref c_2,
+                    extents: ref e_2,
+                },
+            ) => (0..3).all(|i| (c_2[i] - c_1[i]).abs() <= e_1[i] + e_2[i]),
+            (
+                &BoundingVolume::Sphere {
+                    ref center,
+                    square_radius,
+                },
+                &BoundingVolume::Aabb {
+                    center: ref c,
+                    extents: ref e,
+                },
+            )
+            | (
+                &BoundingVolume::Aabb {
+                    center: ref c,
+                    extents: ref e,
+                },
+                &BoundingVolume::Sphere {
+                    ref center,
+                    square_radius,
+                },
+            ) => {
+                // Find the point within the AABB closest to the sphere center.
+                let d = center - c;
+                let closest = Vector3::new(
+                    d.x.max(-e.x).min(e.x),
+                    d.y.max(-e.y).min(e.y),
+                    d.z.max(-e.z).min(e.z),
+                );
+                let r = d - closest;
+                r.dot(&r) <= square_radius
+            }
+            (
+                &BoundingVolume::Sphere {
+                    ref center,
+                    square_radius,
+                },
+                &BoundingVolume::KDop(ref slabs),
+            )
+            | (
+                &BoundingVolume::KDop(ref slabs),
+                &BoundingVolume::Sphere {
+                    ref center,
+                    square_radius,
+                },
+            ) => kdop_closest_point(slabs, center)
+                .map(|p| {
+                    let r = p - center;
+                    r.dot(&r) <= square_radius + KDOP_TOLERANCE
+                })
+                .unwrap_or(false),
+            _ => {
+                // Both volumes are convex polytopes, thus they overlap if the polytope bounded
+                // by the slabs of both has at least one vertex.
+                let mut slabs = self.slabs().unwrap_or_default();
+                slabs.extend(other.slabs().unwrap_or_default());
+                !kdop_vertices(&slabs).is_empty()
+            }
+        }
+    }
+    /// Returns a `BoundingVolume` of the same kind that encloses both this and the other volume.
+    pub fn merge(&self, other: &BoundingVolume) -> BoundingVolume {
+        match *self {
+            BoundingVolume::Sphere { .. } => {
+                let (c_1, r_1) = self.bounding_sphere();
+                let (c_2, r_2) = other.bounding_sphere();
+                let d = c_2 - c_1;
+                let distance = d.norm();
+
+                if distance + r_2 <= r_1 {
+                    // The other sphere lies within this one.
+                    BoundingVolume::Sphere {
+                        center: c_1,
+                        square_radius: r_1 * r_1,
+                    }
+                } else if distance + r_1 <= r_2 {
+                    // This sphere lies within the other one.
+                    BoundingVolume::Sphere {
+                        center: c_2,
+                        square_radius: r_2 * r_2,
+                    }
+                } else {
+                    let radius = (distance + r_1 + r_2) / 2.0;
+                    BoundingVolume::Sphere {
+                        center: c_1 + d * ((radius - r_1) / distance),
+                        square_radius: radius * radius,
+                    }
+                }
+            }
+            BoundingVolume::Aabb { .. } => {
+                Self::from_bounds(&self.bounds().merge(&other.bounds()))
+            }
+            BoundingVolume::KDop(ref slabs) => BoundingVolume::KDop(
+                slabs
+                    .iter()
+                    .map(|&(n, min, max)| {
+                        let (other_min, other_max) = other.project(&n);
+                        (n, min.min(other_min), max.max(other_max))
+                    })
+                    .collect(),
+            ),
+        }
+    }
+    /// Returns a `BoundingVolume` of the same kind that encloses this volume after the
+    /// transformation. Bounding boxes and k-DOPs retain their axes, thus they enclose the rotated
+    /// volume rather than rotate along with it.
+    pub fn transform(&self, transform: &AffineTransform<f32>) -> BoundingVolume {
+        match *self {
+            BoundingVolume::Sphere {
+                ref center,
+                square_radius,
+            } => {
+                let s = transform.scale;
+                let max_scale = s.x.abs().max(s.y.abs()).max(s.z.abs());
+                BoundingVolume::Sphere {
+                    center: transform.transform_point(center),
+                    square_radius: square_radius * max_scale * max_scale,
+                }
+            }
+            BoundingVolume::Aabb { .. } => Self::from_bounds(&self.bounds().transform(transform)),
+            BoundingVolume::KDop(ref slabs) => {
+                let vertices = kdop_vertices(slabs)
+                    .iter()
+                    .map(|v| transform.transform_point(v))
+                    .collect::<Vec<_>>();
+
+                BoundingVolume::KDop(
+                    slabs
+                        .iter()
+                        .map(|&(n, _, _)| {
+                            let (min, max) = vertices.iter().fold(
+                                (f32::INFINITY, -f32::INFINITY),
+                                |(min, max), v| {
+                                    let d = n.dot(&v.coords);
+                                    (min.min(d), max.max(d))
+                                },
+                            );
+                            (n, min, max)
+                        })
+                        .collect(),
+                )
+            }
+        }
+    }
+    /// Creates an axis-aligned bounding box from the supplied `Bounds`.
+    fn from_bounds(bounds: &Bounds) -> Self {
+        let center = Point3::from_coordinates((bounds.min.coords + bounds.max.coords) / 2.0);
+        BoundingVolume::Aabb {
+            center,
+            extents: bounds.max - center,
+        }
+    }
+    /// Returns the center and radius of a sphere that encloses the `BoundingVolume`.
+    fn bounding_sphere(&self) -> (Point3<f32>, f32) {
+        match *self {
+            BoundingVolume::Sphere {
+                center,
+                square_radius,
+            } => (center, square_radius.sqrt()),
+            BoundingVolume::Aabb { center, extents } => (center, extents.norm()),
+            BoundingVolume::KDop(ref slabs) => {
+                let vertices = kdop_vertices(slabs);
+                let bounds = Bounds::from_points(&vertices);
+                let center =
+                    Point3::from_coordinates((bounds.min.coords + bounds.max.coords) / 2.0);
+                let radius = vertices
+                    .iter()
+                    .map(|v| (v - center).norm())
+                    .fold(0.0, f32::max);
+                (center, radius)
+            }
+        }
+    }
+    /// Returns the interval covered by the `BoundingVolume` when projected onto the specified
+    /// axis.
+    fn project(&self, axis: &Vector3<f32>) -> (f32, f32) {
+        match *self {
+            BoundingVolume::Sphere {
+                ref center,
+                square_radius,
+            } => {
+                let d = axis.dot(&center.coords);
+                let r = square_radius.sqrt() * axis.norm();
+                (d - r, d + r)
+            }
+            BoundingVolume::Aabb {
+                ref center,
+                ref extents,
+            } => {
+                let d = axis.dot(&center.coords);
+                let r = axis.x.abs() * extents.x + axis.y.abs() * extents.y
+                    + axis.z.abs() * extents.z;
+                (d - r, d + r)
+            }
+            BoundingVolume::KDop(ref slabs) => kdop_vertices(slabs).iter().fold(
+                (f32::INFINITY, -f32::INFINITY),
+                |(min, max), v| {
+                    let d = axis.dot(&v.coords);
+                    (min.min(d), max.max(d))
+                },
+            ),
+        }
+    }
+    /// Returns the slabs that bound the `BoundingVolume`, unless it is a sphere.
+    fn slabs(&self) -> Option<Vec<(Vector3<f32>, f32, f32)>> {
+        match *self {
+            BoundingVolume::Sphere { .. } => None,
+            BoundingVolume::Aabb {
+                ref center,
+                ref extents,
+            } => Some(vec![
+                (Vector3::x(), center.x - extents.x, center.x + extents.x),
+                (Vector3::y(), center.y - extents.y, center.y + extents.y),
+                (Vector3::z(), center.z - extents.z, center.z + extents.z),
+            ]),
+            BoundingVolume::KDop(ref slabs) => Some(slabs.clone()),
+        }
+    }
+}
+
+/// Converts the slabs of a k-DOP to the half-spaces `n * p <= d` that bound it.
+fn kdop_planes(slabs: &[(Vector3<f32>, f32, f32)]) -> Vec<(Vector3<f32>, f32)> {
+    slabs
+        .iter()
+        .flat_map(|&(n, min, max)| vec![(n, max), (-n, -min)])
+        .collect()
+}
+
+/// Determines whether the point lies within all of the specified half-spaces.
+fn within_planes(planes: &[(Vector3<f32>, f32)], p: &Vector3<f32>) -> bool {
+    planes
+        .iter()
+        .all(|&(ref n, d)| n.dot(p) <= d + KDOP_TOLERANCE)
+}
+
+/// Determines the vertices of a k-DOP by intersecting each triple of its bounding planes and
+/// retaining the points that lie within all slabs.
+fn kdop_vertices(slabs: &[(Vector3<f32>, f32, f32)]) -> Vec<Point3<f32>> {
+    let planes = kdop_planes(slabs);
+
+    let mut vertices = Vec::new();
+    for (i, a) in planes.iter().enumerate() {
+        for (j, b) in planes.iter().enumerate().skip(i + 1) {
+            for c in planes.iter().skip(j + 1) {
+                let normals = Matrix3::new(
+                    a.0.x, a.0.y, a.0.z, b.0.x, b.0.y, b.0.z, c.0.x, c.0.y, c.0.z,
+                );
+                if let Some(inverse) = normals.try_inverse() {
+                    let p = inverse * Vector3::new(a.1, b.1, c.1);
+                    if within_planes(&planes, &p) {
+                        vertices.push(Point3::from_coordinates(p));
+                    }
+                }
+            }
         }
     }
+    vertices
+}
+
+/// Returns the point of a k-DOP closest to the specified point, or `None` if the k-DOP is empty.
+/// The closest point is the projection of the point onto either the k-DOP itself, one of its
+/// faces, one of its edges, or one of its vertices, whichever is closest.
+fn kdop_closest_point(
+    slabs: &[(Vector3<f32>, f32, f32)],
+    point: &Point3<f32>,
+) -> Option<Point3<f32>> {
+    let planes = kdop_planes(slabs);
+    let p = point.coords;
+    if within_planes(&planes, &p) {
+        return Some(*point);
+    }
+
+    let mut candidates = kdop_vertices(slabs)
+        .into_iter()
+        .map(|v| v.coords)
+        .collect::<Vec<_>>();
+
+    for (i, &(n_1, d_1)) in planes.iter().enumerate() {
+        // Project the point onto the face.
+        let q = p - n_1 * ((n_1.dot(&p) - d_1) / n_1.dot(&n_1));
+        if within_planes(&planes, &q) {
+            candidates.push(q);
+        }
+
+        // Project the point onto the edges, i.e. onto the lines where two planes meet.
+        for &(n_2, d_2) in planes.iter().skip(i + 1) {
+            let (a, b, c) = (n_1.dot(&n_1), n_1.dot(&n_2), n_2.dot(&n_2));
+            let det = a * c - b * b;
+            if det.abs() > KDOP_TOLERANCE {
+                let e_1 = d_1 - n_1.dot(&p);
+                let e_2 = d_2 - n_2.dot(&p);
+                let alpha = (e_1 * c - e_2 * b) / det;
+                let beta = (e_2 * a - e_1 * b) / det;
+                let q = p + n_1 * alpha + n_2 * beta;
+                if within_planes(&planes, &q) {
+                    candidates.push(q);
+                }
+            }
+        }
+    }
+
+    candidates
+        .into_iter()
+        .map(|q| ((q - p).norm_squared(), q))
+        .min_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal))
+        .map(|(_, q)| Point3::from_coordinates(q))
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use nalgebra::{Translation3, UnitQuaternion};
 
     #[test]
     fn test_sphere() {
@@ -322,4 +717,236 @@ mod test {
             bv => panic!("Expected a k-DOP enum variant, got {:?} instead", bv),
         }
     }
+    #[test]
+    fn test_bounds() {
+        let sphere = BoundingVolume::Sphere {
+            center: Point3::new(1.0, 0.0, 0.0),
+            square_radius: 4.0,
+        };
+        let b = sphere.bounds();
+        assert!(b.min == Point3::new(-1.0, -2.0, -2.0), "Got {:?} instead", b.min);
+        assert!(b.max == Point3::new(3.0, 2.0, 2.0), "Got {:?} instead", b.max);
+
+        let aabb = BoundingVolume::Aabb {
+            center: Point3::new(0.0, 1.0, 0.0),
+            extents: Vector3::new(0.5, 0.5, 0.0),
+        };
+        let b = aabb.bounds();
+        assert!(b.min == Point3::new(-0.5, 0.5, 0.0), "Got {:?} instead", b.min);
+        assert!(b.max == Point3::new(0.5, 1.5, 0.0), "Got {:?} instead", b.max);
+
+        // The four diagonal slabs of a unit cube's 8-DOP enclose an octahedron-like polytope
+        // that reaches beyond the cube itself.
+        let s = 1.5 / 3.0f32.sqrt();
+        let kdop = BoundingVolume::KDop(vec![
+            (Vector3::new(1.0, 1.0, 1.0).normalize(), -s, s),
+            (Vector3::new(1.0, 1.0, -1.0).normalize(), -s, s),
+            (Vector3::new(1.0, -1.0, 1.0).normalize(), -s, s),
+            (Vector3::new(-1.0, 1.0, 1.0).normalize(), -s, s),
+        ]);
+        let b = kdop.bounds();
+        for i in 0..3 {
+            assert!((b.min[i] + 1.5).abs() < 1.0e-4, "Got {:?} instead", b.min);
+            assert!((b.max[i] - 1.5).abs() < 1.0e-4, "Got {:?} instead", b.max);
+        }
+    }
+    #[test]
+    fn test_kdop_ray() {
+        let cube = BoundingVolume::KDop(vec![
+            (Vector3::x(), -0.5, 0.5),
+            (Vector3::y(), -0.5, 0.5),
+            (Vector3::z(), -0.5, 0.5),
+        ]);
+        let ray = Ray::new(Point3::new(-5.0, 0.0, 0.0), Vector3::x_axis());
+        match cube.intersect_ray(&ray) {
+            Some((t, p)) => {
+                assert!(t == 4.5, "Got {:?} instead", t);
+                assert!(p == Point3::new(-0.5, 0.0, 0.0), "Got {:?} instead", p);
+            }
+            None => panic!("Expected the ray to hit the k-DOP"),
+        }
+
+        // Rays originating within the k-DOP report the exit point.
+        let ray = Ray::new(Point3::origin(), Vector3::x_axis());
+        let hit = cube.intersect_ray(&ray);
+        assert!(hit == Some((0.5, Point3::new(0.5, 0.0, 0.0))), "Got {:?} instead", hit);
+
+        let ray = Ray::new(Point3::new(-5.0, 2.0, 0.0), Vector3::x_axis());
+        assert!(cube.intersect_ray(&ray).is_none());
+        let ray = Ray::new(Point3::new(5.0, 0.0, 0.0), Vector3::x_axis());
+        assert!(cube.intersect_ray(&ray).is_none());
+
+        let s = 1.5 / 3.0f32.sqrt();
+        let kdop = BoundingVolume::KDop(vec![
+            (Vector3::new(1.0, 1.0, 1.0).normalize(), -s, s),
+            (Vector3::new(1.0, 1.0, -1.0).normalize(), -s, s),
+            (Vector3::new(1.0, -1.0, 1.0).normalize(), -s, s),
+            (Vector3::new(-1.0, 1.0, 1.0).normalize(), -s, s),
+        ]);
+        let ray = Ray::new(Point3::new(-5.0, 0.0, 0.0), Vector3::x_axis());
+        match kdop.intersect_ray(&ray) {
+            Some((t, _)) => assert!((t - 3.5).abs() < 1.0e-4, "Got {:?} instead", t),
+            None => panic!("Expected the ray to hit the k-DOP"),
+        }
+        let ray = Ray::new(Point3::new(-5.0, 1.0, 1.0), Vector3::x_axis());
+        assert!(kdop.intersect_ray(&ray).is_none());
+    }
+    #[test]
+    fn test_intersects() {
+        let sphere = |x: f32, y: f32, z: f32, square_radius: f32| BoundingVolume::Sphere {
+            center: Point3::new(x, y, z),
+            square_radius,
+        };
+        let aabb = |x: f32, y: f32, z: f32| BoundingVolume::Aabb {
+            center: Point3::new(x, y, z),
+            extents: Vector3::new(0.5, 0.5, 0.5),
+        };
+        let cube = |x: f32| {
+            BoundingVolume::KDop(vec![
+                (Vector3::x(), x - 0.5, x + 0.5),
+                (Vector3::y(), -0.5, 0.5),
+                (Vector3::z(), -0.5, 0.5),
+            ])
+        };
+        let s = 1.5 / 3.0f32.sqrt();
+        let octahedron = BoundingVolume::KDop(vec![
+            (Vector3::new(1.0, 1.0, 1.0).normalize(), -s, s),
+            (Vector3::new(1.0, 1.0, -1.0).normalize(), -s, s),
+            (Vector3::new(1.0, -1.0, 1.0).normalize(), -s, s),
+            (Vector3::new(-1.0, 1.0, 1.0).normalize(), -s, s),
+        ]);
+
+        assert!(sphere(0.0, 0.0, 0.0, 1.0).intersects(&sphere(1.9, 0.0, 0.0, 1.0)));
+        assert!(!sphere(0.0, 0.0, 0.0, 1.0).intersects(&sphere(2.1, 0.0, 0.0, 1.0)));
+
+        assert!(aabb(0.0, 0.0, 0.0).intersects(&aabb(0.9, 0.9, 0.0)));
+        assert!(!aabb(0.0, 0.0, 0.0).intersects(&aabb(0.9, 1.1, 0.0)));
+
+        // Near the corner of an AABB, the sphere must reach the corner itself.
+        assert!(aabb(0.0, 0.0, 0.0).intersects(&sphere(1.0, 1.0, 0.0, 0.6)));
+        assert!(!sphere(1.0, 1.0, 0.0, 0.4).intersects(&aabb(0.0, 0.0, 0.0)));
+
+        assert!(cube(0.0).intersects(&cube(0.9)));
+        assert!(!cube(0.0).intersects(&cube(1.1)));
+        assert!(cube(0.9).intersects(&aabb(0.0, 0.0, 0.0)));
+        assert!(!aabb(0.0, 0.0, 0.0).intersects(&cube(1.1)));
+        assert!(octahedron.intersects(&cube(1.9)));
+        assert!(!octahedron.intersects(&aabb(1.1, 1.1, 1.1)));
+
+        // The sphere touches either a vertex or a face of the octahedron.
+        assert!(octahedron.intersects(&sphere(2.0, 0.0, 0.0, 0.36)));
+        assert!(!octahedron.intersects(&sphere(2.0, 0.0, 0.0, 0.16)));
+        assert!(sphere(1.0, 1.0, 1.0, 0.8).intersects(&octahedron));
+        assert!(!sphere(1.0, 1.0, 1.0, 0.7).intersects(&octahedron));
+        assert!(sphere(0.0, 0.0, 0.0, 0.01).intersects(&octahedron));
+    }
+    #[test]
+    fn test_merge() {
+        let a = BoundingVolume::Sphere {
+            center: Point3::origin(),
+            square_radius: 1.0,
+        };
+        let b = BoundingVolume::Sphere {
+            center: Point3::new(4.0, 0.0, 0.0),
+            square_radius: 1.0,
+        };
+        match a.merge(&b) {
+            BoundingVolume::Sphere {
+                center: c,
+                square_radius: r,
+            } => {
+                assert!(c == Point3::new(2.0, 0.0, 0.0), "Got {:?} instead", c);
+                assert!(r == 9.0, "Got {:?} instead", r);
+            }
+            bv => panic!("Expected a sphere enum variant, got {:?} instead", bv),
+        }
+
+        let c = BoundingVolume::Aabb {
+            center: Point3::origin(),
+            extents: Vector3::new(0.5, 0.5, 0.5),
+        };
+        match c.merge(&b) {
+            BoundingVolume::Aabb {
+                center: c,
+                extents: e,
+            } => {
+                assert!(c == Point3::new(2.25, 0.0, 0.0), "Got {:?} instead", c);
+                assert!(e == Vector3::new(2.75, 1.0, 1.0), "Got {:?} instead", e);
+            }
+            bv => panic!("Expected an AABB enum variant, got {:?} instead", bv),
+        }
+
+        let d = BoundingVolume::KDop(vec![
+            (Vector3::x(), -0.5, 0.5),
+            (Vector3::y(), -0.5, 0.5),
+            (Vector3::z(), -0.5, 0.5),
+        ]);
+        match d.merge(&b) {
+            BoundingVolume::KDop(d) => {
+                assert!(d.len() == 3, "Got {:?} instead", d.len());
+                assert!(d[0] == (Vector3::x(), -0.5, 5.0), "Got {:?} instead", d[0]);
+                assert!(d[1] == (Vector3::y(), -1.0, 1.0), "Got {:?} instead", d[1]);
+                assert!(d[2] == (Vector3::z(), -1.0, 1.0), "Got {:?} instead", d[2]);
+            }
+            bv => panic!("Expected a k-DOP enum variant, got {:?} instead", bv),
+        }
+    }
+    #[test]
+    fn test_transform() {
+        let t = AffineTransform::from_parts(
+            Translation3::new(1.0, 2.0, 3.0),
+            UnitQuaternion::from_axis_angle(&Vector3::z_axis(), f32::consts::FRAC_PI_4),
+            Vector3::new(1.0, 2.0, 1.0),
+        );
+
+        let sphere = BoundingVolume::Sphere {
+            center: Point3::origin(),
+            square_radius: 1.0,
+        };
+        match sphere.transform(&t) {
+            BoundingVolume::Sphere {
+                center: c,
+                square_radius: r,
+            } => {
+                assert!(c == Point3::new(1.0, 2.0, 3.0), "Got {:?} instead", c);
+                assert!(r == 4.0, "Got {:?} instead", r);
+            }
+            bv => panic!("Expected a sphere enum variant, got {:?} instead", bv),
+        }
+
+        // A unit cube scaled along y and rotated by 45 degrees spans 1.5 / sqrt(2) along x and y.
+        let e = 1.5 / 2.0f32.sqrt();
+        let aabb = BoundingVolume::Aabb {
+            center: Point3::origin(),
+            extents: Vector3::new(0.5, 0.5, 0.5),
+        };
+        match aabb.transform(&t) {
+            BoundingVolume::Aabb {
+                center: c,
+                extents: r,
+            } => {
+                assert!((c - Point3::new(1.0, 2.0, 3.0)).norm() < 1.0e-4, "Got {:?} instead", c);
+                assert!((r - Vector3::new(e, e, 0.5)).norm() < 1.0e-4, "Got {:?} instead", r);
+            }
+            bv => panic!("Expected an AABB enum variant, got {:?} instead", bv),
+        }
+
+        let kdop = BoundingVolume::KDop(vec![
+            (Vector3::x(), -0.5, 0.5),
+            (Vector3::y(), -0.5, 0.5),
+            (Vector3::z(), -0.5, 0.5),
+        ]);
+        match kdop.transform(&t) {
+            BoundingVolume::KDop(d) => {
+                assert!(d.len() == 3, "Got {:?} instead", d.len());
+                assert!((d[0].1 - 1.0 + e).abs() < 1.0e-4, "Got {:?} instead", d[0]);
+                assert!((d[0].2 - 1.0 - e).abs() < 1.0e-4, "Got {:?} instead", d[0]);
+                assert!((d[1].1 - 2.0 + e).abs() < 1.0e-4, "Got {:?} instead", d[1]);
+                assert!((d[1].2 - 2.0 - e).abs() < 1.0e-4, "Got {:?} instead", d[1]);
+                assert!((d[2].1 - 2.5).abs() < 1.0e-4, "Got {:?} instead", d[2]);
+                assert!((d[2].2 - 3.5).abs() < 1.0e-4, "Got {:?} instead", d[2]);
+            }
+            bv => panic!("Expected a k-DOP enum variant, got {:?} instead", bv),
+        }
+    }
 }
@@ -7,6 +7,7 @@ use common::ray::Ray;
 
 /// The `Camera` encapsulates functionality necessary to provide a camera to the `Renderer`.
 #[derive(Serialize, Deserialize, Component)]
+#[component(storage = "sparse", name = "camera")]
 pub struct Camera {
     /// Provides access to the viewport dimensions.
     pub dimensions: [u32; 2],
@@ -4,6 +4,7 @@ use glium::glutin::{ElementState, MouseButton};
 
 /// The `Cursor` stores information about the pointing device in use by the operating system.
 #[derive(Component)]
+#[component(storage = "sparse", name = "cursor")]
 pub struct Cursor {
     /// Holds the current position of the `Cursor` in screen-space coordinates (pixel values).
     pub position: Point2<u32>,
@@ -2,6 +2,7 @@
 
 /// The `Description` component encodes information that describes a particular entity.
 #[derive(Clone, Serialize, Deserialize, Component)]
+#[component(name = "description")]
 pub struct Description {
     /// Holds the name of the connected entity or object.
     pub name: String,
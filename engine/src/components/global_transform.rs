@@ -0,0 +1,10 @@
+//! The `global_transform` module provides access to the `GlobalTransform` component.
+
+use components::model::Model;
+
+/// The `GlobalTransform` component holds the world-space `Model` of an entity, that is the
+/// product of its own (local) `Model` and those of all its ancestors in the scene graph. It is
+/// maintained by the `TransformPropagator` and should not be modified otherwise.
+#[derive(Clone, Component)]
+#[component(name = "global_transform")]
+pub struct GlobalTransform(pub Model);
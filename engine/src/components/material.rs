@@ -9,6 +9,7 @@ use common::resource_group::{ShaderGroup, TextureGroup};
 
 /// The `Material` represents an abstraction of a real-world material of an object.
 #[derive(Clone, Component)]
+#[component(name = "material")]
 pub struct Material {
     /// Provides access to the shader program.
     pub shader: Rc<Program>,
@@ -61,6 +62,14 @@ impl Material {
             texture_origins: textures,
         })
     }
+    /// Returns the shader files the `Material` was created from.
+    pub fn shaders(&self) -> &ShaderGroup {
+        &self.shader_origins
+    }
+    /// Returns the texture files the `Material` was created from.
+    pub fn textures(&self) -> &TextureGroup {
+        &self.texture_origins
+    }
 }
 
 /// Operations with `Material` may fail. `MaterialError` describes those errors.
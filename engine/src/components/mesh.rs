@@ -10,6 +10,7 @@ use common::vertex::Vertex;
 /// The `Mesh` encapsulates a vertex and an index buffer. In concert, they specify all vertices of
 /// a 3D object.
 #[derive(Component)]
+#[component(name = "mesh")]
 pub struct Mesh {
     /// Holds the vertex buffer type
     pub buffer_type: BufferType,
@@ -17,6 +18,8 @@ pub struct Mesh {
     pub vertices: VertexBuffer<Vertex>,
     /// Holds the index buffer object.
     pub indices: IndexBuffer<u16>,
+    /// Identifies the builtin shape the `Mesh` was created from, if any.
+    pub origin: Option<MeshOrigin>,
 }
 
 impl Mesh {
@@ -46,6 +49,7 @@ impl Mesh {
             buffer_type: buffer_type,
             vertices: vertices,
             indices: indices,
+            origin: None,
         })
     }
     /// Creates a new unit square with static buffers.
@@ -60,13 +64,17 @@ impl Mesh {
         ];
         let indices = [0, 1, 2, 2, 3, 0];
 
-        Self::new(
+        let mesh = Self::new(
             display,
             &vertices,
             &indices,
             index::PrimitiveType::TrianglesList,
             BufferType::Static,
-        )
+        )?;
+        Ok(Mesh {
+            origin: Some(MeshOrigin::Quad),
+            ..mesh
+        })
     }
     /// Creates a new unit cube with static buffers.
     pub fn new_cube(display: &Display) -> Result<Self, MeshError> {
@@ -109,13 +117,24 @@ impl Mesh {
             17, 18, 18, 19, 16, 20, 21, 22, 22, 23, 20,
         ];
 
-        Self::new(
+        let mesh = Self::new(
             display,
             &vertices,
             &indices,
             index::PrimitiveType::TrianglesList,
             BufferType::Static,
-        )
+        )?;
+        Ok(Mesh {
+            origin: Some(MeshOrigin::Cube),
+            ..mesh
+        })
+    }
+    /// Recreates a `Mesh` from the specified builtin shape.
+    pub fn from_origin(display: &Display, origin: &MeshOrigin) -> Result<Self, MeshError> {
+        match *origin {
+            MeshOrigin::Quad => Self::new_quad(display),
+            MeshOrigin::Cube => Self::new_cube(display),
+        }
     }
     pub fn update(&mut self, vertices: &[Vertex], indices: &[u16]) {
         if self.buffer_type != BufferType::Dynamic {
@@ -147,6 +166,14 @@ impl Mesh {
     }
 }
 
+/// Identifies the builtin shapes that a `Mesh` may be recreated from. Meshes with custom vertex
+/// data have no origin and cannot be stored in scene files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MeshOrigin {
+    Quad,
+    Cube,
+}
+
 /// Determines the type of buffer used by the `Mesh`.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum BufferType {
@@ -6,6 +6,8 @@ pub mod description;
 pub mod tooltip;
 pub mod camera;
 pub mod model;
+pub mod global_transform;
+pub mod previous_model;
 pub mod material;
 pub mod mesh;
 pub mod ui_state;
@@ -1,11 +1,13 @@
 //! The `model` module provides access to the `Model` component.
 
 use std::ops::Mul;
-use nalgebra::{Affine3, Isometry3, Matrix4, UnitQuaternion, Vector3};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use nalgebra::{Affine3, Isometry3, Matrix4, Translation3, UnitQuaternion, Vector3};
 use common::affine_transform::AffineTransform;
 
 /// `Model` provides an abstraction for the model matrix for each 3D object.
-#[derive(Clone, Serialize, Deserialize, Component)]
+#[derive(Clone, Component)]
+#[component(name = "model")]
 pub struct Model {
     /// Provides access to the model matrix (an affine matrix).
     inner: Affine3<f32>,
@@ -97,6 +99,25 @@ impl Model {
             self.inner = self.decomp.into();
         }
     }
+    /// Interpolates between this and the specified `Model`, where an `alpha` of zero yields this
+    /// `Model` and an `alpha` of one yields the other. Translation and scale are interpolated
+    /// linearly, the rotation spherically.
+    pub fn interpolate(&self, other: &Model, alpha: f32) -> Model {
+        let (a, b) = (&self.decomp, &other.decomp);
+        let translation =
+            a.translation.vector + (b.translation.vector - a.translation.vector) * alpha;
+        let rotation = a.rotation
+            .try_slerp(&b.rotation, alpha, 1.0e-6)
+            .unwrap_or(b.rotation);
+        let scale = a.scale + (b.scale - a.scale) * alpha;
+
+        let decomp =
+            AffineTransform::from_parts(Translation3::from_vector(translation), rotation, scale);
+        Model {
+            inner: decomp.into(),
+            decomp,
+        }
+    }
 }
 
 impl<'a> Mul for &'a Model {
@@ -111,9 +132,30 @@ impl<'a> Mul for &'a Model {
     }
 }
 
+impl Serialize for Model {
+    /// Serializes only the decomposed form of the model matrix, such that scene files remain
+    /// editable by hand.
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.decomp.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Model {
+    /// Deserializes the decomposed form of the model matrix and recalculates the compound affine
+    /// matrix.
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let decomp = AffineTransform::deserialize(deserializer)?;
+        Ok(Model {
+            inner: decomp.into(),
+            decomp,
+        })
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
+    use serde_json;
 
     #[test]
     fn test_getters_and_setters() {
@@ -131,4 +173,38 @@ mod test {
         model.set_scale(scale.component_mul(&Vector3::new(2.0, 3.0, 4.0)));
         assert_eq!(model.scale(), &Vector3::new(2.0, 3.0, 4.0));
     }
+
+    #[test]
+    fn test_interpolate() {
+        let a = Model::identity();
+        let b = Model::new(
+            Vector3::new(2.0, 4.0, 6.0),
+            Vector3::new(0.0, 0.0, 1.0),
+            Vector3::new(3.0, 3.0, 3.0),
+        );
+
+        let m = a.interpolate(&b, 0.5);
+        assert_eq!(m.translation(), &Vector3::new(1.0, 2.0, 3.0));
+        assert_eq!(m.scale(), &Vector3::new(2.0, 2.0, 2.0));
+        assert!((m.rotation().angle() - 0.5).abs() < 1.0e-6);
+
+        assert_eq!(a.interpolate(&b, 0.0).translation(), a.translation());
+        assert_eq!(a.interpolate(&b, 1.0).translation(), b.translation());
+    }
+
+    #[test]
+    fn test_serialization() {
+        let a = Model::new(
+            Vector3::new(1.0, 2.0, 3.0),
+            Vector3::new(0.0, 1.0, 0.0),
+            Vector3::new(2.0, 2.0, 2.0),
+        );
+
+        let json = serde_json::to_string(&a).unwrap();
+        let b: Model = serde_json::from_str(&json).unwrap();
+        assert_eq!(b.translation(), a.translation());
+        assert_eq!(b.rotation(), a.rotation());
+        assert_eq!(b.scale(), a.scale());
+        assert_eq!(b.matrix(), a.matrix());
+    }
 }
@@ -0,0 +1,10 @@
+//! The `previous_model` module provides access to the `PreviousModel` component.
+
+use components::model::Model;
+
+/// The `PreviousModel` component holds the world-space `Model` (see `GlobalTransform`) of an
+/// entity as of the previous fixed update, such that render systems may interpolate between the
+/// previous and the current state.
+#[derive(Clone, Component)]
+#[component(name = "previous_model")]
+pub struct PreviousModel(pub Model);
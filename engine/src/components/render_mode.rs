@@ -1,4 +1,5 @@
-#[derive(Debug, Clone, PartialOrd, Ord, PartialEq, Eq, Component)]
+#[derive(Debug, Clone, PartialOrd, Ord, PartialEq, Eq, Serialize, Deserialize, Component)]
+#[component(name = "render_mode")]
 pub enum RenderMode {
     World,
     Ui,
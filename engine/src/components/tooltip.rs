@@ -1,6 +1,7 @@
 /// The `TooltipData` component allows objects to display information about themselves upon hovering
 /// the mouse cursor over the object in 3-space.
 #[derive(Clone, Serialize, Deserialize, Component)]
+#[component(storage = "sparse", name = "tooltip")]
 pub struct TooltipData {
     pub text: String,
 }
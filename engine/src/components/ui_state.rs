@@ -9,6 +9,7 @@ use common::ui_styles::{SpeechBubble, Tooltip};
 
 /// The `UiState` component encodes information about the user interface.
 #[derive(Component)]
+#[component(storage = "sparse", name = "ui_state")]
 pub struct UiState {
     /// Holds all user interface elements, so-called `UiElement`s, indexed by a `Uuid`.
     pub elements: HashMap<Uuid, UiElement>,
@@ -1,82 +1,74 @@
-use std::u64;
+//! Defines the events emitted and consumed by the engine. Each event is a type of its own, such
+//! that game crates may define further events without touching the engine. The foundational
+//! events (shutdown and readiness) are provided by `ecs::EcsEvent`.
+
+use std::collections::BTreeMap;
+use std::time::Duration;
 use nalgebra::Point2;
+use serde_json::Value;
+use uuid::Uuid;
 use glium::glutin::{ElementState, MouseButton};
-use ecs::{EcsEvent, EventTrait};
+use ecs::EventTrait;
 use components::cursor::FlankDirection;
 
-bitflags! {
-    pub struct EngineEventFlag: u64 {
-        const SHUTDOWN = 0x1;
-        const IMMEDIATE_SHUTDOWN = 0x2;
-        const READY = 0x4;
-        const CONSOLE_COMMAND = 0x08;
-        const RENDERER_READY = 0x10;
-        const RESIZE_WINDOW = 0x20;
-        const RELOAD_SHADERS = 0x40;
-        const SPEECH_BUBBLE = 0x80;
-        const CURSOR_POSITION = 0x100;
-        const MOUSE_INPUT = 0x200;
-        const MOUSE_INPUT_FLANK = 0x400;
-        const ALL_EVENTS = u64::MAX;
-    }
-}
+/// Signals that the `Renderer` has completed its initialization.
+#[derive(Debug, Clone)]
+pub struct RendererReady;
+
+impl EventTrait for RendererReady {}
+
+/// Carries a command line (split into arguments) that was entered into the debug console.
+#[derive(Debug, Clone)]
+pub struct ConsoleCommand(pub Vec<String>);
+
+impl EventTrait for ConsoleCommand {}
+
+/// Signals that the window was resized to the specified width and height (in pixels).
+#[derive(Debug, Clone)]
+pub struct ResizeWindow(pub u32, pub u32);
+
+impl EventTrait for ResizeWindow {}
+
+/// Requests that all shaders be reloaded.
+#[derive(Debug, Clone)]
+pub struct ReloadShaders;
+
+impl EventTrait for ReloadShaders {}
+
+/// Requests a speech bubble for the entity of the specified name (first field) with the
+/// specified content (second field) and lifetime in game time (third field).
+#[derive(Debug, Clone)]
+pub struct SpeechBubble(pub String, pub String, pub Duration);
+
+impl EventTrait for SpeechBubble {}
+
+/// Signals that the lifetime of the speech bubble with the specified id has ended.
+#[derive(Debug, Clone)]
+pub struct SpeechBubbleExpired(pub Uuid);
+
+impl EventTrait for SpeechBubbleExpired {}
+
+/// Requests an entity from the prefab of the specified name (first field), with the specified
+/// component overrides (second field, see `ecs::Prefab::with_overrides`).
+#[derive(Debug, Clone)]
+pub struct SpawnPrefab(pub String, pub BTreeMap<String, Value>);
+
+impl EventTrait for SpawnPrefab {}
 
+/// Carries the current position of the cursor (in pixels).
 #[derive(Debug, Clone)]
-pub enum EngineEvent {
-    Shutdown,
-    ImmediateShutdown,
-    Ready,
-    RendererReady,
-    ConsoleCommand(Vec<String>),
-    ResizeWindow(u32, u32),
-    ReloadShaders,
-    SpeechBubble(String, String, u64),
-    CursorPosition(Point2<u32>),
-    MouseInput(MouseButton, ElementState),
-    MouseInputFlank(MouseButton, FlankDirection),
-}
-
-impl EventTrait for EngineEvent {
-    type EventFlag = EngineEventFlag;
-    fn match_filter(&self, filter: EngineEventFlag) -> bool {
-        filter.contains(EngineEventFlag::from(self.clone()))
-    }
-    fn as_ecs_event(&self) -> Option<EcsEvent> {
-        use self::EngineEvent::*;
-        match *self {
-            Shutdown => Some(EcsEvent::Shutdown),
-            ImmediateShutdown => Some(EcsEvent::ImmediateShutdown),
-            Ready => Some(EcsEvent::Ready),
-            _ => None,
-        }
-    }
-}
-
-impl From<EcsEvent> for EngineEvent {
-    fn from(value: EcsEvent) -> EngineEvent {
-        match value {
-            EcsEvent::ImmediateShutdown => EngineEvent::ImmediateShutdown,
-            EcsEvent::Shutdown => EngineEvent::Shutdown,
-            EcsEvent::Ready => EngineEvent::Ready,
-        }
-    }
-}
-
-impl From<EngineEvent> for EngineEventFlag {
-    fn from(value: EngineEvent) -> EngineEventFlag {
-        use self::EngineEvent::*;
-        match value {
-            Shutdown => EngineEventFlag::SHUTDOWN,
-            ImmediateShutdown => EngineEventFlag::IMMEDIATE_SHUTDOWN,
-            Ready => EngineEventFlag::READY,
-            RendererReady => EngineEventFlag::RENDERER_READY,
-            ConsoleCommand(_) => EngineEventFlag::CONSOLE_COMMAND,
-            ResizeWindow(..) => EngineEventFlag::RESIZE_WINDOW,
-            ReloadShaders => EngineEventFlag::RELOAD_SHADERS,
-            SpeechBubble(..) => EngineEventFlag::SPEECH_BUBBLE,
-            CursorPosition(_) => EngineEventFlag::CURSOR_POSITION,
-            MouseInput(..) => EngineEventFlag::MOUSE_INPUT,
-            MouseInputFlank(..) => EngineEventFlag::MOUSE_INPUT_FLANK,
-        }
-    }
-}
+pub struct CursorPosition(pub Point2<u32>);
+
+impl EventTrait for CursorPosition {}
+
+/// Carries the state of a mouse button as reported by the operating system.
+#[derive(Debug, Clone)]
+pub struct MouseInput(pub MouseButton, pub ElementState);
+
+impl EventTrait for MouseInput {}
+
+/// Signals that the state of a mouse button changed.
+#[derive(Debug, Clone)]
+pub struct MouseInputFlank(pub MouseButton, pub FlankDirection);
+
+impl EventTrait for MouseInputFlank {}
@@ -1,6 +1,4 @@
 extern crate alga;
-#[macro_use]
-extern crate bitflags;
 extern crate clap;
 extern crate daggy;
 #[macro_use]
@@ -22,6 +20,8 @@ extern crate rusttype;
 extern crate serde;
 #[macro_use]
 extern crate serde_derive;
+#[macro_use]
+extern crate serde_json;
 extern crate unicode_normalization;
 extern crate uuid;
 
@@ -32,16 +32,21 @@ pub mod common;
 pub mod components;
 pub mod systems;
 
-pub use self::event::EngineEvent;
 pub use self::common::ui_styles::{SpeechBubble, Tooltip};
+pub use self::common::input::{InputEvent, InputRecord, InputRecorder, InputReplay};
+pub use self::common::time_control::TimeControl;
+pub use self::common::scene::{load_prefab, scene_registry, BoundingVolumeAsset, BoundingVolumeKind,
+                              MaterialAsset, SceneAssets, WorldSnapshot};
 pub use self::common::resource_group::{FontGroup, ShaderGroup, TextureGroup};
 pub use self::components::bounding_volume::BoundingVolume;
 pub use self::components::camera::Camera;
 pub use self::components::cursor::Cursor;
 pub use self::components::description::Description;
 pub use self::components::material::Material;
-pub use self::components::mesh::Mesh;
+pub use self::components::mesh::{Mesh, MeshOrigin};
 pub use self::components::model::Model;
+pub use self::components::global_transform::GlobalTransform;
+pub use self::components::previous_model::PreviousModel;
 pub use self::components::render_mode::RenderMode;
 pub use self::components::tooltip::TooltipData;
 pub use self::components::ui_state::UiState;
@@ -55,4 +60,7 @@ pub use self::systems::event_monitor::EventMonitor;
 pub use self::systems::renderer::Renderer;
 pub use self::systems::tooltip_controller::TooltipController;
 pub use self::systems::speech_bubble_controller::SpeechBubbleController;
+pub use self::systems::prefab_spawner::PrefabSpawner;
+pub use self::systems::transform_propagator::TransformPropagator;
+pub use self::systems::physics_updater::PhysicsUpdater;
 pub use self::orchestrator::Orchestrator;
@@ -1,82 +1,398 @@
 use std::cmp;
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 use std::time::{Duration, Instant};
 
-use ecs::{EcsError, World};
-use event::EngineEvent;
+use glium::Display;
+use serde_json::Value;
+use ecs::{ComponentRegistry, EcsError, EcsEvent, Entity, Scene, SystemHandle, World};
 use singletons::Singletons;
 use systems::SystemGroup;
+use components::global_transform::GlobalTransform;
+use components::model::Model;
+use components::previous_model::PreviousModel;
+use common::physics::duration_as_float;
 use common::file_manipulation::{verify_accessible_file, FileError};
+use common::scene::{load_prefab, scene_registry, SceneAssets, WorldSnapshot};
 
 /// The `Orchestrator` owns the `World` and manages time (and the game loop).
 pub struct Orchestrator {
     /// Holds an instance of the `World`.
-    pub world: World<EngineEvent, Singletons, SystemGroup>,
+    pub world: World<Singletons, SystemGroup>,
     /// If `true`, activate debugging functionality.
     pub debug: bool,
     /// Specifies the path to the resource tree.
     resource_path: PathBuf,
+    /// Holds the registry of serializable components, which refers to the resource tree.
+    registry: ComponentRegistry<SceneAssets>,
     /// Specifies the fixed time interval of the simulation.
     delta_time: Duration,
     /// Specifies the maximum duration of a single frame.
     max_frame_time: Duration,
+    /// Holds the current game time, which advances by `delta_time` with each update.
+    game_time: Duration,
+    /// Holds the simulation time that has not yet been consumed by updates.
+    accumulator: Duration,
+    /// Is `false` once the `World` has shut down.
+    running: bool,
 }
 
 impl Orchestrator {
-    /// Creates a new instance of the `Orchestrator`.
+    /// Creates a new instance of the `Orchestrator`. The scene graph is kept in sync with the
+    /// `Model` components of the `World`.
     pub fn new(rp: &Path, delta_time: Duration, max_frame_time: Duration, debug: bool) -> Self {
+        let mut world: World<Singletons, SystemGroup> = Default::default();
+        world.on_add::<Model, _>(|entities, aux, entity| {
+            if let Ok(m) = entities.borrow_component::<Model>(entity) {
+                if !aux.scene_graph.has(entity) {
+                    aux.scene_graph.insert(*entity, m.clone());
+                }
+            }
+        });
+        world.on_remove::<Model, _>(|_, aux, entity| {
+            aux.scene_graph.remove_and_promote(entity).ok();
+        });
+
         Orchestrator {
-            world: Default::default(),
+            world: world,
             debug: debug,
             resource_path: rp.to_owned(),
+            registry: scene_registry(rp),
             delta_time: delta_time,
             max_frame_time: max_frame_time,
+            game_time: Duration::new(0, 0),
+            accumulator: Duration::new(0, 0),
+            running: true,
         }
     }
+    /// Creates an `Orchestrator` without rendering, that is meant to be advanced manually with
+    /// `step`. Neither a `Renderer` nor an `EventInterface` are required, such that integration
+    /// tests may drive the `World` tick by tick.
+    pub fn headless(rp: &Path, delta_time: Duration) -> Self {
+        let mut o = Orchestrator::new(rp, delta_time, delta_time, false);
+        o.world.rendering_suspended = true;
+        o
+    }
     /// Initializes state and starts the game loop. Using the supplied closure, the state of the
     /// `Orchestrator` and subsequently the `World` may be initialized.
     pub fn run<I>(&mut self, init: I)
     where
         I: FnOnce(&mut Orchestrator),
     {
-        init(self);
-        self.world.dispatch(EngineEvent::Ready);
+        self.initialize(init);
         self.main_loop();
     }
+    /// Initializes state using the supplied closure and signals readiness to the `World`,
+    /// without starting the game loop.
+    pub fn initialize<I>(&mut self, init: I)
+    where
+        I: FnOnce(&mut Orchestrator),
+    {
+        init(self);
+        self.world.dispatch(EcsEvent::Ready);
+    }
+    /// Advances the `World` by the specified number of frames of exactly one update each, using
+    /// a virtual clock instead of the wall clock. The `TimeControl` is not consulted. Returns
+    /// `false` once the `World` has shut down, after which further steps have no effect.
+    pub fn step(&mut self, ticks: u32) -> bool {
+        let delta_time = self.delta_time;
+        for _ in 0..ticks {
+            if !self.advance(delta_time, delta_time) {
+                break;
+            }
+        }
+        self.running
+    }
+    /// Returns the current game time.
+    pub fn game_time(&self) -> Duration {
+        self.game_time
+    }
+    /// Returns `true` until the `World` has shut down.
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
     /// Attempts to retrieve a file path from the resource tree.
     pub fn get_file(&self, category: &str, filename: &str) -> Result<PathBuf, FileError> {
         let path = self.resource_path.join(category).join(filename);
         verify_accessible_file(&path)?;
         Ok(path)
     }
-    pub fn add_system<S: Into<SystemGroup>>(&mut self, system: S) -> Result<(), EcsError> {
+    /// Loads the entities of the specified scene file in the `scenes` directory of the resource
+    /// tree. GPU-backed components are rebuilt for the supplied display.
+    pub fn load_scene(
+        &mut self,
+        display: &Display,
+        filename: &str,
+    ) -> Result<Vec<Entity>, EcsError> {
+        let scene = Scene::read(&self.resource_path.join("scenes").join(filename))?;
+        let mut assets = SceneAssets::take(display, &mut self.world.aux.factory);
+        let result = self.world.load_scene(&self.registry, &scene, &mut assets);
+        assets.restore(&mut self.world.aux.factory);
+        result
+    }
+    /// Creates an entity from the specified prefab file in the `prefabs` directory of the
+    /// resource tree, with the overrides applied (see `Prefab::with_overrides`).
+    pub fn spawn_prefab(
+        &mut self,
+        display: &Display,
+        name: &str,
+        overrides: &BTreeMap<String, Value>,
+    ) -> Result<Entity, EcsError> {
+        let prefab = load_prefab(&self.resource_path, name)?.with_overrides(overrides);
+        let mut assets = SceneAssets::take(display, &mut self.world.aux.factory);
+        let result = prefab.instantiate(&mut self.world, &self.registry, &mut assets);
+        assets.restore(&mut self.world.aux.factory);
+        result
+    }
+    /// Saves the serializable components of all entities to the specified scene file.
+    pub fn save_scene(&self, path: &Path) -> Result<(), EcsError> {
+        self.world.save_scene(&self.registry)?.write(path)
+    }
+    /// Captures the serializable components of all entities and the scene graph.
+    pub fn snapshot(&self) -> Result<WorldSnapshot, EcsError> {
+        Ok(WorldSnapshot {
+            entities: self.world.snapshot(&self.registry)?,
+            scene_graph: self.world.aux.scene_graph.clone(),
+        })
+    }
+    /// Rolls the `World` back to the specified snapshot. GPU-backed components that changed
+    /// since are rebuilt for the supplied display.
+    pub fn restore(&mut self, display: &Display, snapshot: &WorldSnapshot) -> Result<(), EcsError> {
+        let mut assets = SceneAssets::take(display, &mut self.world.aux.factory);
+        let result = self.world.restore(&self.registry, &snapshot.entities, &mut assets);
+        assets.restore(&mut self.world.aux.factory);
+        result?;
+        *self.world.aux.scene_graph = snapshot.scene_graph.clone();
+        Ok(())
+    }
+    /// Adds a system to the `World` and returns its handle, which may be used to remove, disable
+    /// or access the system later on.
+    pub fn add_system<S: Into<SystemGroup>>(
+        &mut self,
+        system: S,
+    ) -> Result<SystemHandle, EcsError> {
         self.world.add_system(system.into())
     }
     /// Runs the actual game loop. This loop uses a fixed time-step method to ensure that
     /// `World::update` is called at a fixed interval, always. After a cycle of update calls, the
     /// loop then issues calls to `World::dynamic_update`, `World::render` and
     /// `World::handle_events`. The return value of `World::handle_events` is used to terminate the
-    /// loop.
+    /// loop. The `TimeControl` of the `Singletons` determines how much simulation time elapses
+    /// per frame.
     fn main_loop(&mut self) {
-        let mut game_time = Duration::new(0, 0);
-        let mut accumulator = Duration::new(0, 0);
         let mut loop_time = Instant::now();
 
-        let mut running = true;
-        while running {
+        while self.running {
             let frame_time = cmp::min(loop_time.elapsed(), self.max_frame_time);
             loop_time = Instant::now();
-            accumulator += frame_time;
-
-            while accumulator >= self.delta_time {
-                self.world.update(&game_time, &self.delta_time);
-                game_time += self.delta_time;
-                accumulator -= self.delta_time;
+            let simulated_time = self.world
+                .aux
+                .time_control
+                .simulated_time(frame_time, self.delta_time, self.max_frame_time);
+            self.advance(simulated_time, frame_time);
+        }
+    }
+    /// Records the current `GlobalTransform` of each entity as its `PreviousModel`, such that
+    /// render systems may interpolate between the states before and after an update.
+    fn snapshot_models(&mut self) {
+        let snapshots = self.world
+            .r1_iter::<GlobalTransform>()
+            .map(|(e, g)| (e, PreviousModel(g.0.clone())))
+            .collect::<Vec<_>>();
+        for (e, p) in snapshots {
+            // Update existing snapshots in place, such that no lifecycle hooks are triggered.
+            if let Ok(previous) = self.world.borrow_component_mut::<PreviousModel>(&e) {
+                *previous = p;
+                continue;
             }
+            self.world
+                .add_component(&e, p)
+                .expect("Unable to record the previous model");
+        }
+    }
+    /// Determines how far the frame lies between the previous and the next update. If no
+    /// simulation time elapses during the frame (e.g. while paused), the latest state is rendered
+    /// as is, because the next update may never come.
+    fn interpolation_factor(&self, simulated_time: Duration) -> f32 {
+        if simulated_time == Duration::default() {
+            return 1.0;
+        }
+        duration_as_float::<f32>(self.accumulator).unwrap_or(0.0)
+            / duration_as_float::<f32>(self.delta_time).unwrap_or(1.0)
+    }
+    /// Runs a single frame of the specified duration, during which the specified amount of
+    /// simulation time elapses, and returns whether the `World` is still running.
+    fn advance(&mut self, simulated_time: Duration, frame_time: Duration) -> bool {
+        if !self.running {
+            return false;
+        }
 
-            self.world.dynamic_update(&game_time, &frame_time);
-            self.world.render(&game_time, &frame_time);
-            running = self.world.handle_events();
+        self.accumulator += simulated_time;
+        while self.accumulator >= self.delta_time {
+            self.snapshot_models();
+            self.world.update(&self.game_time, &self.delta_time);
+            self.game_time += self.delta_time;
+            self.accumulator -= self.delta_time;
         }
+
+        let alpha = self.interpolation_factor(simulated_time);
+        self.world.dynamic_update(&self.game_time, &frame_time);
+        self.world.render(&self.game_time, &frame_time, alpha);
+        self.running = self.world.handle_events();
+        self.running
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+    use nalgebra::{zero, Vector3};
+    use ecs::ExecutionMode;
+    use common::vertex::Vertex;
+    use components::bounding_volume::BoundingVolume;
+    use components::description::Description;
+    use systems::debug_mover::DebugMover;
+    use systems::event_monitor::EventMonitor;
+    use systems::physics_updater::PhysicsUpdater;
+    use systems::transform_propagator::TransformPropagator;
+
+    /// Creates the headless `Orchestrator` that is shared by the tests of the engine.
+    pub(crate) fn orchestrator() -> Orchestrator {
+        Orchestrator::headless(Path::new("."), Duration::from_millis(50))
+    }
+
+    /// Creates an unscaled `Model` at the specified position on the x-axis.
+    pub(crate) fn model(x: f32) -> Model {
+        Model::new(Vector3::new(x, 0.0, 0.0), zero(), Vector3::new(1.0, 1.0, 1.0))
+    }
+
+    /// Creates an entity with a `Model` at the specified position on the x-axis and a unit-sized
+    /// `BoundingVolume`.
+    pub(crate) fn spawn(o: &mut Orchestrator, x: f32) -> Entity {
+        let vertices = vec![
+            Vertex::new([-0.5, -0.5, 0.0], [0.0, 0.0], [0.0, 0.0, 1.0]),
+            Vertex::new([0.5, 0.5, 0.0], [1.0, 1.0], [0.0, 0.0, 1.0]),
+        ];
+        let e = o.world.create_entity();
+        o.world.add_component(&e, model(x)).unwrap();
+        o.world
+            .add_component(&e, BoundingVolume::new_aabb(&vertices))
+            .unwrap();
+        e
+    }
+
+    /// Steps a scene with the specified systems, and returns the local and global translation
+    /// of each entity, as well as whether the physics knows the entity.
+    fn simulate(
+        mode: ExecutionMode,
+        systems: Vec<SystemGroup>,
+    ) -> Vec<(Vector3<f32>, Option<Vector3<f32>>, bool)> {
+        let mut o = orchestrator();
+        o.world.set_execution_mode(mode);
+        let mut entities = Vec::new();
+        o.initialize(|o| {
+            for (i, name) in ["target", "bystander"].iter().enumerate() {
+                let e = spawn(o, i as f32);
+                o.world.add_component(&e, Description::new(name)).unwrap();
+                entities.push(e);
+            }
+            for system in systems {
+                o.add_system(system).unwrap();
+            }
+        });
+        o.step(3);
+
+        entities
+            .iter()
+            .map(|e| {
+                let local = *o.world.borrow_component::<Model>(e).unwrap().translation();
+                let global = o.world
+                    .borrow_component::<GlobalTransform>(e)
+                    .map(|g| *g.0.translation())
+                    .ok();
+                (local, global, o.world.aux.physics.contains(e))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn parallel_stepping() {
+        // In the dynamic update, each `EventMonitor` shares a batch with one of the other systems,
+        // such that their declared access is enforced.
+        let systems = || {
+            vec![
+                EventMonitor::new().into(),
+                TransformPropagator::new().into(),
+                PhysicsUpdater::new().into(),
+                EventMonitor::new().into(),
+            ]
+        };
+        let sequential = simulate(ExecutionMode::SingleThreaded, systems());
+        assert!(sequential.iter().all(|&(_, g, p)| g.is_some() && p));
+        assert_eq!(simulate(ExecutionMode::Parallel, systems()), sequential);
+
+        // Without a `TransformPropagator` in between, the `DebugMover` and the `PhysicsUpdater`
+        // share a batch in the update stage.
+        let systems = || vec![DebugMover::new("target").into(), PhysicsUpdater::new().into()];
+        let sequential = simulate(ExecutionMode::SingleThreaded, systems());
+        assert_ne!(sequential[0].0, Vector3::new(0.0, 0.0, 0.0));
+        assert_eq!(simulate(ExecutionMode::Parallel, systems()), sequential);
+    }
+
+    #[test]
+    fn headless_stepping() {
+        let mut o = orchestrator();
+        let mut target = None;
+        o.initialize(|o| {
+            let e = o.world.create_entity();
+            o.world.add_component(&e, Description::new("target")).unwrap();
+            o.world.add_component(&e, Model::identity()).unwrap();
+            o.add_system(DebugMover::new("target")).unwrap();
+            target = Some(e);
+        });
+        let target = target.unwrap();
+
+        assert!(o.step(1));
+        assert_eq!(o.game_time(), Duration::from_millis(50));
+        let first = *o.world.borrow_component::<Model>(&target).unwrap().translation();
+        assert_eq!(first, Vector3::new(0.0, 1.0, 0.0));
+
+        assert!(o.step(1));
+        let second = *o.world.borrow_component::<Model>(&target).unwrap().translation();
+        assert_ne!(first, second);
+        assert_eq!(o.game_time(), Duration::from_millis(100));
+    }
+
+    #[test]
+    fn interpolation() {
+        let mut o = orchestrator();
+        let e = o.world.create_entity();
+        o.world
+            .add_component(&e, GlobalTransform(Model::identity()))
+            .unwrap();
+
+        assert!(o.advance(Duration::from_millis(75), Duration::from_millis(75)));
+        assert_eq!(o.interpolation_factor(Duration::from_millis(75)), 0.5);
+        assert_eq!(o.interpolation_factor(Duration::default()), 1.0);
+
+        o.world.borrow_component_mut::<GlobalTransform>(&e).unwrap().0 = model(1.0);
+        let tick = o.world.tick();
+        assert!(o.advance(Duration::from_millis(50), Duration::from_millis(50)));
+        let previous = o.world.borrow_component::<PreviousModel>(&e).unwrap();
+        assert_eq!(*previous.0.translation(), Vector3::new(1.0, 0.0, 0.0));
+        assert_eq!(o.world.added::<PreviousModel>(tick).count(), 0);
+    }
+
+    #[test]
+    fn headless_shutdown() {
+        let mut o = orchestrator();
+        o.initialize(|_| ());
+        o.world.dispatch(EcsEvent::Shutdown);
+
+        assert!(!o.step(3));
+        assert!(!o.is_running());
+        assert_eq!(o.game_time(), Duration::from_millis(100));
+        assert!(!o.step(1));
+        assert_eq!(o.game_time(), Duration::from_millis(100));
     }
 }
@@ -1,14 +1,37 @@
 use uuid::Uuid;
-use ecs::Entity;
+use ecs::{Access, AuxPart, Entity, SplitAux};
 use common::factory::ComponentFactory;
 use common::physics::PhysicsController;
 use common::hierarchy::Hierarchy;
+use common::time_control::TimeControl;
 use components::model::Model;
 
+/// Holds the state that is shared by all systems. Each field is an `AuxPart`, such that systems
+/// which declare a part with `Access::aux` may run concurrently.
 #[derive(Default)]
 pub struct Singletons {
-    pub factory: ComponentFactory,
-    pub physics: PhysicsController,
-    pub scene_graph: Hierarchy<Entity, Model>,
-    pub ui_hierarchy: Hierarchy<Uuid, Model>,
+    pub factory: AuxPart<ComponentFactory>,
+    pub physics: AuxPart<PhysicsController>,
+    pub scene_graph: AuxPart<Hierarchy<Entity, Model>>,
+    pub ui_hierarchy: AuxPart<Hierarchy<Uuid, Model>>,
+    pub time_control: AuxPart<TimeControl>,
+}
+
+unsafe impl SplitAux for Singletons {
+    fn split(&mut self, access: &Access) -> Self {
+        Singletons {
+            factory: self.factory.split(access),
+            physics: self.physics.split(access),
+            scene_graph: self.scene_graph.split(access),
+            ui_hierarchy: self.ui_hierarchy.split(access),
+            time_control: self.time_control.split(access),
+        }
+    }
+    fn merge(&mut self, parts: Self) {
+        self.factory.merge(parts.factory);
+        self.physics.merge(parts.physics);
+        self.scene_graph.merge(parts.scene_graph);
+        self.ui_hierarchy.merge(parts.ui_hierarchy);
+        self.time_control.merge(parts.time_control);
+    }
 }
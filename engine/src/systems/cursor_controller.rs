@@ -1,6 +1,6 @@
 use glium::glutin::ElementState;
-use ecs::{Assembly, DispatchEvents, LoopStageFlag, SystemTrait};
-use event::{EngineEvent, EngineEventFlag};
+use ecs::{Assembly, DispatchEvents, Event, EventFilter, LoopStageFlag, SystemTrait};
+use event::{CursorPosition, MouseInput, MouseInputFlank};
 use singletons::Singletons;
 use components::cursor::{Cursor, FlankDirection};
 
@@ -15,7 +15,7 @@ impl CursorController {
     }
 }
 
-impl SystemTrait<EngineEvent, Singletons> for CursorController {
+impl SystemTrait<Singletons> for CursorController {
     /// The `CursorController` requires exactly one `Cursor` component.
     fn verify_requirements(&self, entities: &Assembly) -> bool {
         entities.count1::<Cursor>() == 1
@@ -24,9 +24,15 @@ impl SystemTrait<EngineEvent, Singletons> for CursorController {
     fn get_loop_stage_filter(&self) -> LoopStageFlag {
         LoopStageFlag::HANDLE_EVENT
     }
+    /// `CursorController` is labeled `"cursor_controller"`.
+    fn get_label(&self) -> Option<&'static str> {
+        Some("cursor_controller")
+    }
     /// The `CursorController` listens for the `CursorPosition` and `MouseInput` events.
-    fn get_event_filter(&self) -> EngineEventFlag {
-        EngineEventFlag::CURSOR_POSITION | EngineEventFlag::MOUSE_INPUT
+    fn get_event_filter(&self) -> EventFilter {
+        EventFilter::new()
+            .with::<CursorPosition>()
+            .with::<MouseInput>()
     }
     /// Upon receiving a `CursorPosition` event, the respective field of the `Cursor` component is
     /// updated. Upon receiving a `MouseInput` event, the new button state is compared to the
@@ -36,53 +42,51 @@ impl SystemTrait<EngineEvent, Singletons> for CursorController {
         &mut self,
         entities: &mut Assembly,
         _: &mut Singletons,
-        event: &EngineEvent,
-    ) -> DispatchEvents<EngineEvent> {
-        match *event {
-            EngineEvent::CursorPosition(position) => {
-                // Update the cursor component's position.
-                entities
-                    .ws1::<Cursor>()
-                    .map(|(_, c)| c.position = position)
-                    .expect("Could not access the Cursor component");
-                (None, None)
-            }
-            EngineEvent::MouseInput(button, state) => {
-                // Given the current button state and the new one, emit an event if the button went
-                // from released to pressed (eg. a down flank) or from pressed to released (eg. an
-                // up flank). Subsequently update the button state on the cursor component.
-                entities
-                    .ws1::<Cursor>()
-                    .map(|(_, c)| {
-                        // Get the current mouse button state
-                        let current_state =
-                            c.buttons.entry(button).or_insert(ElementState::Released);
+        event: &Event,
+    ) -> DispatchEvents {
+        if let Some(&CursorPosition(position)) = event.downcast_ref() {
+            // Update the cursor component's position.
+            entities
+                .ws1::<Cursor>()
+                .map(|(_, c)| c.position = position)
+                .expect("Could not access the Cursor component");
+            (None, None)
+        } else if let Some(&MouseInput(button, state)) = event.downcast_ref() {
+            // Given the current button state and the new one, emit an event if the button went
+            // from released to pressed (eg. a down flank) or from pressed to released (eg. an
+            // up flank). Subsequently update the button state on the cursor component.
+            entities
+                .ws1::<Cursor>()
+                .map(|(_, c)| {
+                    // Get the current mouse button state
+                    let current_state =
+                        c.buttons.entry(button).or_insert(ElementState::Released);
 
-                        // Determine the derivative of the mouse button press function.
-                        let resulting_event = match *current_state {
-                            ElementState::Pressed => match state {
-                                ElementState::Pressed => None,
-                                ElementState::Released => Some(vec![
-                                    EngineEvent::MouseInputFlank(button, FlankDirection::Up),
-                                ]),
-                            },
-                            ElementState::Released => match state {
-                                ElementState::Pressed => Some(vec![
-                                    EngineEvent::MouseInputFlank(button, FlankDirection::Down),
-                                ]),
-                                ElementState::Released => None,
-                            },
-                        };
+                    // Determine the derivative of the mouse button press function.
+                    let resulting_event = match *current_state {
+                        ElementState::Pressed => match state {
+                            ElementState::Pressed => None,
+                            ElementState::Released => Some(vec![
+                                MouseInputFlank(button, FlankDirection::Up).into(),
+                            ]),
+                        },
+                        ElementState::Released => match state {
+                            ElementState::Pressed => Some(vec![
+                                MouseInputFlank(button, FlankDirection::Down).into(),
+                            ]),
+                            ElementState::Released => None,
+                        },
+                    };
 
-                        // Update the current mouse button state.
-                        *current_state = state;
+                    // Update the current mouse button state.
+                    *current_state = state;
 
-                        // Return the resulting flank event for immediate dispatch.
-                        (resulting_event, None)
-                    })
-                    .expect("Could not access the Cursor component")
-            }
-            _ => (None, None),
+                    // Return the resulting flank event for immediate dispatch.
+                    (resulting_event, None)
+                })
+                .expect("Could not access the Cursor component")
+        } else {
+            (None, None)
         }
     }
 }
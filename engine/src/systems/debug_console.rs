@@ -3,8 +3,8 @@ use std::string::FromUtf8Error;
 use std::sync::mpsc::{channel, Receiver, TryRecvError};
 use std::time::Duration;
 use std::thread::spawn;
-use ecs::{Assembly, DispatchEvents, LoopStageFlag, SystemTrait};
-use event::EngineEvent;
+use ecs::{Access, Assembly, DispatchEvents, LoopStageFlag, SystemTrait};
+use event::ConsoleCommand;
 use singletons::Singletons;
 use common::text_manipulation::split_arguments;
 
@@ -75,7 +75,7 @@ impl DebugConsole {
     }
 }
 
-impl SystemTrait<EngineEvent, Singletons> for DebugConsole {
+impl SystemTrait<Singletons> for DebugConsole {
     /// `DebugConsole` has no requirements wrt. the `Assembly`.
     fn verify_requirements(&self, _: &Assembly) -> bool {
         true
@@ -84,6 +84,18 @@ impl SystemTrait<EngineEvent, Singletons> for DebugConsole {
     fn get_loop_stage_filter(&self) -> LoopStageFlag {
         LoopStageFlag::DYNAMIC_UPDATE
     }
+    /// `DebugConsole` is labeled `"debug_console"`.
+    fn get_label(&self) -> Option<&'static str> {
+        Some("debug_console")
+    }
+    /// `DebugConsole` does not access any components.
+    fn get_access(&self) -> Access {
+        Access::new()
+    }
+    /// `DebugConsole` may be called on another thread.
+    fn as_send(&mut self) -> Option<&mut (dyn SystemTrait<Singletons> + Send)> {
+        Some(self)
+    }
     /// Attempts to retrieve data from the worker thread and emits a `ConsoleCommand` event once a
     /// full line of input has been received. Also performs argument splitting before emitting the
     /// event.
@@ -93,10 +105,10 @@ impl SystemTrait<EngineEvent, Singletons> for DebugConsole {
         _: &mut Singletons,
         _: &Duration,
         _: &Duration,
-    ) -> DispatchEvents<EngineEvent> {
+    ) -> DispatchEvents {
         let event = self.try_read_line()
             .map(|s| split_arguments(&s, self.escape_char, self.quote_char))
-            .map(|c| vec![EngineEvent::ConsoleCommand(c)]);
+            .map(|c| vec![ConsoleCommand(c).into()]);
 
         (None, event)
     }
@@ -1,7 +1,6 @@
 use std::time::Duration;
 use nalgebra::{zero, Vector3};
-use ecs::{Assembly, DispatchEvents, Entity, LoopStageFlag, SystemTrait};
-use event::EngineEvent;
+use ecs::{Access, Assembly, DispatchEvents, Entity, LoopStageFlag, SystemTrait};
 use singletons::Singletons;
 use components::description::Description;
 use components::model::Model;
@@ -24,7 +23,7 @@ impl DebugMover {
     }
 }
 
-impl SystemTrait<EngineEvent, Singletons> for DebugMover {
+impl SystemTrait<Singletons> for DebugMover {
     /// `DebugMover` has no requirements.
     fn verify_requirements(&self, _: &Assembly) -> bool {
         true
@@ -33,13 +32,25 @@ impl SystemTrait<EngineEvent, Singletons> for DebugMover {
     fn get_loop_stage_filter(&self) -> LoopStageFlag {
         LoopStageFlag::UPDATE
     }
+    /// `DebugMover` is labeled `"debug_mover"`.
+    fn get_label(&self) -> Option<&'static str> {
+        Some("debug_mover")
+    }
+    /// `DebugMover` looks up its target by `Description` and moves its `Model`.
+    fn get_access(&self) -> Access {
+        Access::new().read::<Description>().write::<Model>()
+    }
+    /// `DebugMover` may be called on another thread.
+    fn as_send(&mut self) -> Option<&mut (dyn SystemTrait<Singletons> + Send)> {
+        Some(self)
+    }
     fn update(
         &mut self,
         entities: &mut Assembly,
         _: &mut Singletons,
         time: &Duration,
         _: &Duration,
-    ) -> DispatchEvents<EngineEvent> {
+    ) -> DispatchEvents {
         if self.target.is_none() {
             let (target, target_position) = entities
                 .rsf2::<_, Description, Model>(|&(_, d, _)| d.name == self.target_name)
@@ -50,18 +61,23 @@ impl SystemTrait<EngineEvent, Singletons> for DebugMover {
             self.target_position = target_position;
         }
 
-        if let Some(ref target) = self.target {
-            entities
-                .borrow_component_mut::<Model>(target)
-                .map(|m| {
-                    let r = 1.0;
-                    let w = 1.0;
-                    let t = time.as_secs() as f32 + time.subsec_nanos() as f32 * 1e-9;
-                    let translation = self.target_position
-                        + Vector3::new(0.0, r * (w * t).cos(), r * (w * t).sin());
-                    m.set_translation(translation);
-                })
-                .unwrap();
+        if let Some(target) = self.target {
+            if entities.has_entity(&target) {
+                entities
+                    .borrow_component_mut::<Model>(&target)
+                    .map(|m| {
+                        let r = 1.0;
+                        let w = 1.0;
+                        let t = time.as_secs() as f32 + time.subsec_nanos() as f32 * 1e-9;
+                        let translation = self.target_position
+                            + Vector3::new(0.0, r * (w * t).cos(), r * (w * t).sin());
+                        m.set_translation(translation);
+                    })
+                    .unwrap();
+            } else {
+                // The target was destroyed, thus the handle must not be used anymore.
+                self.target = None;
+            }
         }
         (None, None)
     }
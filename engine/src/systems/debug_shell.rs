@@ -1,11 +1,13 @@
 #![allow(dead_code)]
 
-use std::collections::HashMap;
-use std::num::ParseIntError;
+use std::collections::{BTreeMap, HashMap};
+use std::num::{ParseFloatError, ParseIntError};
+use std::time::Duration;
 use clap::{App, AppSettings, Arg};
-use ecs::{Assembly, DispatchEvents, LoopStageFlag, SystemTrait};
+use ecs::{Assembly, DispatchEvents, EcsEvent, Event, EventFilter, LoopStageFlag, SystemTrait};
 use singletons::Singletons;
-use event::{EngineEvent, EngineEventFlag};
+use event::{ConsoleCommand, ReloadShaders, SpawnPrefab, SpeechBubble};
+use components::description::Description;
 
 /// The `DebugShell` listens for `ConsoleCommand` events and interprets them as commands. The shell
 /// provides both builtin commands and the ability to register custom commands through the
@@ -33,12 +35,18 @@ impl DebugShell {
     }
     /// Interprets a set of arguments as a command line (first argument specifies the command
     /// name).
-    fn interpret(&self, args: &[String]) -> ShellResult {
+    fn interpret(&self, entities: &Assembly, aux: &mut Singletons, args: &[String]) -> ShellResult {
         if !args.is_empty() {
             match args[0].as_str() {
                 "help" => self.help(),
+                "components" => self.components(entities, args),
                 "reload-shaders" => self.reload_shaders(),
                 "speech-bubble" => self.speech_bubble(args),
+                "spawn" => self.spawn(args),
+                "time-scale" => self.time_scale(aux, args),
+                "pause" => self.pause(aux, true),
+                "resume" => self.pause(aux, false),
+                "step" => self.step(aux, args),
                 "exit" => self.exit(),
                 n => match self.registry.get(n) {
                     Some(c) => c.run(args),
@@ -55,15 +63,52 @@ impl DebugShell {
             "\
              For more information on a specific command, type COMMAND-NAME --help.\
              \nhelp\tPrints this message.\
+             \ncomponents\tLists the components of an entity.\
              \nreload-shaders\tReloads all OpenGl shaders in use by the engine.\
              \nspeech-bubble\tSends a speech bubble event.\
+             \nspawn\tCreates an entity from a prefab.\
+             \ntime-scale\tDisplays or sets the speed of the simulation.\
+             \npause\tPauses the simulation, while rendering continues.\
+             \nresume\tResumes the simulation.\
+             \nstep\tAdvances the paused simulation by a number of ticks.\
              \nexit\tShuts down the engine."
         );
         Ok((None, None))
     }
+    /// Lists the names of all components of the entity with the specified name.
+    fn components(&self, entities: &Assembly, args: &[String]) -> ShellResult {
+        let matches = App::new("components")
+            .about("Lists the components of the entity with the specified name.")
+            .setting(AppSettings::DisableVersion)
+            .arg(
+                Arg::with_name("name")
+                    .takes_value(true)
+                    .help("Determines the name of the target entity"),
+            )
+            .get_matches_from_safe(args);
+
+        match matches {
+            Ok(m) => {
+                let name = m.value_of("name").ok_or_else(|| {
+                    DebugShellError::MissingArgument(args[0].clone(), "name".into())
+                })?;
+                let (entity, _) = entities
+                    .rsf1::<_, Description>(|&(_, d)| d.name == name)
+                    .map_err(|_| DebugShellError::EntityNotFound(name.into()))?;
+                let names = entities.component_names(&entity).unwrap_or_default();
+
+                println!("{}: {}", entity, names.join(", "));
+                Ok((None, None))
+            }
+            Err(e) => {
+                println!("{}", e);
+                Ok((None, None))
+            }
+        }
+    }
     /// Sends the reload-shaders event to the bus.
     fn reload_shaders(&self) -> ShellResult {
-        Ok((None, Some(vec![EngineEvent::ReloadShaders])))
+        Ok((None, Some(vec![ReloadShaders.into()])))
     }
     /// Sends a speech-bubble event to the bus.
     fn speech_bubble(&self, args: &[String]) -> ShellResult {
@@ -107,7 +152,8 @@ impl DebugShell {
                 Ok((
                     None,
                     Some(vec![
-                        EngineEvent::SpeechBubble(target.into(), text.into(), lifetime),
+                        SpeechBubble(target.into(), text.into(), Duration::from_secs(lifetime))
+                            .into(),
                     ]),
                 ))
             }
@@ -117,13 +163,136 @@ impl DebugShell {
             }
         }
     }
+    /// Sends a spawn-prefab event to the bus.
+    fn spawn(&self, args: &[String]) -> ShellResult {
+        let matches = App::new("spawn")
+            .about("Creates an entity from the specified prefab, with optional overrides.")
+            .setting(AppSettings::DisableVersion)
+            .arg(
+                Arg::with_name("prefab")
+                    .takes_value(true)
+                    .help("Determines the name of the prefab file (without extension)"),
+            )
+            .arg(
+                Arg::with_name("name")
+                    .short("n")
+                    .long("name")
+                    .takes_value(true)
+                    .help("Overrides the name of the entity"),
+            )
+            .arg(
+                Arg::with_name("tooltip")
+                    .short("t")
+                    .long("tooltip")
+                    .takes_value(true)
+                    .help("Overrides the tooltip text of the entity"),
+            )
+            .arg(
+                Arg::with_name("position")
+                    .short("p")
+                    .long("position")
+                    .takes_value(true)
+                    .number_of_values(3)
+                    .allow_hyphen_values(true)
+                    .help("Overrides the position of the entity (x, y and z)"),
+            )
+            .get_matches_from_safe(args);
+
+        match matches {
+            Ok(m) => {
+                let prefab = m.value_of("prefab").ok_or_else(|| {
+                    DebugShellError::MissingArgument(args[0].clone(), "prefab".into())
+                })?;
+
+                let mut overrides = BTreeMap::new();
+                if let Some(n) = m.value_of("name") {
+                    overrides.insert("description".into(), json!({ "name": n }));
+                }
+                if let Some(t) = m.value_of("tooltip") {
+                    overrides.insert("tooltip".into(), json!({ "text": t }));
+                }
+                if let Some(p) = m.values_of("position") {
+                    let position = p.map(|c| c.parse()).collect::<Result<Vec<f32>, _>>()?;
+                    overrides.insert("model".into(), json!({ "translation": position }));
+                }
+
+                Ok((None, Some(vec![SpawnPrefab(prefab.into(), overrides).into()])))
+            }
+            Err(e) => {
+                println!("{}", e);
+                Ok((None, None))
+            }
+        }
+    }
+    /// Displays or sets the factor by which the simulation time is scaled.
+    fn time_scale(&self, aux: &mut Singletons, args: &[String]) -> ShellResult {
+        let matches = App::new("time-scale")
+            .about("Displays or sets the speed of the simulation relative to real time.")
+            .setting(AppSettings::DisableVersion)
+            .arg(
+                Arg::with_name("factor")
+                    .takes_value(true)
+                    .help("Determines the new time scale (e.g. 0.5 for half speed)"),
+            )
+            .get_matches_from_safe(args);
+
+        match matches {
+            Ok(m) => {
+                match m.value_of("factor") {
+                    Some(f) => aux.time_control.set_time_scale(f.parse()?),
+                    None => println!("{}", aux.time_control.time_scale()),
+                }
+                Ok((None, None))
+            }
+            Err(e) => {
+                println!("{}", e);
+                Ok((None, None))
+            }
+        }
+    }
+    /// Pauses or resumes the simulation.
+    fn pause(&self, aux: &mut Singletons, paused: bool) -> ShellResult {
+        aux.time_control.set_paused(paused);
+        Ok((None, None))
+    }
+    /// Pauses the simulation and advances it by the specified number of ticks.
+    fn step(&self, aux: &mut Singletons, args: &[String]) -> ShellResult {
+        let matches = App::new("step")
+            .about("Pauses the simulation and advances it by the specified number of ticks.")
+            .setting(AppSettings::DisableVersion)
+            .arg(
+                Arg::with_name("ticks")
+                    .takes_value(true)
+                    .default_value("1")
+                    .help("Determines the number of fixed updates to perform"),
+            )
+            .get_matches_from_safe(args);
+
+        match matches {
+            Ok(m) => {
+                let ticks: u32 = m.value_of("ticks")
+                    .ok_or_else(|| {
+                        DebugShellError::MissingArgument(args[0].clone(), "ticks".into())
+                    })
+                    .and_then(|s| s.parse().map_err(From::from))?;
+
+                aux.time_control.set_paused(true);
+                aux.time_control.request_steps(ticks);
+                Ok((None, None))
+            }
+            Err(e) => {
+                println!("{}", e);
+                Ok((None, None))
+            }
+        }
+    }
     /// Sends the shutdown event to the bus to exit the engine.
     fn exit(&self) -> ShellResult {
-        Ok((None, Some(vec![EngineEvent::Shutdown])))
+        Ok((None, Some(vec![EcsEvent::Shutdown.into()])))
     }
 }
 
-impl SystemTrait<EngineEvent, Singletons> for DebugShell {
+impl SystemTrait<Singletons> for DebugShell {
     /// `DebugShell` has no requirements wrt. the `Assembly`.
     fn verify_requirements(&self, _: &Assembly) -> bool {
         true
@@ -132,24 +301,28 @@ impl SystemTrait<EngineEvent, Singletons> for DebugShell {
     fn get_loop_stage_filter(&self) -> LoopStageFlag {
         LoopStageFlag::HANDLE_EVENT
     }
+    /// `DebugShell` is labeled `"debug_shell"`.
+    fn get_label(&self) -> Option<&'static str> {
+        Some("debug_shell")
+    }
     /// `DebugShell` subscribes to the `ConsoleCommand` event.
-    fn get_event_filter(&self) -> EngineEventFlag {
-        EngineEventFlag::CONSOLE_COMMAND
+    fn get_event_filter(&self) -> EventFilter {
+        EventFilter::new().with::<ConsoleCommand>()
     }
     /// Interprets a `ConsoleCommand` event as a command to the engine and executes the respective
     /// actions, while printing the output to the console.
     fn handle_event(
         &mut self,
-        _: &mut Assembly,
-        _: &mut Singletons,
-        event: &EngineEvent,
-    ) -> DispatchEvents<EngineEvent> {
-        match *event {
-            EngineEvent::ConsoleCommand(ref c) => self.interpret(c).unwrap_or_else(|e| {
+        entities: &mut Assembly,
+        aux: &mut Singletons,
+        event: &Event,
+    ) -> DispatchEvents {
+        match event.downcast_ref::<ConsoleCommand>() {
+            Some(&ConsoleCommand(ref c)) => self.interpret(entities, aux, c).unwrap_or_else(|e| {
                 println!("{}", e);
                 (None, None)
             }),
-            _ => (None, None),
+            None => (None, None),
         }
     }
 }
@@ -157,9 +330,11 @@ impl SystemTrait<EngineEvent, Singletons> for DebugShell {
 #[derive(Debug, Fail)]
 pub enum DebugShellError {
     #[fail(display = "'{}' is not a recognized builtin or command", _0)] CommandNotFound(String),
+    #[fail(display = "No entity named '{}' was found", _0)] EntityNotFound(String),
     #[fail(display = "The required argument '{}' is missing for command '{}'", _1, _0)]
     MissingArgument(String, String),
     #[fail(display = "{}", _0)] ParseError(#[cause] ParseIntError),
+    #[fail(display = "{}", _0)] ParseFloatError(#[cause] ParseFloatError),
 }
 
 impl From<ParseIntError> for DebugShellError {
@@ -168,7 +343,13 @@ impl From<ParseIntError> for DebugShellError {
     }
 }
 
-type ShellResult = Result<DispatchEvents<EngineEvent>, DebugShellError>;
+impl From<ParseFloatError> for DebugShellError {
+    fn from(value: ParseFloatError) -> Self {
+        DebugShellError::ParseFloatError(value)
+    }
+}
+
+type ShellResult = Result<DispatchEvents, DebugShellError>;
 
 /// Represents a custom shell command.
 pub trait CustomCommand {
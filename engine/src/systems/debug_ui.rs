@@ -3,8 +3,7 @@ use std::time::Duration;
 use glium::Display;
 use nalgebra::{Vector2, Vector3};
 use uuid::Uuid;
-use ecs::{Assembly, DispatchEvents, LoopStageFlag, SystemTrait};
-use event::EngineEvent;
+use ecs::{Access, Assembly, DispatchEvents, LoopStageFlag, SystemTrait};
 use singletons::Singletons;
 use common::ui_element::UiElement;
 use common::physics::duration_as_float;
@@ -36,20 +35,29 @@ impl DebugUi {
     }
 }
 
-impl SystemTrait<EngineEvent, Singletons> for DebugUi {
+impl SystemTrait<Singletons> for DebugUi {
     fn verify_requirements(&self, _: &Assembly) -> bool {
         true
     }
     fn get_loop_stage_filter(&self) -> LoopStageFlag {
         LoopStageFlag::DYNAMIC_UPDATE
     }
+    /// `DebugUi` is labeled `"debug_ui"`.
+    fn get_label(&self) -> Option<&'static str> {
+        Some("debug_ui")
+    }
+    /// `DebugUi` claims exclusive access, because it creates GPU resources via the
+    /// `ComponentFactory` and the `UiState`, neither of which may be shared between threads.
+    fn get_access(&self) -> Access {
+        Access::exclusive()
+    }
     fn dynamic_update(
         &mut self,
         entities: &mut Assembly,
         aux: &mut Singletons,
         time: &Duration,
         delta_time: &Duration,
-    ) -> DispatchEvents<EngineEvent> {
+    ) -> DispatchEvents {
         // Update the buffer of past delta time values.
         self.dt_history.push_back(*delta_time);
         while self.dt_history.len() > self.window_size {
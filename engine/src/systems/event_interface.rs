@@ -1,21 +1,33 @@
+use std::mem;
 use std::time::Duration;
-use nalgebra::Point2;
-use glium::glutin::{Event, EventsLoop, WindowEvent};
-use ecs::{Assembly, DispatchEvents, LoopStageFlag, SystemTrait};
+use glium::glutin::{Event, EventsLoop};
+use ecs::{Access, Assembly, DispatchEvents, LoopStageFlag, SystemTrait};
 use singletons::Singletons;
-use event::EngineEvent;
+use common::input::{InputEvent, InputRecorder, InputReplay};
 
 /// The task of the `EventInterface` is to regularly poll for events from the operating system and
 /// graphical backend. Any events of interest are then sent off to the event bus of `World`.
+/// Window events are relayed right away, whereas the inputs that drive the simulation enter the
+/// `World` at the next fixed-step update. Alternatively, these inputs may be fed from a recording,
+/// such that sessions can be reproduced deterministically.
 pub struct EventInterface {
-    /// Provides access to the `EventsLoop`.
-    pub events_loop: EventsLoop,
+    /// Provides access to the `EventsLoop`, unless the `EventInterface` runs without a window.
+    pub events_loop: Option<EventsLoop>,
+    /// If present, simulation inputs are taken from the replay instead of the `EventsLoop`.
+    replay: Option<InputReplay>,
+    /// If present, all simulation inputs are recorded.
+    recorder: Option<InputRecorder>,
+    /// Holds the simulation inputs polled since the last fixed-step update.
+    pending: Vec<InputEvent>,
 }
 
 impl Default for EventInterface {
     fn default() -> Self {
         EventInterface {
-            events_loop: EventsLoop::new(),
+            events_loop: Some(EventsLoop::new()),
+            replay: None,
+            recorder: None,
+            pending: Vec::new(),
         }
     }
 }
@@ -25,58 +37,182 @@ impl EventInterface {
     pub fn new() -> Self {
         Default::default()
     }
+    /// Creates an `EventInterface` without a window that feeds the specified replay to the
+    /// `World`.
+    pub fn headless(replay: InputReplay) -> Self {
+        EventInterface {
+            events_loop: None,
+            replay: Some(replay),
+            recorder: None,
+            pending: Vec::new(),
+        }
+    }
+    /// Replaces the simulation inputs of the operating system with the specified replay. Window
+    /// events are still relayed.
+    pub fn with_replay(mut self, replay: InputReplay) -> Self {
+        self.replay = Some(replay);
+        self
+    }
+    /// Records all simulation inputs with the specified recorder.
+    pub fn with_recorder(mut self, recorder: InputRecorder) -> Self {
+        self.recorder = Some(recorder);
+        self
+    }
 }
 
-impl SystemTrait<EngineEvent, Singletons> for EventInterface {
+impl SystemTrait<Singletons> for EventInterface {
     /// `EventInterface` does not have any requirements wrt. to the `Assembly`.
     fn verify_requirements(&self, _: &Assembly) -> bool {
         true
     }
-    /// `EventInterface` subscribes to the update call.
+    /// `EventInterface` subscribes to the update and dynamic update calls.
     fn get_loop_stage_filter(&self) -> LoopStageFlag {
-        LoopStageFlag::DYNAMIC_UPDATE
+        LoopStageFlag::UPDATE | LoopStageFlag::DYNAMIC_UPDATE
     }
-    /// Polls for operating system events and relays them to the ECS event queue.
-    fn dynamic_update(
+    /// `EventInterface` is labeled `"event_interface"`.
+    fn get_label(&self) -> Option<&'static str> {
+        Some("event_interface")
+    }
+    /// `EventInterface` runs after all other systems of the dynamic update stage, such that the
+    /// events it polls are handled as early as possible.
+    fn get_after(&self) -> &'static [&'static str] {
+        &["debug_console", "debug_ui", "speech_bubble_controller"]
+    }
+    /// `EventInterface` does not access any components. As it owns the `EventsLoop`, it is always
+    /// called on the current thread.
+    fn get_access(&self) -> Access {
+        Access::new()
+    }
+    /// Relays the simulation inputs of the current tick to the ECS event queue, either from the
+    /// replay or from the events polled since the last update. Each input is recorded along
+    /// with the tick, if a recorder is attached.
+    fn update(
         &mut self,
-        _: &mut Assembly,
+        entities: &mut Assembly,
         _: &mut Singletons,
         _: &Duration,
         _: &Duration,
-    ) -> DispatchEvents<EngineEvent> {
+    ) -> DispatchEvents {
+        let tick = entities.timers().ticks();
+        let inputs = match self.replay {
+            Some(ref mut replay) => replay.poll(tick),
+            None => mem::take(&mut self.pending),
+        };
+
         let mut pd = Vec::new();
         let mut d = Vec::new();
-
-        self.events_loop.poll_events(|ge| {
-            if let Event::WindowEvent { event: we, .. } = ge {
-                match we {
-                    WindowEvent::Closed => d.push(EngineEvent::Shutdown),
-                    WindowEvent::Resized(w, h) => d.push(EngineEvent::ResizeWindow(w, h)),
-                    WindowEvent::CursorMoved {
-                        position: (x, y), ..
-                    } => {
-                        // Convert the coordinates to pixels.
-                        let x = x.floor() as u32;
-                        let y = y.floor() as u32;
-
-                        // Dispatch the cursor movement event.
-                        pd.push(EngineEvent::CursorPosition(Point2::new(x, y)));
-                    }
-                    WindowEvent::MouseInput {
-                        state: s,
-                        button: b,
-                        ..
-                    } => {
-                        // Dispatch the mouse input event.
-                        pd.push(EngineEvent::MouseInput(b, s));
-                    }
-                    _ => (),
-                }
+        for input in inputs {
+            let recorded = self.recorder
+                .as_mut()
+                .map_or(Ok(()), |r| r.record(tick, &input));
+            if let Err(e) = recorded {
+                warn!("Stopped recording the input events: {}", e);
+                self.recorder = None;
             }
-        });
+            if input.is_priority() {
+                pd.push(input.into_event());
+            } else {
+                d.push(input.into_event());
+            }
+        }
 
         let pd = if pd.is_empty() { None } else { Some(pd) };
         let d = if d.is_empty() { None } else { Some(d) };
         (pd, d)
     }
+    /// Polls for events from the operating system. Window events are relayed to the ECS event
+    /// queue right away, whereas simulation inputs are held back until the next update. While
+    /// replaying, the simulation inputs of the operating system are ignored.
+    fn dynamic_update(
+        &mut self,
+        _: &mut Assembly,
+        _: &mut Singletons,
+        _: &Duration,
+        _: &Duration,
+    ) -> DispatchEvents {
+        let mut inputs = Vec::new();
+        if let Some(ref mut events_loop) = self.events_loop {
+            events_loop.poll_events(|ge| {
+                if let Event::WindowEvent { event: we, .. } = ge {
+                    inputs.extend(InputEvent::from_window_event(we));
+                }
+            });
+        }
+
+        let mut d = Vec::new();
+        for input in inputs {
+            if !input.is_priority() {
+                d.push(input.into_event());
+            } else if self.replay.is_none() {
+                self.pending.push(input);
+            }
+        }
+
+        let d = if d.is_empty() { None } else { Some(d) };
+        (None, d)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use nalgebra::Point2;
+    use glium::glutin::{ElementState, MouseButton};
+    use common::input::InputRecord;
+    use components::cursor::Cursor;
+    use orchestrator::Orchestrator;
+    use orchestrator::test::orchestrator;
+    use systems::cursor_controller::CursorController;
+
+    #[test]
+    fn replay_through_orchestrator() {
+        let replay = InputReplay::new(vec![
+            InputRecord {
+                tick: 2,
+                event: InputEvent::CursorMoved(10, 20),
+            },
+            InputRecord {
+                tick: 2,
+                event: InputEvent::MouseInput(MouseButton::Left, ElementState::Pressed),
+            },
+            InputRecord {
+                tick: 4,
+                event: InputEvent::CursorMoved(30, 40),
+            },
+            InputRecord {
+                tick: 5,
+                event: InputEvent::Close,
+            },
+        ]);
+        let mut o = orchestrator();
+        let mut cursor = None;
+        o.initialize(|o| {
+            let e = o.world.create_entity();
+            o.world.add_component(&e, Cursor::new()).unwrap();
+            o.add_system(CursorController::new()).unwrap();
+            o.add_system(EventInterface::headless(replay)).unwrap();
+            cursor = Some(e);
+        });
+        let cursor = cursor.unwrap();
+        let position =
+            |o: &Orchestrator| o.world.borrow_component::<Cursor>(&cursor).unwrap().position;
+
+        assert!(o.step(1));
+        assert_eq!(position(&o), Point2::new(0, 0));
+        assert!(o.step(1));
+        assert_eq!(position(&o), Point2::new(10, 20));
+        assert_eq!(
+            o.world.borrow_component::<Cursor>(&cursor).unwrap().buttons[&MouseButton::Left],
+            ElementState::Pressed
+        );
+        assert!(o.step(1));
+        assert_eq!(position(&o), Point2::new(10, 20));
+        assert!(o.step(1));
+        assert_eq!(position(&o), Point2::new(30, 40));
+
+        // The recorded window close is handled at the end of the fifth frame, and the `World`
+        // shuts down one frame later.
+        assert!(!o.step(3));
+        assert_eq!(o.game_time(), Duration::from_millis(300));
+    }
 }
@@ -1,8 +1,10 @@
-use ecs::{Assembly, DispatchEvents, LoopStageFlag, SystemTrait};
-use event::{EngineEvent, EngineEventFlag};
+use std::time::Duration;
+use ecs::{Access, Assembly, DispatchEvents, Event, EventFilter, LoopStageFlag, SystemTrait};
+use event::CursorPosition;
 use singletons::Singletons;
 
-/// Defines a system that logs all events on the bus to the console (log level TRACE).
+/// Defines a system that logs all events on the bus to the console (log level TRACE), along with
+/// the number of events dispatched per type during each frame.
 #[derive(Default)]
 pub struct EventMonitor;
 
@@ -13,27 +15,56 @@ impl EventMonitor {
     }
 }
 
-impl SystemTrait<EngineEvent, Singletons> for EventMonitor {
+impl SystemTrait<Singletons> for EventMonitor {
     /// `EventMonitor` has no requirements wrt. the `Assembly`.
     fn verify_requirements(&self, _: &Assembly) -> bool {
         true
     }
-    /// `EventMonitor` subscribes to the `handle_event` call.
+    /// `EventMonitor` subscribes to the `handle_event` and dynamic update calls.
     fn get_loop_stage_filter(&self) -> LoopStageFlag {
-        LoopStageFlag::HANDLE_EVENT
+        LoopStageFlag::HANDLE_EVENT | LoopStageFlag::DYNAMIC_UPDATE
+    }
+    /// `EventMonitor` is labeled `"event_monitor"`.
+    fn get_label(&self) -> Option<&'static str> {
+        Some("event_monitor")
+    }
+    /// `EventMonitor` only reads the event statistics.
+    fn get_access(&self) -> Access {
+        Access::new()
+    }
+    /// `EventMonitor` may be called on another thread.
+    fn as_send(&mut self) -> Option<&mut (dyn SystemTrait<Singletons> + Send)> {
+        Some(self)
     }
     /// `EventMonitor` subscribes to all events except for very frequent events.
-    fn get_event_filter(&self) -> EngineEventFlag {
-        EngineEventFlag::ALL_EVENTS & !EngineEventFlag::CURSOR_POSITION
+    fn get_event_filter(&self) -> EventFilter {
+        EventFilter::all().without::<CursorPosition>()
     }
     /// Issues a logging call (TRACE level) for each received event.
     fn handle_event(
         &mut self,
         _: &mut Assembly,
         _: &mut Singletons,
-        event: &EngineEvent,
-    ) -> DispatchEvents<EngineEvent> {
+        event: &Event,
+    ) -> DispatchEvents {
         trace!("Received event '{:?}'", event);
         (None, None)
     }
+    /// Issues a logging call (TRACE level) with the event counts of the previous frame.
+    fn dynamic_update(
+        &mut self,
+        entities: &mut Assembly,
+        _: &mut Singletons,
+        _: &Duration,
+        _: &Duration,
+    ) -> DispatchEvents {
+        let statistics = entities.event_statistics();
+        for (name, count) in statistics.counts() {
+            trace!("Dispatched {} event(s) of type '{}'", count, name);
+        }
+        for error in statistics.errors() {
+            trace!("Dispatching failed: {}", error);
+        }
+        (None, None)
+    }
 }
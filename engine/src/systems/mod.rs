@@ -8,12 +8,14 @@ pub mod event_interface;
 pub mod renderer;
 pub mod tooltip_controller;
 pub mod speech_bubble_controller;
+pub mod prefab_spawner;
+pub mod transform_propagator;
+pub mod physics_updater;
 
-use event::{EngineEvent, EngineEventFlag};
 use singletons::Singletons;
 
 impl_system_group! {
-    pub enum SystemGroup<EngineEvent, EngineEventFlag, Singletons> {
+    pub enum SystemGroup<Singletons> {
         CursorControllerSys(cursor_controller::CursorController),
         DebugConsoleSys(debug_console::DebugConsole),
         DebugMoverSys(debug_mover::DebugMover),
@@ -24,5 +26,8 @@ impl_system_group! {
         RendererSys(renderer::Renderer),
         TooltipControllerSys(tooltip_controller::TooltipController),
         SpeechBubbleControllerSys(speech_bubble_controller::SpeechBubbleController),
+        PrefabSpawnerSys(prefab_spawner::PrefabSpawner),
+        TransformPropagatorSys(transform_propagator::TransformPropagator),
+        PhysicsUpdaterSys(physics_updater::PhysicsUpdater),
     }
 }
@@ -0,0 +1,116 @@
+use std::time::Duration;
+use ecs::{Access, Assembly, DispatchEvents, LoopStageFlag, SystemTrait};
+use singletons::Singletons;
+use common::physics::PhysicsController;
+use components::bounding_volume::BoundingVolume;
+use components::global_transform::GlobalTransform;
+
+/// The `PhysicsUpdater` keeps the bounding volume hierarchy of the `PhysicsController` in sync
+/// with the `GlobalTransform` and `BoundingVolume` components. It runs in every frame, such
+/// that despawned entities are removed even if no ray cast happens in the meantime.
+#[derive(Default)]
+pub struct PhysicsUpdater;
+
+impl PhysicsUpdater {
+    /// Creates a new `PhysicsUpdater`.
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl SystemTrait<Singletons> for PhysicsUpdater {
+    /// `PhysicsUpdater` has no requirements wrt. the `Assembly`.
+    fn verify_requirements(&self, _: &Assembly) -> bool {
+        true
+    }
+    /// `PhysicsUpdater` subscribes to the update and dynamic update calls.
+    fn get_loop_stage_filter(&self) -> LoopStageFlag {
+        LoopStageFlag::UPDATE | LoopStageFlag::DYNAMIC_UPDATE
+    }
+    /// `PhysicsUpdater` is labeled `"physics_updater"`.
+    fn get_label(&self) -> Option<&'static str> {
+        Some("physics_updater")
+    }
+    /// `PhysicsUpdater` refits after the global transforms were propagated.
+    fn get_after(&self) -> &'static [&'static str] {
+        &["transform_propagator"]
+    }
+    /// `PhysicsUpdater` reads the `GlobalTransform`s and `BoundingVolume`s and owns the
+    /// `PhysicsController`.
+    fn get_access(&self) -> Access {
+        Access::new()
+            .read::<GlobalTransform>()
+            .read::<BoundingVolume>()
+            .aux::<PhysicsController>()
+    }
+    /// `PhysicsUpdater` may be called on another thread.
+    fn as_send(&mut self) -> Option<&mut (dyn SystemTrait<Singletons> + Send)> {
+        Some(self)
+    }
+    /// Refits the bounding volume hierarchy after each fixed update.
+    fn update(
+        &mut self,
+        entities: &mut Assembly,
+        aux: &mut Singletons,
+        _: &Duration,
+        _: &Duration,
+    ) -> DispatchEvents {
+        aux.physics.refit(entities);
+        (None, None)
+    }
+    /// Refits the bounding volume hierarchy once per frame, e.g. while the simulation is paused.
+    fn dynamic_update(
+        &mut self,
+        entities: &mut Assembly,
+        aux: &mut Singletons,
+        _: &Duration,
+        _: &Duration,
+    ) -> DispatchEvents {
+        aux.physics.refit(entities);
+        (None, None)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use nalgebra::{Point3, Unit, Vector3};
+    use common::ray::Ray;
+    use orchestrator::test::{orchestrator, spawn};
+    use systems::transform_propagator::TransformPropagator;
+
+    fn ray(x: f32) -> Ray<f32> {
+        Ray::new(
+            Point3::new(x, 0.0, 5.0),
+            Unit::new_normalize(Vector3::new(0.0, 0.0, -1.0)),
+        )
+    }
+
+    #[test]
+    fn remove_despawned_entities() {
+        let mut o = orchestrator();
+        o.initialize(|o| {
+            o.add_system(TransformPropagator::new()).unwrap();
+            o.add_system(PhysicsUpdater::new()).unwrap();
+        });
+        let e = spawn(&mut o, 0.0);
+        let f = spawn(&mut o, 3.0);
+        o.step(1);
+        assert!(o.world.aux.physics.contains(&e));
+        assert_eq!(
+            o.world.aux.physics.raycast(&o.world, &ray(0.0)).map(|h| h.target),
+            Some(e)
+        );
+
+        // Despawn the entity and let several frames pass without any ray cast.
+        o.world.destroy_entity(&e);
+        o.step(3);
+        assert!(!o.world.aux.physics.contains(&e));
+        assert!(o.world.aux.physics.contains(&f));
+        assert!(o.world.aux.physics.raycast(&o.world, &ray(0.0)).is_none());
+        assert_eq!(
+            o.world.aux.physics.raycast(&o.world, &ray(3.0)).map(|h| h.target),
+            Some(f)
+        );
+    }
+}
@@ -0,0 +1,75 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use glium::Display;
+use serde_json::Value;
+use ecs::{Assembly, ComponentRegistry, DispatchEvents, EcsError, Entity, Event, EventFilter,
+          LoopStageFlag, SystemTrait};
+use event::SpawnPrefab;
+use singletons::Singletons;
+use common::scene::{load_prefab, scene_registry, SceneAssets};
+
+/// The `PrefabSpawner` creates entities from prefab files in the `prefabs` directory of the
+/// resource tree upon receiving `SpawnPrefab` events.
+pub struct PrefabSpawner {
+    display: Display,
+    resource_path: PathBuf,
+    registry: ComponentRegistry<SceneAssets>,
+}
+
+impl PrefabSpawner {
+    /// Creates a new `PrefabSpawner` for the specified resource tree.
+    pub fn new(display: &Display, resource_path: &Path) -> Self {
+        PrefabSpawner {
+            display: display.clone(),
+            resource_path: resource_path.to_owned(),
+            registry: scene_registry(resource_path),
+        }
+    }
+    /// Instantiates the named prefab with the specified overrides.
+    fn spawn(
+        &self,
+        entities: &mut Assembly,
+        aux: &mut Singletons,
+        name: &str,
+        overrides: &BTreeMap<String, Value>,
+    ) -> Result<Entity, EcsError> {
+        let prefab = load_prefab(&self.resource_path, name)?.with_overrides(overrides);
+        let mut assets = SceneAssets::take(&self.display, &mut aux.factory);
+        let result = prefab.instantiate(entities, &self.registry, &mut assets);
+        assets.restore(&mut aux.factory);
+        result
+    }
+}
+
+impl SystemTrait<Singletons> for PrefabSpawner {
+    /// `PrefabSpawner` has no requirements wrt. the `Assembly`.
+    fn verify_requirements(&self, _: &Assembly) -> bool {
+        true
+    }
+    /// `PrefabSpawner` subscribes to the `handle_event` call.
+    fn get_loop_stage_filter(&self) -> LoopStageFlag {
+        LoopStageFlag::HANDLE_EVENT
+    }
+    /// `PrefabSpawner` is labeled `"prefab_spawner"`.
+    fn get_label(&self) -> Option<&'static str> {
+        Some("prefab_spawner")
+    }
+    /// `PrefabSpawner` subscribes to the `SpawnPrefab` event.
+    fn get_event_filter(&self) -> EventFilter {
+        EventFilter::new().with::<SpawnPrefab>()
+    }
+    fn handle_event(
+        &mut self,
+        entities: &mut Assembly,
+        aux: &mut Singletons,
+        event: &Event,
+    ) -> DispatchEvents {
+        if let Some(&SpawnPrefab(ref name, ref overrides)) = event.downcast_ref::<SpawnPrefab>() {
+            match self.spawn(entities, aux, name, overrides) {
+                Ok(e) => debug!("Spawned the prefab '{}' as {}", name, e),
+                Err(e) => warn!("Could not spawn the prefab '{}': {}", name, e),
+            }
+        }
+        (None, None)
+    }
+}
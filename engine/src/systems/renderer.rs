@@ -6,13 +6,15 @@ use glium::{Display, DrawParameters, Frame, Surface};
 use glium::backend::glutin::DisplayCreationError;
 use glium::glutin::{Api, ContextBuilder, EventsLoop, GlProfile, GlRequest, WindowBuilder};
 use nalgebra::Vector3;
-use ecs::{Assembly, DispatchEvents, LoopStageFlag, SystemTrait};
-use event::{EngineEvent, EngineEventFlag};
+use ecs::{Assembly, DispatchEvents, EcsEvent, Event, EventFilter, LoopStageFlag, SystemTrait};
+use event::{RendererReady, ResizeWindow};
 use singletons::Singletons;
 use components::camera::Camera;
+use components::global_transform::GlobalTransform;
 use components::material::Material;
 use components::mesh::Mesh;
 use components::model::Model;
+use components::previous_model::PreviousModel;
 use components::render_mode::RenderMode;
 use common::uniforms::Uniforms;
 use components::ui_state::UiState;
@@ -124,22 +126,19 @@ impl Renderer {
             .map(|(_, u)| {
                 // Update the UI scene graph.
                 aux.ui_hierarchy
-                    .update(&|id, parent_model| {
-                        let current_element = u.elements.get(id)?;
-                        Some(parent_model * &current_element.model)
-                    })
+                    .update(&|parent_model, model| parent_model * model)
                     .expect("Unable to update the UI scene graph.");
 
                 // Sort the UI scene graph nodes.
                 let mut nodes = aux.ui_hierarchy.iter().collect::<Vec<_>>();
-                nodes.sort_unstable_by_key(|n| (n.data.translation().z / f32::EPSILON).round() as i32);
+                nodes.sort_unstable_by_key(|n| (n.global.translation().z / f32::EPSILON).round() as i32);
 
                 // Render all UI elements.
                 for node in nodes {
                     if let Some(e) = u.elements.get(&node.key) {
                         for p in &e.primitives {
                             let uniforms = UiUniforms {
-                                pvm_matrix: node.data.matrix() * p.model.matrix(),
+                                pvm_matrix: node.global.matrix() * p.model.matrix(),
                                 font_cache: &u.font_cache.gpu,
                                 font_color: p.text_color,
                                 diff_tex: p.material.diff_tex.as_ref().map(|dt| dt.borrow()),
@@ -163,7 +162,7 @@ impl Renderer {
     }
 }
 
-impl SystemTrait<EngineEvent, Singletons> for Renderer {
+impl SystemTrait<Singletons> for Renderer {
     /// The `Renderer` depends on the presence of exactly one `Camera` component and one `UiState`
     /// component.
     fn verify_requirements(&self, entities: &Assembly) -> bool {
@@ -178,9 +177,25 @@ impl SystemTrait<EngineEvent, Singletons> for Renderer {
             LoopStageFlag::HANDLE_EVENT
         }
     }
+    /// `Renderer` is labeled `"renderer"`.
+    fn get_label(&self) -> Option<&'static str> {
+        Some("renderer")
+    }
+    /// `Renderer` runs after the systems that manipulate the scene and the user interface, such
+    /// that each frame shows their latest state regardless of the order of registration.
+    fn get_after(&self) -> &'static [&'static str] {
+        &[
+            "cursor_controller",
+            "debug_ui",
+            "prefab_spawner",
+            "speech_bubble_controller",
+            "tooltip_controller",
+            "transform_propagator",
+        ]
+    }
     /// `Renderer` subscribes to the `Ready` and `ResizeWindow` events.
-    fn get_event_filter(&self) -> EngineEventFlag {
-        EngineEventFlag::READY | EngineEventFlag::RESIZE_WINDOW
+    fn get_event_filter(&self) -> EventFilter {
+        EventFilter::new().with::<EcsEvent>().with::<ResizeWindow>()
     }
     /// Once the `Ready` event has been received, the `Renderer` completes its initialization and
     /// emits a `RendererReady` event. Upon receiving a `ResizeWindow` event, the `Camera`
@@ -189,61 +204,61 @@ impl SystemTrait<EngineEvent, Singletons> for Renderer {
         &mut self,
         entities: &mut Assembly,
         _: &mut Singletons,
-        event: &EngineEvent,
-    ) -> DispatchEvents<EngineEvent> {
-        match *event {
-            EngineEvent::Ready => {
-                self.ready = true;
-                (None, Some(vec![EngineEvent::RendererReady]))
-            }
-            EngineEvent::ResizeWindow(w, h) => {
-                entities
-                    .ws1::<Camera>()
-                    .map(|(_, c)| c.set_dimensions([w, h]))
-                    .expect("Unable to update the projection matrices.");
-                (None, None)
-            }
-            _ => (None, None),
+        event: &Event,
+    ) -> DispatchEvents {
+        if event.as_ecs_event() == Some(&EcsEvent::Ready) {
+            self.ready = true;
+            (None, Some(vec![RendererReady.into()]))
+        } else if let Some(&ResizeWindow(w, h)) = event.downcast_ref::<ResizeWindow>() {
+            entities
+                .ws1::<Camera>()
+                .map(|(_, c)| c.set_dimensions([w, h]))
+                .expect("Unable to update the projection matrices.");
+            (None, None)
+        } else {
+            (None, None)
         }
     }
-    /// First updates the `Hierarchy` to receive accurate and current hierarchical model data.
-    /// Subsequently renders the `Entity`s to the frame, followed by the user interface state as
-    /// defined in `UiState`.
-    fn render(&mut self, entities: &Assembly, aux: &mut Singletons, _: &Duration, _: &Duration) {
+    /// Renders the `Entity`s to the frame, where each `GlobalTransform` is interpolated from its
+    /// `PreviousModel` by `alpha`, followed by the user interface state as defined in `UiState`.
+    fn render(
+        &mut self,
+        entities: &Assembly,
+        _: &mut Singletons,
+        _: &Duration,
+        _: &Duration,
+        alpha: f32,
+    ) {
         // Create the current frame.
         let mut target = self.display.draw();
         target.clear_color_and_depth(self.clear_color, 1.0);
 
-        // Update the scene graph.
-        aux.scene_graph
-            .update(&|entity, parent_model| {
-                let current_model = entities
-                    .borrow_component(entity)
-                    .ok()?;
-                Some(parent_model * current_model)
-            })
-            .expect("Unable to update the scene graph");
-
         // Get a reference to the camera.
         let (_, camera) = entities.rs1::<Camera>().expect("Could not access the camera component.");
 
         // Get a reference to the UI state.
         let (_, ui_state) = entities.rs1::<UiState>().expect("Could not access the UI state component.");
 
-        // Sort the nodes according to their z-value.
-        let mut nodes = aux.scene_graph.iter().collect::<Vec<_>>();
-        nodes.sort_unstable_by_key(|n| (n.data.translation().z / f32::EPSILON).round() as i32);
+        // Interpolate the global transforms and sort them according to their z-value.
+        let mut nodes = entities
+            .r1_iter::<GlobalTransform>()
+            .map(|(e, g)| match entities.borrow_component::<PreviousModel>(&e) {
+                Ok(p) => (e, p.0.interpolate(&g.0, alpha)),
+                Err(_) => (e, g.0.clone()),
+            })
+            .collect::<Vec<_>>();
+        nodes.sort_unstable_by_key(|&(_, ref m)| (m.translation().z / f32::EPSILON).round() as i32);
 
         // Render all entities
-        for node in nodes {
-            if entities.has_component::<Mesh>(&node.key) && entities.has_component::<Material>(&node.key) && entities.has_component::<RenderMode>(&node.key) {
-                let mesh = entities.borrow_component::<Mesh>(&node.key).unwrap();
-                let material = entities.borrow_component::<Material>(&node.key).unwrap();
-                let render_mode = entities.borrow_component::<RenderMode>(&node.key).unwrap();
+        for (entity, model) in nodes {
+            if entities.has_component::<Mesh>(&entity) && entities.has_component::<Material>(&entity) && entities.has_component::<RenderMode>(&entity) {
+                let mesh = entities.borrow_component::<Mesh>(&entity).unwrap();
+                let material = entities.borrow_component::<Material>(&entity).unwrap();
+                let render_mode = entities.borrow_component::<RenderMode>(&entity).unwrap();
 
                 match render_mode {
-                    &RenderMode::World => self.render_world_entity(&mut target, camera, &node.data, mesh, material, &self.draw_params),
-                    &RenderMode::Ui => self.render_ui_entity(&mut target, ui_state, &node.data, mesh, material, &self.draw_params),
+                    &RenderMode::World => self.render_world_entity(&mut target, camera, &model, mesh, material, &self.draw_params),
+                    &RenderMode::Ui => self.render_ui_entity(&mut target, ui_state, &model, mesh, material, &self.draw_params),
                 }
             }
         }
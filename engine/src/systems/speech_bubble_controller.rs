@@ -1,21 +1,18 @@
-use std::collections::HashMap;
-use std::time::{Duration, Instant};
+use std::time::Duration;
 use glium::Display;
 use nalgebra::{Point3, Vector2};
 use uuid::Uuid;
-use ecs::{Assembly, DispatchEvents, EcsError, LoopStageFlag, SystemTrait};
-use event::{EngineEvent, EngineEventFlag};
+use ecs::{Assembly, DispatchEvents, EcsError, Event, EventFilter, LoopStageFlag, SystemTrait};
+use event::{SpeechBubble, SpeechBubbleExpired};
 use singletons::Singletons;
 use common::ui_element::{UiElement, UiElementError as RootUiElementError};
 use components::camera::Camera;
 use components::description::Description;
-use components::model::Model;
+use components::global_transform::GlobalTransform;
 use components::ui_state::UiState;
 
 pub struct SpeechBubbleController {
     display: Display,
-    /// Each `UiElement` may have a lifetime, after which it is destroyed.
-    lifetimes: HashMap<Uuid, (Instant, Duration)>,
 }
 
 impl SpeechBubbleController {
@@ -23,22 +20,22 @@ impl SpeechBubbleController {
     pub fn new(display: &Display) -> Self {
         SpeechBubbleController {
             display: display.clone(),
-            lifetimes: HashMap::new(),
         }
     }
-    /// Creates a new speech-bubble `UiElement` and attaches it to the `UiState`.
+    /// Creates a new speech-bubble `UiElement` and attaches it to the `UiState`. The element is
+    /// destroyed once its lifetime (in game time) has passed.
     fn create_speech_bubble(
         &mut self,
         entities: &mut Assembly,
         aux: &mut Singletons,
         target: &str,
         content: &str,
-        lifetime: u64,
+        lifetime: Duration,
     ) -> Result<(), SpeechBubbleError> {
         // Attempt to find the entity named in `target` and retreive its world position.
         let entity_pos_world = entities
-            .rsf2::<_, Description, Model>(|&(_, d, _)| d.name == target)
-            .map(|(_, _, m)| Point3::from_coordinates(*m.translation()))
+            .rsf2::<_, Description, GlobalTransform>(|&(_, d, _)| d.name == target)
+            .map(|(_, _, g)| Point3::from_coordinates(*g.0.translation()))
             .map_err(|e| SpeechBubbleError::EntityNameNotFound(target.into(), e))?;
 
         // Project the entity position to normalized device coordinates (this requires the camera
@@ -79,75 +76,61 @@ impl SpeechBubbleController {
         let id = Uuid::new_v4();
         aux.ui_hierarchy.insert(id, element.model.clone());
         ui_state.elements.insert(id, element);
-        self.lifetimes
-            .insert(id, (Instant::now(), Duration::new(lifetime, 0)));
+        entities
+            .timers_mut()
+            .map_err(SpeechBubbleError::Timers)?
+            .schedule_after(lifetime, SpeechBubbleExpired(id));
 
         Ok(())
     }
-    /// Checks the lifetimes of the registered `UiElement`s and removes those with expired
-    /// lifetimes.
-    fn update_lifetimes(&mut self, entities: &mut Assembly, aux: &mut Singletons) {
-        if !self.lifetimes.is_empty() {
-            let to_delete = self.lifetimes
-                .iter()
-                .filter(|&(_, l)| l.0.elapsed() >= l.1)
-                .map(|(i, _)| i)
-                .cloned()
-                .collect::<Vec<_>>();
-
-            entities
-                .ws1::<UiState>()
-                .map(|(_, u)| {
-                    to_delete.iter().for_each(|i| {
-                        u.elements.remove(i);
-                        aux.ui_hierarchy.remove(i).unwrap_or_else(|_| unreachable!());
-                        self.lifetimes.remove(i);
-                    });
-                })
-                .expect("Could not access the UiState component")
-        }
+    /// Removes the speech-bubble `UiElement` whose lifetime has expired.
+    fn destroy_speech_bubble(&self, entities: &mut Assembly, aux: &mut Singletons, id: &Uuid) {
+        entities
+            .ws1::<UiState>()
+            .map(|(_, u)| {
+                if u.elements.remove(id).is_some() {
+                    aux.ui_hierarchy.remove(id).unwrap_or_else(|_| unreachable!());
+                }
+            })
+            .expect("Could not access the UiState component")
     }
 }
 
-impl SystemTrait<EngineEvent, Singletons> for SpeechBubbleController {
+impl SystemTrait<Singletons> for SpeechBubbleController {
     /// The `SpeechBubbleController` depends on the presence of exactly one `UiState` and exactly one
     /// `Camera` component.
     fn verify_requirements(&self, entities: &Assembly) -> bool {
         entities.count1::<UiState>() == 1 && entities.count1::<Camera>() == 1
     }
-    /// `SpeechBubbleController` subscribes to the `handle_event` and update calls.
+    /// `SpeechBubbleController` subscribes to the `handle_event` call.
     fn get_loop_stage_filter(&self) -> LoopStageFlag {
-        LoopStageFlag::HANDLE_EVENT | LoopStageFlag::DYNAMIC_UPDATE
+        LoopStageFlag::HANDLE_EVENT
+    }
+    /// `SpeechBubbleController` is labeled `"speech_bubble_controller"`.
+    fn get_label(&self) -> Option<&'static str> {
+        Some("speech_bubble_controller")
     }
-    /// `SpeechBubbleController` subscribes to the `SpeechBubble` event.
-    fn get_event_filter(&self) -> EngineEventFlag {
-        EngineEventFlag::SPEECH_BUBBLE
+    /// `SpeechBubbleController` subscribes to the `SpeechBubble` and `SpeechBubbleExpired`
+    /// events.
+    fn get_event_filter(&self) -> EventFilter {
+        EventFilter::new()
+            .with::<SpeechBubble>()
+            .with::<SpeechBubbleExpired>()
     }
     fn handle_event(
         &mut self,
         entities: &mut Assembly,
         aux: &mut Singletons,
-        event: &EngineEvent,
-    ) -> DispatchEvents<EngineEvent> {
-        match *event {
-            EngineEvent::SpeechBubble(ref t, ref c, l) => {
-                self.create_speech_bubble(entities, aux, t, c, l)
-                    .unwrap_or_else(|e| warn!("Could not create a speech bubble: {}", e))
-            }
-            _ => (),
+        event: &Event,
+    ) -> DispatchEvents {
+        if let Some(&SpeechBubble(ref t, ref c, l)) = event.downcast_ref::<SpeechBubble>() {
+            self.create_speech_bubble(entities, aux, t, c, l)
+                .unwrap_or_else(|e| warn!("Could not create a speech bubble: {}", e))
+        } else if let Some(e) = event.downcast_ref::<SpeechBubbleExpired>() {
+            self.destroy_speech_bubble(entities, aux, &e.0);
         }
         (None, None)
     }
-    fn dynamic_update(
-        &mut self,
-        entities: &mut Assembly,
-        aux: &mut Singletons,
-        _: &Duration,
-        _: &Duration,
-    ) -> DispatchEvents<EngineEvent> {
-        self.update_lifetimes(entities, aux);
-        (None, None)
-    }
 }
 
 #[derive(Debug, Fail)]
@@ -155,6 +138,7 @@ pub enum SpeechBubbleError {
     #[fail(display = "The entity name '{}' could not be uniquely identified.", _0)]
     EntityNameNotFound(String, #[cause] EcsError),
     #[fail(display = "{}", _0)] UiElementError(#[cause] RootUiElementError),
+    #[fail(display = "{}", _0)] Timers(#[cause] EcsError),
 }
 
 impl From<RootUiElementError> for SpeechBubbleError {
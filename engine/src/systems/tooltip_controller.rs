@@ -1,11 +1,12 @@
 use glium::Display;
 use nalgebra::{Point3, Vector2};
 use uuid::Uuid;
-use ecs::{Assembly, DispatchEvents, EcsError, Entity, LoopStageFlag, SystemTrait};
-use event::{EngineEvent, EngineEventFlag};
+use ecs::{Assembly, DispatchEvents, EcsError, Entity, Event, EventFilter, LoopStageFlag,
+          SystemTrait};
+use event::CursorPosition;
 use singletons::Singletons;
 use components::camera::Camera;
-use components::model::Model;
+use components::global_transform::GlobalTransform;
 use components::tooltip::TooltipData;
 use components::ui_state::UiState;
 use common::ui_element::{UiElement, UiElementError as RootUiElementError};
@@ -43,9 +44,11 @@ impl TooltipController {
         {
             // Attempt to determine the location of the entity.
             let entity_pos_world = entities
-                .borrow_component::<Model>(target)
-                .map(|m| Point3::from_coordinates(*m.translation()))
-                .map_err(|e| TooltipError::ComponentNotFound("Model".into(), target.clone(), e))?;
+                .borrow_component::<GlobalTransform>(target)
+                .map(|g| Point3::from_coordinates(*g.0.translation()))
+                .map_err(|e| {
+                    TooltipError::ComponentNotFound("GlobalTransform".into(), target.clone(), e)
+                })?;
 
             // Project the entity position to normalized device coordinates (this requires the camera
             // entity).
@@ -105,7 +108,7 @@ impl TooltipController {
     }
 }
 
-impl SystemTrait<EngineEvent, Singletons> for TooltipController {
+impl SystemTrait<Singletons> for TooltipController {
     /// The `TooltipController` depends on the presence of exactly one `UiState` and exactly one
     /// `Camera` component.
     fn verify_requirements(&self, entities: &Assembly) -> bool {
@@ -115,18 +118,22 @@ impl SystemTrait<EngineEvent, Singletons> for TooltipController {
     fn get_loop_stage_filter(&self) -> LoopStageFlag {
         LoopStageFlag::HANDLE_EVENT
     }
+    /// `TooltipController` is labeled `"tooltip_controller"`.
+    fn get_label(&self) -> Option<&'static str> {
+        Some("tooltip_controller")
+    }
     /// `TooltipController` subscribes to the `CursorPosition` event.
-    fn get_event_filter(&self) -> EngineEventFlag {
-        EngineEventFlag::CURSOR_POSITION
+    fn get_event_filter(&self) -> EventFilter {
+        EventFilter::new().with::<CursorPosition>()
     }
     fn handle_event(
         &mut self,
         entities: &mut Assembly,
         aux: &mut Singletons,
-        event: &EngineEvent,
-    ) -> DispatchEvents<EngineEvent> {
-        match *event {
-            EngineEvent::CursorPosition(position) => {
+        event: &Event,
+    ) -> DispatchEvents {
+        match event.downcast_ref::<CursorPosition>() {
+            Some(&CursorPosition(position)) => {
                 let menu_active = entities
                     .rs1::<UiState>()
                     .map(|(_, u)| u.menu_active)
@@ -168,7 +175,7 @@ impl SystemTrait<EngineEvent, Singletons> for TooltipController {
                     }
                 }
             }
-            _ => (),
+            None => (),
         }
         (None, None)
     }
@@ -0,0 +1,190 @@
+use std::time::Duration;
+use ecs::{Access, Assembly, DispatchEvents, Entity, LoopStageFlag, SystemTrait};
+use singletons::Singletons;
+use common::hierarchy::Hierarchy;
+use components::global_transform::GlobalTransform;
+use components::model::Model;
+
+/// The `TransformPropagator` computes the `GlobalTransform` of each entity from its local `Model`
+/// and those of its ancestors in the scene graph. Only the dirty subtrees of the scene graph are
+/// recomputed (see `Hierarchy::update`). Propagation also happens in the dynamic update, such
+/// that entities spawned or moved while the simulation is paused are rendered as well.
+#[derive(Default)]
+pub struct TransformPropagator {
+    /// Holds the change tick as of the previous update.
+    last_tick: u64,
+}
+
+impl TransformPropagator {
+    /// Creates a new `TransformPropagator`.
+    pub fn new() -> Self {
+        Default::default()
+    }
+    /// Copies the local `Model`s that changed since the previous update into the scene graph,
+    /// which marks the corresponding nodes dirty.
+    fn sync_models(&self, entities: &Assembly, aux: &mut Singletons) {
+        for entity in entities.changed::<Model>(self.last_tick) {
+            if let Ok(m) = entities.borrow_component::<Model>(&entity) {
+                if aux.scene_graph.has(&entity) {
+                    aux.scene_graph
+                        .set(&entity, m.clone())
+                        .unwrap_or_else(|_| unreachable!());
+                } else {
+                    aux.scene_graph.insert(entity, m.clone());
+                }
+            }
+        }
+    }
+    /// Recomputes the `GlobalTransform` of all entities whose `Model` changed, or which were
+    /// moved within the scene graph, along with their descendants. Once a `Model` is removed,
+    /// the `GlobalTransform` is removed as well.
+    fn propagate(&mut self, entities: &mut Assembly, aux: &mut Singletons) {
+        self.sync_models(entities, aux);
+
+        let removed = entities
+            .removed::<Model>(self.last_tick)
+            .collect::<Vec<_>>();
+        for entity in &removed {
+            if !entities.has_component::<Model>(entity) {
+                entities.remove_component::<GlobalTransform>(entity).ok();
+            }
+        }
+
+        let updated = aux.scene_graph
+            .update(&|parent: &Model, local: &Model| parent * local)
+            .expect("Unable to update the scene graph");
+        for entity in updated {
+            if entities.has_component::<Model>(&entity) {
+                let global = aux.scene_graph
+                    .borrow_global(&entity)
+                    .map(|g| GlobalTransform(g.clone()))
+                    .unwrap_or_else(|_| unreachable!());
+                entities
+                    .add_component(&entity, global)
+                    .expect("Unable to record the global transform");
+            }
+        }
+
+        self.last_tick = entities.tick();
+    }
+}
+
+impl SystemTrait<Singletons> for TransformPropagator {
+    /// `TransformPropagator` has no requirements wrt. the `Assembly`.
+    fn verify_requirements(&self, _: &Assembly) -> bool {
+        true
+    }
+    /// `TransformPropagator` subscribes to the update and dynamic update calls.
+    fn get_loop_stage_filter(&self) -> LoopStageFlag {
+        LoopStageFlag::UPDATE | LoopStageFlag::DYNAMIC_UPDATE
+    }
+    /// `TransformPropagator` is labeled `"transform_propagator"`.
+    fn get_label(&self) -> Option<&'static str> {
+        Some("transform_propagator")
+    }
+    /// `TransformPropagator` updates after the systems that move entities.
+    fn get_after(&self) -> &'static [&'static str] {
+        &["debug_mover"]
+    }
+    /// `TransformPropagator` reads the `Model`s, writes the `GlobalTransform`s and owns the scene
+    /// graph.
+    fn get_access(&self) -> Access {
+        Access::new()
+            .read::<Model>()
+            .write::<GlobalTransform>()
+            .aux::<Hierarchy<Entity, Model>>()
+    }
+    /// `TransformPropagator` may be called on another thread.
+    fn as_send(&mut self) -> Option<&mut (dyn SystemTrait<Singletons> + Send)> {
+        Some(self)
+    }
+    /// Propagates the transforms after the systems that move entities.
+    fn update(
+        &mut self,
+        entities: &mut Assembly,
+        aux: &mut Singletons,
+        _: &Duration,
+        _: &Duration,
+    ) -> DispatchEvents {
+        self.propagate(entities, aux);
+        (None, None)
+    }
+    /// Propagates the transforms of entities that were spawned or moved since the previous
+    /// update, e.g. while the simulation is paused.
+    fn dynamic_update(
+        &mut self,
+        entities: &mut Assembly,
+        aux: &mut Singletons,
+        _: &Duration,
+        _: &Duration,
+    ) -> DispatchEvents {
+        self.propagate(entities, aux);
+        (None, None)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use nalgebra::Vector3;
+    use orchestrator::Orchestrator;
+    use orchestrator::test::{model, orchestrator};
+
+    fn global_x(o: &Orchestrator, entity: &Entity) -> f32 {
+        o.world
+            .borrow_component::<GlobalTransform>(entity)
+            .map(|g| g.0.translation().x)
+            .unwrap()
+    }
+
+    #[test]
+    fn propagate_transforms() {
+        let mut o = orchestrator();
+        let mut entities = None;
+        o.initialize(|o| {
+            let p = o.world.create_entity();
+            o.world.add_component(&p, model(1.0)).unwrap();
+            let c = o.world.create_entity();
+            o.world.add_component(&c, model(2.0)).unwrap();
+            o.add_system(TransformPropagator::new()).unwrap();
+            entities = Some((p, c));
+        });
+        let (p, c) = entities.unwrap();
+
+        o.step(1);
+        assert_eq!(global_x(&o, &c), 2.0);
+
+        o.world.aux.scene_graph.reparent(&c, &p).unwrap();
+        o.step(1);
+        assert!((global_x(&o, &c) - 3.0).abs() < 1.0e-6);
+        assert_eq!(o.world.borrow_component::<Model>(&c).unwrap().translation().x, 2.0);
+
+        o.world
+            .borrow_component_mut::<Model>(&p)
+            .unwrap()
+            .set_translation(Vector3::new(5.0, 0.0, 0.0));
+        o.step(1);
+        assert!((global_x(&o, &c) - 7.0).abs() < 1.0e-6);
+
+        o.world.remove_component::<Model>(&p).unwrap();
+        o.step(1);
+        assert!(!o.world.has_component::<GlobalTransform>(&p));
+        assert!((global_x(&o, &c) - 2.0).abs() < 1.0e-6);
+    }
+
+    #[test]
+    fn propagate_while_paused() {
+        let mut o = orchestrator();
+        o.initialize(|o| {
+            o.add_system(TransformPropagator::new()).unwrap();
+        });
+        o.step(1);
+
+        // Without any fixed update, as is the case while the simulation is paused.
+        let e = o.world.create_entity();
+        o.world.add_component(&e, model(4.0)).unwrap();
+        let t = o.game_time();
+        o.world.dynamic_update(&t, &Duration::from_millis(16));
+        assert_eq!(global_x(&o, &e), 4.0);
+    }
+}
@@ -1,15 +1,20 @@
 use std::f32;
 use std::io;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
-use nalgebra;
 use nalgebra::{Point3, Vector3};
-use engine::{BoundingVolume, Camera, Cursor, CursorController, DebugConsole, DebugMover,
-             DebugShell, DebugUi, Description, EventInterface, EventMonitor, FontGroup, Mesh,
-             Model, Orchestrator, Renderer, ShaderGroup, SpeechBubble, SpeechBubbleController,
-             TextureGroup, Tooltip, TooltipController, TooltipData, UiState, RenderMode};
-
-pub fn run(resource_path: &Path, debugging: bool) {
+use engine::{Camera, Cursor, CursorController, DebugConsole, DebugMover, DebugShell, DebugUi,
+             Description, EventInterface, EventMonitor, FontGroup, InputRecorder, InputReplay,
+             Orchestrator, PhysicsUpdater, PrefabSpawner, Renderer, ShaderGroup, SpeechBubble,
+             SpeechBubbleController, TextureGroup, Tooltip, TooltipController,
+             TransformPropagator, UiState};
+
+pub fn run(
+    resource_path: &Path,
+    debugging: bool,
+    record: Option<PathBuf>,
+    replay: Option<PathBuf>,
+) {
     // The following variables set up the state of the engine.
     let delta_time = Duration::from_millis(50);
     let max_frame_time = Duration::from_millis(250);
@@ -24,9 +29,20 @@ pub fn run(resource_path: &Path, debugging: bool) {
     let mut orchestrator = Orchestrator::new(resource_path, delta_time, max_frame_time, debugging);
     orchestrator.run(move |o| {
         // Create the renderer (and dependencies).
-        let event_interface = EventInterface::new();
+        let mut event_interface = EventInterface::new();
+        if let Some(ref path) = record {
+            let recorder = InputRecorder::create(path).expect("Unable to create the recording");
+            event_interface = event_interface.with_recorder(recorder);
+        }
+        if let Some(ref path) = replay {
+            let replay = InputReplay::open(path).expect("Unable to load the recording");
+            event_interface = event_interface.with_replay(replay);
+        }
         let renderer = Renderer::new(
-            &event_interface.events_loop,
+            event_interface
+                .events_loop
+                .as_ref()
+                .expect("The EventInterface has no window"),
             &title,
             &dimensions,
             vsync,
@@ -100,122 +116,8 @@ pub fn run(resource_path: &Path, debugging: bool) {
             o.world.add_component(&cursor, c).unwrap();
         }
 
-        // Assemble the first test entity.
-        {
-            let position = Vector3::new(0.0, 0.0, -10.0);
-            let axisangle = nalgebra::zero();
-            let scale = Vector3::new(1.0, 1.0, 1.0);
-            let vs = o.get_file("shaders", "test-vertex.glsl").unwrap();
-            let fs = o.get_file("shaders", "test-fragment.glsl").unwrap();
-            let shaders = ShaderGroup::new(&vs, &fs, None).unwrap();
-            let textures = TextureGroup::empty();
-
-            let test_entity_a = o.world.create_entity();
-            let d = Description::new("test-entity-a");
-            let tooltip = TooltipData::new("Hi, I'm a quad!");
-            let model = Model::new(position, axisangle, scale);
-            let mesh = Mesh::new_quad(&renderer.display).unwrap();
-            let material = o.world
-                .aux
-                .factory
-                .new_material(&renderer.display, &shaders, &textures)
-                .unwrap();
-            let bounding_volume = BoundingVolume::from_mesh_aabb(&mesh).unwrap();
-            let render_mode = RenderMode::World;
-
-            o.world
-                .aux
-                .scene_graph
-                .insert(test_entity_a.clone(), model.clone());
-
-            o.world.add_component(&test_entity_a, d).unwrap();
-            o.world.add_component(&test_entity_a, tooltip).unwrap();
-            o.world.add_component(&test_entity_a, model).unwrap();
-            o.world.add_component(&test_entity_a, mesh).unwrap();
-            o.world.add_component(&test_entity_a, material).unwrap();
-            o.world
-                .add_component(&test_entity_a, bounding_volume)
-                .unwrap();
-            o.world.add_component(&test_entity_a, render_mode).unwrap();
-        }
-
-        // Assemble the second test entity.
-        {
-            let position = Vector3::new(-2.0, 1.0, -7.0);
-            let axisangle = Vector3::new(0.0, f32::consts::PI / 4.0, 0.0);
-            let scale = Vector3::new(1.0, 1.0, 1.0);
-            let vs = o.get_file("shaders", "test-vertex.glsl").unwrap();
-            let fs = o.get_file("shaders", "test-fragment.glsl").unwrap();
-            let shaders = ShaderGroup::new(&vs, &fs, None).unwrap();
-            let textures = TextureGroup::empty();
-
-            let test_entity_b = o.world.create_entity();
-            let d = Description::new("test-entity-b");
-            let tooltip = TooltipData::new("Hi, I'm a cube!");
-            let model = Model::new(position, axisangle, scale);
-            let mesh = Mesh::new_cube(&renderer.display).unwrap();
-            let material = o.world
-                .aux
-                .factory
-                .new_material(&renderer.display, &shaders, &textures)
-                .unwrap();
-            let bounding_volume = BoundingVolume::from_mesh_aabb(&mesh).unwrap();
-            let render_mode = RenderMode::World;
-
-            o.world
-                .aux
-                .scene_graph
-                .insert(test_entity_b.clone(), model.clone());
-
-            o.world.add_component(&test_entity_b, d).unwrap();
-            o.world.add_component(&test_entity_b, tooltip).unwrap();
-            o.world.add_component(&test_entity_b, model).unwrap();
-            o.world.add_component(&test_entity_b, mesh).unwrap();
-            o.world.add_component(&test_entity_b, material).unwrap();
-            o.world
-                .add_component(&test_entity_b, bounding_volume)
-                .unwrap();
-            o.world.add_component(&test_entity_b, render_mode).unwrap();
-        }
-
-        // Assemble the third test entity.
-        {
-            let position = Vector3::new(1.0, -1.5, -5.0);
-            let axisangle = Vector3::new(1.0, 1.0, 1.0) * f32::consts::PI / 4.0;
-            let scale = Vector3::new(1.0, 1.0, 1.0);
-            let vs = o.get_file("shaders", "test-vertex.glsl").unwrap();
-            let fs = o.get_file("shaders", "test-fragment.glsl").unwrap();
-            let shaders = ShaderGroup::new(&vs, &fs, None).unwrap();
-            let textures = TextureGroup::empty();
-
-            let test_entity_c = o.world.create_entity();
-            let d = Description::new("test-entity-c");
-            let tooltip = TooltipData::new("Hi, I'm a moving cube!");
-            let model = Model::new(position, axisangle, scale);
-            let mesh = Mesh::new_cube(&renderer.display).unwrap();
-            let material = o.world
-                .aux
-                .factory
-                .new_material(&renderer.display, &shaders, &textures)
-                .unwrap();
-            let bounding_volume = BoundingVolume::from_mesh_aabb(&mesh).unwrap();
-            let render_mode = RenderMode::World;
-
-            o.world
-                .aux
-                .scene_graph
-                .insert(test_entity_c.clone(), model.clone());
-
-            o.world.add_component(&test_entity_c, d).unwrap();
-            o.world.add_component(&test_entity_c, tooltip).unwrap();
-            o.world.add_component(&test_entity_c, model).unwrap();
-            o.world.add_component(&test_entity_c, mesh).unwrap();
-            o.world.add_component(&test_entity_c, material).unwrap();
-            o.world
-                .add_component(&test_entity_c, bounding_volume)
-                .unwrap();
-            o.world.add_component(&test_entity_c, render_mode).unwrap();
-        }
+        // Load the test entities from the scene file.
+        o.load_scene(&renderer.display, "test.json").unwrap();
 
         // Add systems to the world.
         if o.debug {
@@ -225,11 +127,15 @@ pub fn run(resource_path: &Path, debugging: bool) {
             o.add_system(DebugShell::new()).unwrap();
             o.add_system(DebugUi::new(&renderer.display)).unwrap();
         }
+        o.add_system(TransformPropagator::new()).unwrap();
+        o.add_system(PhysicsUpdater::new()).unwrap();
         o.add_system(CursorController::new()).unwrap();
         o.add_system(TooltipController::new(&renderer.display))
             .unwrap();
         o.add_system(SpeechBubbleController::new(&renderer.display))
             .unwrap();
+        o.add_system(PrefabSpawner::new(&renderer.display, resource_path))
+            .unwrap();
         o.add_system(renderer).unwrap();
         o.add_system(event_interface).unwrap();
     });
@@ -43,12 +43,29 @@ fn main() {
                 .conflicts_with("verbosity")
                 .help("Disables all output"),
         )
+        .arg(
+            Arg::with_name("record")
+                .long("record")
+                .takes_value(true)
+                .value_name("FILE")
+                .conflicts_with("replay")
+                .help("Records all input events to the specified file"),
+        )
+        .arg(
+            Arg::with_name("replay")
+                .long("replay")
+                .takes_value(true)
+                .value_name("FILE")
+                .help("Replays the input events recorded in the specified file"),
+        )
         .get_matches();
 
     // Obtain the command line arguments.
     let debugging = matches.is_present("debugging");
     let verbosity = matches.occurrences_of("verbosity");
     let quiet = matches.is_present("quiet");
+    let record = matches.value_of("record").map(PathBuf::from);
+    let replay = matches.value_of("replay").map(PathBuf::from);
     let resource_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
         .join("resources")
         .join("rootspace");
@@ -79,5 +96,5 @@ fn main() {
         .apply()
         .expect("Unable to configure the logger");
 
-    game::run(&resource_path, debugging);
+    game::run(&resource_path, debugging, record, replay);
 }